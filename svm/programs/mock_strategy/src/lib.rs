@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+declare_id!("2dXYVdAfJMeB5oU8xuCakFGmpSMB1fjppw9F6tBwoh2V");
+
+/// Minimal lending strategy implementing the minting program's strategy
+/// adapter layout. It holds no yield source of its own: tokens sent to the
+/// strategy vault beyond the deposited principal are paid out as yield on
+/// harvest, so tests simulate yield by minting into the vault directly.
+#[program]
+pub mod mock_strategy {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, depositor: Pubkey) -> Result<()> {
+        let strategy_state = &mut ctx.accounts.strategy_state;
+        strategy_state.depositor = depositor;
+        strategy_state.mint = ctx.accounts.mint.key();
+        strategy_state.vault = ctx.accounts.strategy_vault.key();
+        strategy_state.principal = 0;
+        strategy_state.bump = ctx.bumps.strategy_state;
+        Ok(())
+    }
+
+    pub fn strategy_deposit(ctx: Context<StrategyAction>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.strategy_vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        ctx.accounts.strategy_state.principal += amount;
+        Ok(())
+    }
+
+    pub fn strategy_withdraw(ctx: Context<StrategyAction>, amount: u64) -> Result<()> {
+        require!(
+            amount <= ctx.accounts.strategy_state.principal,
+            MockStrategyError::InsufficientPrincipal
+        );
+
+        pay_out(&ctx, amount)?;
+
+        ctx.accounts.strategy_state.principal -= amount;
+        Ok(())
+    }
+
+    /// Pays everything above the deposited principal back to the depositor
+    pub fn strategy_harvest(ctx: Context<StrategyAction>) -> Result<()> {
+        let yield_amount = ctx
+            .accounts
+            .strategy_vault
+            .amount
+            .saturating_sub(ctx.accounts.strategy_state.principal);
+
+        if yield_amount > 0 {
+            pay_out(&ctx, yield_amount)?;
+        }
+        Ok(())
+    }
+}
+
+fn pay_out(ctx: &Context<StrategyAction>, amount: u64) -> Result<()> {
+    let mint_key = ctx.accounts.mint.key();
+    let seeds = &[
+        b"strategy_state".as_ref(),
+        mint_key.as_ref(),
+        &[ctx.accounts.strategy_state.bump],
+    ];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.strategy_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.depositor_token_account.to_account_info(),
+                authority: ctx.accounts.strategy_state.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        ctx.accounts.mint.decimals,
+    )
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<StrategyState>(),
+        seeds = [b"strategy_state", mint.key().as_ref()],
+        bump
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = strategy_state,
+        seeds = [b"strategy_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub strategy_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Account order is the adapter layout the minting program builds its CPIs with
+#[derive(Accounts)]
+pub struct StrategyAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"strategy_state", mint.key().as_ref()],
+        bump = strategy_state.bump,
+        has_one = depositor,
+        constraint = strategy_state.vault == strategy_vault.key()
+    )]
+    pub strategy_state: Account<'info, StrategyState>,
+    #[account(mut)]
+    pub strategy_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub depositor: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[account]
+pub struct StrategyState {
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub principal: u64,
+    pub bump: u8,
+}
+
+#[error_code]
+pub enum MockStrategyError {
+    #[msg("Withdrawal exceeds deposited principal")]
+    InsufficientPrincipal,
+}
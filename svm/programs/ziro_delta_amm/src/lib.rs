@@ -1,5 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::{self, spl_token_2022};
+use anchor_spl::token_interface::{
+    self, Burn, CloseAccount, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use ziro_delta_epoch_manager::calculate_pfrt_payout_bps;
+use ziro_delta_oracle::OracleState;
+use ziro_delta_treasury::program::ZiroDeltaTreasury;
+use ziro_delta_treasury::VaultInfo;
 
 declare_id!("Bhvh31neQyERSX3UibBto7w2guD5cnSucC8XUMnaMCXG");
 
@@ -7,29 +17,83 @@ declare_id!("Bhvh31neQyERSX3UibBto7w2guD5cnSucC8XUMnaMCXG");
 pub mod ziro_delta_amm {
     use super::*;
 
-    /// Initialize the AMM with production-ready security parameters
-    pub fn initialize(
-        ctx: Context<Initialize>,
+    /// Initialize the pool factory and its governance
+    pub fn initialize_factory(ctx: Context<InitializeFactory>, governance: Pubkey) -> Result<()> {
+        let factory = &mut ctx.accounts.factory;
+        factory.governance = governance;
+        factory.pool_count = 0;
+        factory.bump = ctx.bumps.factory;
+        Ok(())
+    }
+
+    /// Create a pool for a PFRT/NFRT pair with production-ready security parameters
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        epoch_id: u64,
         trading_fee_bps: u16,
         max_trade_size: u64,
         daily_volume_limit: u64,
         max_slippage_bps: u16,
+        curve: CurveType,
     ) -> Result<()> {
-        require!(trading_fee_bps <= 100, AmmError::FeeTooHigh); // Max 1%
+        require!(trading_fee_bps <= MAX_TRADING_FEE_BPS, AmmError::FeeTooHigh);
         require!(max_slippage_bps <= 1000, AmmError::SlippageTooHigh); // Max 10%
+        if let CurveType::Logit { depth_bps } = curve {
+            require!(depth_bps > 0 && depth_bps <= MAX_CURVE_DEPTH_BPS, AmmError::InvalidCurve);
+        }
+
+        require!(
+            ctx.accounts.pfrt_mint.key() != ctx.accounts.nfrt_mint.key(),
+            AmmError::InvalidMintPair
+        );
 
         let state = &mut ctx.accounts.state;
+        state.epoch_id = epoch_id;
+        state.bump = ctx.bumps.state;
         state.authority = ctx.accounts.authority.key();
         state.pfrt_mint = ctx.accounts.pfrt_mint.key();
         state.nfrt_mint = ctx.accounts.nfrt_mint.key();
         state.pfrt_vault = ctx.accounts.pfrt_vault.key();
         state.nfrt_vault = ctx.accounts.nfrt_vault.key();
+        state.lp_mint = ctx.accounts.lp_mint.key();
+        state.curve = curve;
+        state.curve_liquidity = 0;
         
         // Security parameters
         state.trading_fee_bps = trading_fee_bps;
         state.max_trade_size = max_trade_size;
         state.daily_volume_limit = daily_volume_limit;
         state.max_slippage_bps = max_slippage_bps;
+        state.max_twap_deviation_bps = DEFAULT_MAX_TWAP_DEVIATION_BPS;
+        
+        // Static fees until governance configures the dynamic-fee mode
+        state.dynamic_fee_enabled = false;
+        state.min_fee_bps = trading_fee_bps;
+        state.max_fee_bps = trading_fee_bps;
+        state.volatility_fee_factor_bps = 0;
+        state.oracle_fee_factor_bps = 0;
+        state.fee_oracle = Pubkey::default();
+        
+        // Fee switch starts off; all trading fees go to LPs
+        state.protocol_fee_share_bps = 0;
+        state.protocol_fees_pfrt = 0;
+        state.protocol_fees_nfrt = 0;
+        state.cumulative_lp_fees_pfrt = 0;
+        state.cumulative_lp_fees_nfrt = 0;
+        state.cumulative_protocol_fees_pfrt = 0;
+        state.cumulative_protocol_fees_nfrt = 0;
+        
+        // Pool-wide impact cap over a one-slot window; volume uncapped
+        state.slot_window = DEFAULT_SLOT_WINDOW;
+        state.max_window_price_impact_bps = DEFAULT_MAX_WINDOW_PRICE_IMPACT_BPS;
+        state.max_window_volume = 0;
+        state.window_start_slot = 0;
+        state.window_price_movement_bps = 0;
+        state.window_volume = 0;
+        
+        // No per-wallet quotas until governance configures tiers
+        state.volume_quota_tiers = [0; VOLUME_QUOTA_TIERS];
+        state.open_long_term_orders = 0;
         
         // Initialize state
         state.trading_paused = false;
@@ -47,7 +111,33 @@ pub mod ziro_delta_amm {
         state.pfrt_twap = 0;
         state.nfrt_twap = 0;
 
-        msg!("ZiroDelta AMM initialized with production security features");
+        let observations = &mut ctx.accounts.observations.load_init()?;
+        observations.pool = state.key();
+        observations.index = 0;
+        observations.cardinality = 1;
+        observations.cardinality_next = DEFAULT_OBSERVATION_CARDINALITY;
+        observations.observations[0].timestamp = state.last_twap_update;
+
+        // List the pool so clients can enumerate every market
+        let factory = &mut ctx.accounts.factory;
+        let listing = &mut ctx.accounts.pool_listing;
+        listing.index = factory.pool_count;
+        listing.pool = state.key();
+        listing.pfrt_mint = state.pfrt_mint;
+        listing.nfrt_mint = state.nfrt_mint;
+        listing.epoch_id = epoch_id;
+        listing.bump = ctx.bumps.pool_listing;
+        factory.pool_count += 1;
+
+        emit!(PoolCreated {
+            pool: listing.pool,
+            pfrt_mint: listing.pfrt_mint,
+            nfrt_mint: listing.nfrt_mint,
+            epoch_id,
+            index: listing.index,
+        });
+
+        msg!("ZiroDelta AMM pool created with production security features");
         Ok(())
     }
 
@@ -62,62 +152,176 @@ pub mod ziro_delta_amm {
         require!(!state.trading_paused, AmmError::TradingPaused);
         require!(pfrt_amount > 0 && nfrt_amount > 0, AmmError::InvalidAmount);
 
-        let liquidity_to_mint = if state.total_liquidity == 0 {
-            // Initial liquidity - geometric mean
-            (pfrt_amount as u128 * nfrt_amount as u128).integer_sqrt() as u64
-        } else {
-            // Proportional liquidity
-            let pfrt_liquidity = (pfrt_amount as u128 * state.total_liquidity as u128) / state.pfrt_balance as u128;
-            let nfrt_liquidity = (nfrt_amount as u128 * state.total_liquidity as u128) / state.nfrt_balance as u128;
-            std::cmp::min(pfrt_liquidity, nfrt_liquidity) as u64
-        };
+        // Accumulate the pre-trade price before balances move
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        settle_long_term_orders(state, ctx.accounts.twamm.as_ref(), Clock::get()?.unix_timestamp)?;
+
+        // Token-2022 transfer fees are withheld from what the vaults receive
+        let pfrt_received = pfrt_amount - transfer_fee(&ctx.accounts.pfrt_mint, pfrt_amount)?;
+        let nfrt_received = nfrt_amount - transfer_fee(&ctx.accounts.nfrt_mint, nfrt_amount)?;
+
+        let previous_liquidity = state.total_liquidity;
+        let liquidity_to_mint = liquidity_for_deposit(state, pfrt_received, nfrt_received)?;
+        if state.total_liquidity == 0 {
+            state.total_liquidity = MINIMUM_LIQUIDITY;
+        }
 
         require!(liquidity_to_mint >= min_liquidity, AmmError::SlippageExceeded);
 
         // Transfer tokens to vaults
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.user_pfrt_account.to_account_info(),
+                    mint: ctx.accounts.pfrt_mint.to_account_info(),
                     to: ctx.accounts.pfrt_vault.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
             pfrt_amount,
+            ctx.accounts.pfrt_mint.decimals,
         )?;
 
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.user_nfrt_account.to_account_info(),
+                    mint: ctx.accounts.nfrt_mint.to_account_info(),
                     to: ctx.accounts.nfrt_vault.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
             nfrt_amount,
+            ctx.accounts.nfrt_mint.decimals,
+        )?;
+
+        // Mint LP tokens to the provider
+        let state_key = state.key();
+        let seeds = &[
+            b"authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.bumps.authority],
+        ];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    to: ctx.accounts.user_lp_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            liquidity_to_mint,
         )?;
 
         // Update state
-        state.pfrt_balance += pfrt_amount;
-        state.nfrt_balance += nfrt_amount;
+        state.pfrt_balance += pfrt_received;
+        state.nfrt_balance += nfrt_received;
         state.total_liquidity += liquidity_to_mint;
-
-        // Update TWAP
-        update_twap(state)?;
+        rescale_curve_liquidity(state, previous_liquidity)?;
 
         emit!(LiquidityAdded {
             user: ctx.accounts.user.key(),
+            pfrt_amount: pfrt_received,
+            nfrt_amount: nfrt_received,
+            liquidity_minted: liquidity_to_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Burn LP tokens for a pro rata share of both vaults
+    pub fn remove_liquidity(
+        ctx: Context<RemoveLiquidity>,
+        liquidity: u64,
+        min_pfrt_out: u64,
+        min_nfrt_out: u64,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        require!(liquidity > 0, AmmError::InvalidAmount);
+        require!(liquidity < state.total_liquidity, AmmError::InsufficientLiquidity);
+
+        // Accumulate the pre-trade price before balances move
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        settle_long_term_orders(state, ctx.accounts.twamm.as_ref(), Clock::get()?.unix_timestamp)?;
+
+        let (pfrt_amount, nfrt_amount) = withdrawal_amounts(state, liquidity)?;
+
+        // Slippage bounds apply to what the provider receives after transfer fees
+        let pfrt_received = pfrt_amount - transfer_fee(&ctx.accounts.pfrt_mint, pfrt_amount)?;
+        let nfrt_received = nfrt_amount - transfer_fee(&ctx.accounts.nfrt_mint, nfrt_amount)?;
+        require!(pfrt_received >= min_pfrt_out, AmmError::SlippageExceeded);
+        require!(nfrt_received >= min_nfrt_out, AmmError::SlippageExceeded);
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    from: ctx.accounts.user_lp_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            liquidity,
+        )?;
+
+        let state_key = state.key();
+        let seeds = &[
+            b"authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.bumps.authority],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.pfrt_vault.to_account_info(),
+                    mint: ctx.accounts.pfrt_mint.to_account_info(),
+                    to: ctx.accounts.user_pfrt_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
             pfrt_amount,
+            ctx.accounts.pfrt_mint.decimals,
+        )?;
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.nfrt_vault.to_account_info(),
+                    mint: ctx.accounts.nfrt_mint.to_account_info(),
+                    to: ctx.accounts.user_nfrt_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
             nfrt_amount,
-            liquidity_minted: liquidity_to_mint,
+            ctx.accounts.nfrt_mint.decimals,
+        )?;
+
+        // Update state
+        state.pfrt_balance -= pfrt_amount;
+        state.nfrt_balance -= nfrt_amount;
+        let previous_liquidity = state.total_liquidity;
+        state.total_liquidity -= liquidity;
+        rescale_curve_liquidity(state, previous_liquidity)?;
+
+        emit!(LiquidityRemoved {
+            user: ctx.accounts.user.key(),
+            pfrt_amount: pfrt_received,
+            nfrt_amount: nfrt_received,
+            liquidity_burned: liquidity,
         });
 
         Ok(())
     }
 
-    /// Swap tokens with flash loan protection and comprehensive security
+    /// Swap tokens under the pool's slot-window caps and price bounds
     pub fn swap(
         ctx: Context<Swap>,
         amount_in: u64,
@@ -125,6 +329,7 @@ pub mod ziro_delta_amm {
         is_pfrt_to_nfrt: bool,
     ) -> Result<()> {
         let state = &mut ctx.accounts.state;
+        let state_key = state.key();
         let clock = Clock::get()?;
 
         // Security checks
@@ -132,387 +337,2891 @@ pub mod ziro_delta_amm {
         require!(amount_in > 0, AmmError::InvalidAmount);
         require!(amount_in <= state.max_trade_size, AmmError::TradeSizeTooLarge);
 
-        // Flash loan protection - check last transaction slot
-        let last_slot = ctx.accounts.user_trade_state.last_trade_slot;
         let current_slot = clock.slot;
-        require!(last_slot != current_slot, AmmError::FlashLoanDetected);
+        let exempt = ctx.accounts.market_maker.is_some();
 
         // Daily volume check
         check_daily_volume_limit(state, amount_in, clock.unix_timestamp)?;
+        check_user_volume_quota(
+            state,
+            ctx.accounts.user_trade_state.as_deref_mut(),
+            ctx.accounts.market_maker.as_deref(),
+            amount_in,
+            clock.unix_timestamp,
+        )?;
+
+        // Accumulate the pre-trade price before balances move
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        settle_long_term_orders(state, ctx.accounts.twamm.as_ref(), clock.unix_timestamp)?;
+
+        // Token-2022 transfer fees are withheld on both legs, so price the
+        // swap on what the vault receives and check what the user receives
+        let (mint_in, mint_out) = if is_pfrt_to_nfrt {
+            (&ctx.accounts.pfrt_mint, &ctx.accounts.nfrt_mint)
+        } else {
+            (&ctx.accounts.nfrt_mint, &ctx.accounts.pfrt_mint)
+        };
+        let amount_in_received = amount_in - transfer_fee(mint_in, amount_in)?;
 
         // Calculate swap with TWAP protection
+        let fee_bps = effective_fee_bps(state, ctx.accounts.fee_oracle.as_deref().map(|o| &**o))?;
         let (amount_out, fee) = calculate_swap_amount(
             state,
+            amount_in_received,
+            fee_bps,
+            is_pfrt_to_nfrt,
+        )?;
+
+        // Price impact and TWAP deviation bounds
+        enforce_price_bounds(
+            state,
+            state_key,
+            ctx.accounts.user.key(),
+            amount_in_received,
+            fee,
+            amount_out,
+            is_pfrt_to_nfrt,
+        )?;
+        enforce_slot_window(
+            state,
+            state_key,
+            ctx.accounts.user.key(),
+            amount_in_received,
+            amount_out,
+            is_pfrt_to_nfrt,
+            current_slot,
+            exempt,
+        )?;
+
+        let amount_out_received = amount_out - transfer_fee(mint_out, amount_out)?;
+        require!(amount_out_received >= min_amount_out, AmmError::SlippageExceeded);
+
+        let protocol_fee = ctx.accounts.execute_swap(
+            ctx.bumps.authority,
             amount_in,
+            amount_in_received,
+            fee,
+            amount_out,
             is_pfrt_to_nfrt,
+            current_slot,
         )?;
 
-        require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+        emit!(SwapEvent {
+            user: ctx.accounts.user.key(),
+            amount_in,
+            amount_out: amount_out_received,
+            fee,
+            fee_bps,
+            protocol_fee,
+            is_pfrt_to_nfrt,
+        });
 
-        // Execute the swap
-        if is_pfrt_to_nfrt {
-            // Transfer PFRT from user to vault
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.user_pfrt_account.to_account_info(),
-                        to: ctx.accounts.pfrt_vault.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                amount_in,
-            )?;
+        Ok(())
+    }
 
-            // Transfer NFRT from vault to user
-            let seeds = &[
-                b"authority".as_ref(),
-                state.pfrt_mint.as_ref(),
-                &[ctx.bumps.authority],
-            ];
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.nfrt_vault.to_account_info(),
-                        to: ctx.accounts.user_nfrt_account.to_account_info(),
-                        authority: ctx.accounts.authority.to_account_info(),
-                    },
-                    &[&seeds[..]],
-                ),
-                amount_out,
-            )?;
+    /// Swap for an exact output amount, paying at most `max_amount_in`
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        amount_out: u64,
+        max_amount_in: u64,
+        is_pfrt_to_nfrt: bool,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let state_key = state.key();
+        let clock = Clock::get()?;
 
-            state.pfrt_balance += amount_in;
-            state.nfrt_balance -= amount_out;
+        // Security checks
+        require!(!state.trading_paused, AmmError::TradingPaused);
+        require!(amount_out > 0, AmmError::InvalidAmount);
+
+        let current_slot = clock.slot;
+        let exempt = ctx.accounts.market_maker.is_some();
+
+        // Accumulate the pre-trade price before balances move
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        settle_long_term_orders(state, ctx.accounts.twamm.as_ref(), clock.unix_timestamp)?;
+
+        // Gross both legs up for Token-2022 transfer fees so the user
+        // receives exactly amount_out
+        let (mint_in, mint_out) = if is_pfrt_to_nfrt {
+            (&ctx.accounts.pfrt_mint, &ctx.accounts.nfrt_mint)
         } else {
-            // Transfer NFRT from user to vault
-            token::transfer(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.user_nfrt_account.to_account_info(),
-                        to: ctx.accounts.nfrt_vault.to_account_info(),
-                        authority: ctx.accounts.user.to_account_info(),
-                    },
-                ),
-                amount_in,
-            )?;
+            (&ctx.accounts.nfrt_mint, &ctx.accounts.pfrt_mint)
+        };
+        let amount_out_sent = amount_out
+            .checked_add(inverse_transfer_fee(mint_out, amount_out)?)
+            .ok_or(AmmError::InvalidAmount)?;
 
-            // Transfer PFRT from vault to user
-            let seeds = &[
-                b"authority".as_ref(),
-                state.pfrt_mint.as_ref(),
-                &[ctx.bumps.authority],
-            ];
-            token::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    Transfer {
-                        from: ctx.accounts.pfrt_vault.to_account_info(),
-                        to: ctx.accounts.user_pfrt_account.to_account_info(),
-                        authority: ctx.accounts.authority.to_account_info(),
-                    },
-                    &[&seeds[..]],
-                ),
-                amount_out,
-            )?;
+        let fee_bps = effective_fee_bps(state, ctx.accounts.fee_oracle.as_deref().map(|o| &**o))?;
+        let (amount_in_received, fee) = calculate_swap_amount_in(
+            state,
+            amount_out_sent,
+            fee_bps,
+            is_pfrt_to_nfrt,
+        )?;
+        let amount_in = amount_in_received
+            .checked_add(inverse_transfer_fee(mint_in, amount_in_received)?)
+            .ok_or(AmmError::InvalidAmount)?;
 
-            state.nfrt_balance += amount_in;
-            state.pfrt_balance -= amount_out;
-        }
+        require!(amount_in <= max_amount_in, AmmError::SlippageExceeded);
+        require!(amount_in <= state.max_trade_size, AmmError::TradeSizeTooLarge);
 
-        // Update trade tracking
-        let user_trade_state = &mut ctx.accounts.user_trade_state;
-        user_trade_state.last_trade_slot = current_slot;
-        
-        // Update daily volume
-        state.daily_volume += amount_in;
+        // Daily volume check
+        check_daily_volume_limit(state, amount_in, clock.unix_timestamp)?;
+        check_user_volume_quota(
+            state,
+            ctx.accounts.user_trade_state.as_deref_mut(),
+            ctx.accounts.market_maker.as_deref(),
+            amount_in,
+            clock.unix_timestamp,
+        )?;
+
+        // Price impact and TWAP deviation bounds
+        enforce_price_bounds(
+            state,
+            state_key,
+            ctx.accounts.user.key(),
+            amount_in_received,
+            fee,
+            amount_out_sent,
+            is_pfrt_to_nfrt,
+        )?;
+        enforce_slot_window(
+            state,
+            state_key,
+            ctx.accounts.user.key(),
+            amount_in_received,
+            amount_out_sent,
+            is_pfrt_to_nfrt,
+            current_slot,
+            exempt,
+        )?;
 
-        // Update TWAP
-        update_twap(state)?;
+        let protocol_fee = ctx.accounts.execute_swap(
+            ctx.bumps.authority,
+            amount_in,
+            amount_in_received,
+            fee,
+            amount_out_sent,
+            is_pfrt_to_nfrt,
+            current_slot,
+        )?;
 
         emit!(SwapEvent {
             user: ctx.accounts.user.key(),
             amount_in,
             amount_out,
             fee,
+            fee_bps,
+            protocol_fee,
             is_pfrt_to_nfrt,
         });
 
         Ok(())
     }
 
-    /// Emergency pause trading (admin only)
-    pub fn pause_trading(ctx: Context<AdminAction>) -> Result<()> {
-        let state = &mut ctx.accounts.state;
-        state.trading_paused = true;
-
-        emit!(TradingPaused {
-            authority: ctx.accounts.authority.key(),
-        });
+    /// Create the pool's long-term order book (permissionless)
+    pub fn initialize_twamm(ctx: Context<InitializeTwamm>) -> Result<()> {
+        let twamm = &mut ctx.accounts.twamm.load_init()?;
+        twamm.pool = ctx.accounts.state.key();
+        twamm.last_virtual_order_time = Clock::get()?.unix_timestamp;
 
         Ok(())
     }
 
-    /// Resume trading (admin only)
-    pub fn resume_trading(ctx: Context<AdminAction>) -> Result<()> {
+    /// Sell `amount` at a constant rate until the end of the
+    /// `expiry_intervals`-th order interval from now. Orders expire on hourly
+    /// interval boundaries, so one placed mid-interval sells for between
+    /// `expiry_intervals - 1` and `expiry_intervals` hours, and it can run at
+    /// most TWAMM_BUCKETS - 1 (119) intervals. The order streams into the
+    /// curve as pool interactions settle it, so it needn't respect
+    /// `max_trade_size`, but a full interval of every open stream must keep
+    /// the curve in range
+    pub fn place_long_term_order(
+        ctx: Context<PlaceLongTermOrder>,
+        order_id: u64,
+        amount: u64,
+        expiry_intervals: u64,
+        is_pfrt_to_nfrt: bool,
+    ) -> Result<()> {
         let state = &mut ctx.accounts.state;
-        state.trading_paused = false;
+        let now = Clock::get()?.unix_timestamp;
 
-        emit!(TradingResumed {
-            authority: ctx.accounts.authority.key(),
+        require!(!state.trading_paused, AmmError::TradingPaused);
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(
+            expiry_intervals > 0 && expiry_intervals < TWAMM_BUCKETS as u64,
+            AmmError::InvalidOrderDuration
+        );
+        require!(state.pfrt_balance > 0 && state.nfrt_balance > 0, AmmError::InsufficientLiquidity);
+
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        let twamm = &mut *ctx.accounts.twamm.load_mut()?;
+        execute_virtual_orders(state, twamm, now)?;
+        // A new stream would otherwise share in the backlog from before it was placed
+        require!(twamm.last_virtual_order_time == now, AmmError::TwammStalled);
+
+        let (user_from, vault_in, mint_in) = if is_pfrt_to_nfrt {
+            (&ctx.accounts.user_pfrt_account, &ctx.accounts.pfrt_vault, &ctx.accounts.pfrt_mint)
+        } else {
+            (&ctx.accounts.user_nfrt_account, &ctx.accounts.nfrt_vault, &ctx.accounts.nfrt_mint)
+        };
+
+        // Only what the vault receives is streamed
+        let amount_received = amount - transfer_fee(mint_in, amount)?;
+        let expiry = (now / TWAMM_ORDER_INTERVAL + expiry_intervals as i64) * TWAMM_ORDER_INTERVAL;
+        let sell_rate = amount_received as u128 * SELL_RATE_SCALE / (expiry - now) as u128;
+        require!(sell_rate > 0, AmmError::InvalidAmount);
+
+        let earnings_checkpoint = twamm.open_stream(expiry, sell_rate, is_pfrt_to_nfrt)?;
+        let interval = TWAMM_ORDER_INTERVAL as u128;
+        require!(
+            virtual_segment(
+                state,
+                (twamm.pfrt_sell_rate * interval / SELL_RATE_SCALE) as u64,
+                (twamm.nfrt_sell_rate * interval / SELL_RATE_SCALE) as u64,
+            )
+            .is_some(),
+            AmmError::LongTermOrderTooLarge
+        );
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: user_from.to_account_info(),
+                    mint: mint_in.to_account_info(),
+                    to: vault_in.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+            mint_in.decimals,
+        )?;
+
+        let order = &mut ctx.accounts.order;
+        order.pool = state.key();
+        order.owner = ctx.accounts.user.key();
+        order.order_id = order_id;
+        order.is_pfrt_to_nfrt = is_pfrt_to_nfrt;
+        order.sell_rate = sell_rate;
+        order.start_time = now;
+        order.expiry = expiry;
+        order.earnings_checkpoint = earnings_checkpoint;
+        order.bump = ctx.bumps.order;
+
+        state.open_long_term_orders += 1;
+
+        emit!(LongTermOrderPlaced {
+            pool: order.pool,
+            owner: order.owner,
+            order_id,
+            is_pfrt_to_nfrt,
+            amount: amount_received,
+            sell_rate,
+            expiry,
         });
 
         Ok(())
     }
 
-    /// Update trading parameters (admin only)
-    pub fn update_parameters(
-        ctx: Context<AdminAction>,
-        new_max_trade_size: Option<u64>,
-        new_daily_volume_limit: Option<u64>,
-        new_max_slippage_bps: Option<u16>,
-    ) -> Result<()> {
+    /// Withdraw a long-term order's proceeds so far (owner only)
+    pub fn withdraw_long_term_order(ctx: Context<WithdrawLongTermOrder>) -> Result<()> {
         let state = &mut ctx.accounts.state;
+        let now = Clock::get()?.unix_timestamp;
 
-        if let Some(max_trade_size) = new_max_trade_size {
-            state.max_trade_size = max_trade_size;
-        }
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        let twamm = &mut *ctx.accounts.twamm.load_mut()?;
+        execute_virtual_orders(state, twamm, now)?;
 
-        if let Some(daily_volume_limit) = new_daily_volume_limit {
-            state.daily_volume_limit = daily_volume_limit;
-        }
+        let order = &mut ctx.accounts.order;
+        let proceeds = twamm.claim_proceeds(order);
 
-        if let Some(max_slippage_bps) = new_max_slippage_bps {
-            require!(max_slippage_bps <= 1000, AmmError::SlippageTooHigh);
-            state.max_slippage_bps = max_slippage_bps;
-        }
+        let (vault_out, mint_out, owner_to) = if order.is_pfrt_to_nfrt {
+            (&ctx.accounts.nfrt_vault, &ctx.accounts.nfrt_mint, &ctx.accounts.owner_nfrt_account)
+        } else {
+            (&ctx.accounts.pfrt_vault, &ctx.accounts.pfrt_mint, &ctx.accounts.owner_pfrt_account)
+        };
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            vault_out,
+            mint_out,
+            owner_to,
+            &ctx.accounts.authority,
+            state.key(),
+            ctx.bumps.authority,
+            proceeds,
+        )?;
+
+        emit!(LongTermOrderWithdrawn {
+            pool: order.pool,
+            owner: order.owner,
+            order_id: order.order_id,
+            proceeds,
+        });
 
         Ok(())
     }
-}
-
-// Helper functions
-fn update_twap(state: &mut AmmState) -> Result<()> {
-    let clock = Clock::get()?;
-    let time_elapsed = clock.unix_timestamp - state.last_twap_update;
 
-    if time_elapsed > 0 && state.pfrt_balance > 0 && state.nfrt_balance > 0 {
-        // Calculate current prices (with precision)
-        let pfrt_price = (state.nfrt_balance as u128 * 1_000_000) / state.pfrt_balance as u128;
-        let nfrt_price = (state.pfrt_balance as u128 * 1_000_000) / state.nfrt_balance as u128;
-
-        // Update cumulative prices
-        state.pfrt_price_cumulative += pfrt_price * time_elapsed as u128;
-        state.nfrt_price_cumulative += nfrt_price * time_elapsed as u128;
+    /// Cancel a long-term order, paying out its proceeds and refunding what
+    /// is left unsold. Anyone may close an order once it has expired,
+    /// freeing its expiry slot; tokens and rent still go to the owner
+    pub fn cancel_long_term_order(ctx: Context<CancelLongTermOrder>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let now = Clock::get()?.unix_timestamp;
+        let order = &ctx.accounts.order;
+        require!(
+            ctx.accounts.user.key() == order.owner || now >= order.expiry,
+            AmmError::NotOrderOwner
+        );
+
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        let twamm = &mut *ctx.accounts.twamm.load_mut()?;
+        execute_virtual_orders(state, twamm, now)?;
+
+        let order = &mut ctx.accounts.order;
+        let proceeds = twamm.claim_proceeds(order);
+        let refund = twamm.close_stream(order);
+        state.open_long_term_orders -= 1;
+
+        let (vault_in, mint_in, owner_in, vault_out, mint_out, owner_out) = if order.is_pfrt_to_nfrt {
+            (&ctx.accounts.pfrt_vault, &ctx.accounts.pfrt_mint, &ctx.accounts.owner_pfrt_account, &ctx.accounts.nfrt_vault, &ctx.accounts.nfrt_mint, &ctx.accounts.owner_nfrt_account)
+        } else {
+            (&ctx.accounts.nfrt_vault, &ctx.accounts.nfrt_mint, &ctx.accounts.owner_nfrt_account, &ctx.accounts.pfrt_vault, &ctx.accounts.pfrt_mint, &ctx.accounts.owner_pfrt_account)
+        };
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            vault_out,
+            mint_out,
+            owner_out,
+            &ctx.accounts.authority,
+            state.key(),
+            ctx.bumps.authority,
+            proceeds,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            vault_in,
+            mint_in,
+            owner_in,
+            &ctx.accounts.authority,
+            state.key(),
+            ctx.bumps.authority,
+            refund,
+        )?;
 
-        // Update TWAP if window passed
-        if time_elapsed >= state.twap_window {
-            state.pfrt_twap = (state.pfrt_price_cumulative / time_elapsed as u128) as u64;
-            state.nfrt_twap = (state.nfrt_price_cumulative / time_elapsed as u128) as u64;
+        emit!(LongTermOrderCancelled {
+            pool: order.pool,
+            owner: order.owner,
+            order_id: order.order_id,
+            proceeds,
+            refund,
+        });
 
-            // Reset cumulative
-            state.pfrt_price_cumulative = 0;
-            state.nfrt_price_cumulative = 0;
-            state.last_twap_update = clock.unix_timestamp;
-        }
+        Ok(())
     }
 
-    Ok(())
-}
-
-fn calculate_swap_amount(
-    state: &AmmState,
-    amount_in: u64,
-    is_pfrt_to_nfrt: bool,
-) -> Result<(u64, u64)> {
-    let fee = (amount_in as u128 * state.trading_fee_bps as u128) / 10000;
-    let amount_in_after_fee = amount_in - fee as u64;
+    /// Escrow `amount` of `input_mint` as a resting limit order that keepers
+    /// may fill once the owner receives at least `limit_price` (output per
+    /// input, scaled by 1e6). An `expiry` of 0 never expires
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        order_id: u64,
+        amount: u64,
+        limit_price: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        let state = &ctx.accounts.state;
+        let now = Clock::get()?.unix_timestamp;
 
-    let amount_out = if is_pfrt_to_nfrt {
-        // PFRT -> NFRT
-        let new_pfrt_balance = state.pfrt_balance + amount_in_after_fee;
-        let new_nfrt_balance = (state.pfrt_balance as u128 * state.nfrt_balance as u128) / new_pfrt_balance as u128;
-        state.nfrt_balance - new_nfrt_balance as u64
-    } else {
-        // NFRT -> PFRT
-        let new_nfrt_balance = state.nfrt_balance + amount_in_after_fee;
-        let new_pfrt_balance = (state.pfrt_balance as u128 * state.nfrt_balance as u128) / new_nfrt_balance as u128;
-        state.pfrt_balance - new_pfrt_balance as u64
-    };
+        require!(!state.trading_paused, AmmError::TradingPaused);
+        require!(amount > 0 && limit_price > 0, AmmError::InvalidAmount);
+        require!(amount <= state.max_trade_size, AmmError::TradeSizeTooLarge);
+        require!(expiry == 0 || expiry > now, AmmError::InvalidExpiry);
 
-    // TWAP-based bounds check
-    if state.pfrt_twap > 0 && state.nfrt_twap > 0 {
-        let max_deviation = 5; // 5% max deviation from TWAP
-        // Add TWAP validation logic here
-    }
+        let input_mint = &ctx.accounts.input_mint;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_input_account.to_account_info(),
+                    mint: input_mint.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+            input_mint.decimals,
+        )?;
 
-    Ok((amount_out, fee as u64))
-}
+        let order = &mut ctx.accounts.order;
+        order.pool = state.key();
+        order.owner = ctx.accounts.user.key();
+        order.order_id = order_id;
+        order.input_mint = input_mint.key();
+        order.is_pfrt_to_nfrt = input_mint.key() == state.pfrt_mint;
+        order.amount = amount - transfer_fee(input_mint, amount)?;
+        order.limit_price = limit_price;
+        order.expiry = expiry;
+        order.bump = ctx.bumps.order;
+        order.escrow_bump = ctx.bumps.escrow;
+
+        emit!(LimitOrderPlaced {
+            pool: order.pool,
+            owner: order.owner,
+            order_id,
+            is_pfrt_to_nfrt: order.is_pfrt_to_nfrt,
+            amount: order.amount,
+            limit_price,
+            expiry,
+        });
 
-fn check_daily_volume_limit(
-    state: &mut AmmState,
-    trade_size: u64,
-    current_time: i64,
-) -> Result<()> {
-    // Reset daily volume if 24 hours passed
-    if current_time >= state.last_volume_reset + 86400 {
-        state.daily_volume = 0;
-        state.last_volume_reset = current_time;
+        Ok(())
     }
 
-    require!(
-        state.daily_volume + trade_size <= state.daily_volume_limit,
-        AmmError::DailyVolumeLimitExceeded
-    );
+    /// Fill a limit order in full through the curve once the owner's share
+    /// of the output meets its limit price (permissionless). The keeper
+    /// earns KEEPER_FEE_BPS of the output, and the order and its escrow are
+    /// closed to the owner
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        let state_key = state.key();
+        let clock = Clock::get()?;
+        let order = &ctx.accounts.order;
+        let is_pfrt_to_nfrt = order.is_pfrt_to_nfrt;
 
-    Ok(())
-}
+        require!(!state.trading_paused, AmmError::TradingPaused);
+        require!(
+            order.expiry == 0 || clock.unix_timestamp < order.expiry,
+            AmmError::LimitOrderExpired
+        );
 
-// Account structs
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = user,
+        // Accumulate the pre-trade price before balances move
+        update_twap(state, &mut *ctx.accounts.observations.load_mut()?)?;
+        settle_long_term_orders(state, ctx.accounts.twamm.as_ref(), clock.unix_timestamp)?;
+
+        let (mint_in, vault_in, mint_out, vault_out) = if is_pfrt_to_nfrt {
+            (&ctx.accounts.pfrt_mint, &ctx.accounts.pfrt_vault, &ctx.accounts.nfrt_mint, &ctx.accounts.nfrt_vault)
+        } else {
+            (&ctx.accounts.nfrt_mint, &ctx.accounts.nfrt_vault, &ctx.accounts.pfrt_mint, &ctx.accounts.pfrt_vault)
+        };
+        let amount_in = ctx.accounts.escrow.amount;
+        require!(amount_in <= state.max_trade_size, AmmError::TradeSizeTooLarge);
+        let amount_in_received = amount_in - transfer_fee(mint_in, amount_in)?;
+
+        let fee_bps = effective_fee_bps(state, ctx.accounts.fee_oracle.as_deref().map(|o| &**o))?;
+        let (amount_out, fee) =
+            calculate_swap_amount(state, amount_in_received, fee_bps, is_pfrt_to_nfrt)?;
+        let keeper_fee = (amount_out as u128 * KEEPER_FEE_BPS as u128 / 10000) as u64;
+        let owner_amount = amount_out - keeper_fee;
+        // The limit applies to what reaches the owner after the output mint's transfer fee
+        let owner_received = owner_amount - transfer_fee(mint_out, owner_amount)?;
+        require!(
+            owner_received as u128 * LIMIT_PRICE_SCALE >= order.limit_price as u128 * order.amount as u128,
+            AmmError::LimitPriceNotReached
+        );
+
+        // Fills are trades like any other, charged to the owner's quota at
+        // their tier
+        check_daily_volume_limit(state, amount_in, clock.unix_timestamp)?;
+        check_user_volume_quota(
+            state,
+            ctx.accounts.owner_trade_state.as_deref_mut(),
+            None,
+            amount_in,
+            clock.unix_timestamp,
+        )?;
+        enforce_price_bounds(
+            state,
+            state_key,
+            order.owner,
+            amount_in_received,
+            fee,
+            amount_out,
+            is_pfrt_to_nfrt,
+        )?;
+        enforce_slot_window(
+            state,
+            state_key,
+            order.owner,
+            amount_in_received,
+            amount_out,
+            is_pfrt_to_nfrt,
+            clock.slot,
+            false,
+        )?;
+
+        let authority_bump = ctx.bumps.authority;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            mint_in,
+            vault_in,
+            &ctx.accounts.authority,
+            state_key,
+            authority_bump,
+            amount_in,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            vault_out,
+            mint_out,
+            &ctx.accounts.owner_output_account,
+            &ctx.accounts.authority,
+            state_key,
+            authority_bump,
+            owner_amount,
+        )?;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            vault_out,
+            mint_out,
+            &ctx.accounts.keeper_output_account,
+            &ctx.accounts.authority,
+            state_key,
+            authority_bump,
+            keeper_fee,
+        )?;
+        close_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.authority,
+            state_key,
+            authority_bump,
+        )?;
+
+        let protocol_fee =
+            settle_swap_reserves(state, amount_in_received, fee, amount_out, is_pfrt_to_nfrt)?;
+        state.daily_volume += amount_in;
+
+        emit!(LimitOrderFilled {
+            pool: state_key,
+            owner: order.owner,
+            order_id: order.order_id,
+            keeper: ctx.accounts.keeper.key(),
+            amount_in,
+            amount_out: owner_amount,
+            keeper_fee,
+            fee,
+            protocol_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a limit order, returning its escrow to the owner. Anyone may
+    /// clear an order once it has expired
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        let order = &ctx.accounts.order;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.user.key() == order.owner || (order.expiry != 0 && now >= order.expiry),
+            AmmError::NotOrderOwner
+        );
+
+        let state_key = ctx.accounts.state.key();
+        let refund = ctx.accounts.escrow.amount;
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            &ctx.accounts.input_mint,
+            &ctx.accounts.owner_input_account,
+            &ctx.accounts.authority,
+            state_key,
+            ctx.bumps.authority,
+            refund,
+        )?;
+        close_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.authority,
+            state_key,
+            ctx.bumps.authority,
+        )?;
+
+        emit!(LimitOrderCancelled {
+            pool: state_key,
+            owner: order.owner,
+            order_id: order.order_id,
+            refund,
+        });
+
+        Ok(())
+    }
+
+    /// Time-weighted average prices over the last `seconds_ago` seconds,
+    /// returned to the caller (or CPI caller) as return data
+    pub fn consult(ctx: Context<Consult>, seconds_ago: u32) -> Result<TwapQuote> {
+        let observations = ctx.accounts.observations.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        let (pfrt_twap, nfrt_twap) =
+            consult_twap(&ctx.accounts.state, &observations, now, seconds_ago)?;
+
+        Ok(TwapQuote {
+            pfrt_twap,
+            nfrt_twap,
+            seconds_ago,
+        })
+    }
+
+    /// Simulate `swap` without moving tokens; the quote is returned as return
+    /// data. Fees use the pool's TWAP as of its last update. Long-term orders
+    /// are settled up to now on a copy of the pool, so the TWAMM account is
+    /// required while any are open
+    pub fn quote_swap(
+        ctx: Context<Quote>,
+        amount_in: u64,
+        is_pfrt_to_nfrt: bool,
+    ) -> Result<SwapQuote> {
+        let state = &ctx.accounts.settled_state()?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        require!(state.pfrt_balance > 0 && state.nfrt_balance > 0, AmmError::InsufficientLiquidity);
+
+        let (mint_in, mint_out) = if is_pfrt_to_nfrt {
+            (&ctx.accounts.pfrt_mint, &ctx.accounts.nfrt_mint)
+        } else {
+            (&ctx.accounts.nfrt_mint, &ctx.accounts.pfrt_mint)
+        };
+        let amount_in_received = amount_in - transfer_fee(mint_in, amount_in)?;
+
+        let fee_bps = effective_fee_bps(state, ctx.accounts.fee_oracle.as_deref().map(|o| &**o))?;
+        let (amount_out, fee) = calculate_swap_amount(state, amount_in_received, fee_bps, is_pfrt_to_nfrt)?;
+        let price_impact_bps =
+            price_impact_bps(state, amount_in_received, fee, amount_out, is_pfrt_to_nfrt)?;
+        let protocol_fee = protocol_fee_for(state, fee)?;
+
+        let reserve_in = amount_in_received - protocol_fee;
+        let (pfrt_balance_after, nfrt_balance_after) = if is_pfrt_to_nfrt {
+            (state.pfrt_balance + reserve_in, state.nfrt_balance - amount_out)
+        } else {
+            (state.pfrt_balance - amount_out, state.nfrt_balance + reserve_in)
+        };
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out: amount_out - transfer_fee(mint_out, amount_out)?,
+            fee,
+            fee_bps,
+            protocol_fee,
+            price_impact_bps,
+            pfrt_balance_after,
+            nfrt_balance_after,
+        })
+    }
+
+    /// Simulate `add_liquidity`; the quote is returned as return data
+    pub fn quote_add_liquidity(
+        ctx: Context<Quote>,
+        pfrt_amount: u64,
+        nfrt_amount: u64,
+    ) -> Result<AddLiquidityQuote> {
+        let state = &ctx.accounts.settled_state()?;
+        require!(pfrt_amount > 0 && nfrt_amount > 0, AmmError::InvalidAmount);
+
+        let pfrt_received = pfrt_amount - transfer_fee(&ctx.accounts.pfrt_mint, pfrt_amount)?;
+        let nfrt_received = nfrt_amount - transfer_fee(&ctx.accounts.nfrt_mint, nfrt_amount)?;
+        let liquidity = liquidity_for_deposit(state, pfrt_received, nfrt_received)?;
+        let locked = if state.total_liquidity == 0 { MINIMUM_LIQUIDITY } else { 0 };
+
+        Ok(AddLiquidityQuote {
+            pfrt_amount: pfrt_received,
+            nfrt_amount: nfrt_received,
+            liquidity,
+            total_liquidity_after: state.total_liquidity + locked + liquidity,
+            pfrt_balance_after: state.pfrt_balance + pfrt_received,
+            nfrt_balance_after: state.nfrt_balance + nfrt_received,
+        })
+    }
+
+    /// Simulate `remove_liquidity`; the quote is returned as return data
+    pub fn quote_remove_liquidity(
+        ctx: Context<Quote>,
+        liquidity: u64,
+    ) -> Result<RemoveLiquidityQuote> {
+        let state = &ctx.accounts.settled_state()?;
+        require!(liquidity > 0, AmmError::InvalidAmount);
+        require!(liquidity < state.total_liquidity, AmmError::InsufficientLiquidity);
+
+        let (pfrt_amount, nfrt_amount) = withdrawal_amounts(state, liquidity)?;
+
+        Ok(RemoveLiquidityQuote {
+            liquidity,
+            pfrt_amount: pfrt_amount - transfer_fee(&ctx.accounts.pfrt_mint, pfrt_amount)?,
+            nfrt_amount: nfrt_amount - transfer_fee(&ctx.accounts.nfrt_mint, nfrt_amount)?,
+            total_liquidity_after: state.total_liquidity - liquidity,
+            pfrt_balance_after: state.pfrt_balance - pfrt_amount,
+            nfrt_balance_after: state.nfrt_balance - nfrt_amount,
+        })
+    }
+
+    /// Grow the number of observations the pool keeps (permissionless)
+    pub fn increase_observation_cardinality(
+        ctx: Context<IncreaseObservationCardinality>,
+        cardinality_next: u16,
+    ) -> Result<()> {
+        let observations = &mut ctx.accounts.observations.load_mut()?;
+        require!(
+            cardinality_next as usize <= OBSERVATION_CAPACITY,
+            AmmError::CardinalityTooHigh
+        );
+
+        // Like Uniswap v3, shrinking is a no-op; new slots are used once the ring wraps
+        if cardinality_next > observations.cardinality_next {
+            let previous = observations.cardinality_next;
+            observations.cardinality_next = cardinality_next;
+
+            emit!(ObservationCardinalityIncreased {
+                pool: ctx.accounts.state.key(),
+                previous,
+                next: cardinality_next,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Emergency pause trading (admin only)
+    pub fn pause_trading(ctx: Context<AdminAction>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.trading_paused = true;
+
+        emit!(TradingPaused {
+            authority: ctx.accounts.governance.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Resume trading (admin only)
+    pub fn resume_trading(ctx: Context<AdminAction>) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.trading_paused = false;
+
+        emit!(TradingResumed {
+            authority: ctx.accounts.governance.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Update a pool's trading parameters (admin only)
+    pub fn update_parameters(
+        ctx: Context<AdminAction>,
+        new_max_trade_size: Option<u64>,
+        new_daily_volume_limit: Option<u64>,
+        new_max_slippage_bps: Option<u16>,
+        new_max_twap_deviation_bps: Option<u16>,
+        new_protocol_fee_share_bps: Option<u16>,
+        new_trading_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+
+        if let Some(trading_fee_bps) = new_trading_fee_bps {
+            require!(trading_fee_bps <= MAX_TRADING_FEE_BPS, AmmError::FeeTooHigh);
+            state.trading_fee_bps = trading_fee_bps;
+        }
+
+        if let Some(max_trade_size) = new_max_trade_size {
+            state.max_trade_size = max_trade_size;
+        }
+
+        if let Some(daily_volume_limit) = new_daily_volume_limit {
+            state.daily_volume_limit = daily_volume_limit;
+        }
+
+        if let Some(max_slippage_bps) = new_max_slippage_bps {
+            require!(max_slippage_bps <= 1000, AmmError::SlippageTooHigh);
+            state.max_slippage_bps = max_slippage_bps;
+        }
+
+        if let Some(max_twap_deviation_bps) = new_max_twap_deviation_bps {
+            require!(
+                max_twap_deviation_bps > 0 && max_twap_deviation_bps <= MAX_TWAP_DEVIATION_BPS,
+                AmmError::DeviationBoundTooHigh
+            );
+            state.max_twap_deviation_bps = max_twap_deviation_bps;
+        }
+
+        if let Some(protocol_fee_share_bps) = new_protocol_fee_share_bps {
+            require!(
+                protocol_fee_share_bps <= MAX_PROTOCOL_FEE_SHARE_BPS,
+                AmmError::ProtocolFeeTooHigh
+            );
+            state.protocol_fee_share_bps = protocol_fee_share_bps;
+        }
+
+        Ok(())
+    }
+
+    /// Configure the dynamic-fee mode (admin only). Factors are the fee bps
+    /// added per 10000 bps of TWAP or oracle deviation; `fee_oracle` of
+    /// `Pubkey::default()` leaves the oracle term out
+    pub fn configure_dynamic_fee(
+        ctx: Context<AdminAction>,
+        enabled: bool,
+        min_fee_bps: u16,
+        max_fee_bps: u16,
+        volatility_fee_factor_bps: u16,
+        oracle_fee_factor_bps: u16,
+        fee_oracle: Pubkey,
+    ) -> Result<()> {
+        require!(min_fee_bps <= max_fee_bps, AmmError::InvalidFeeBounds);
+        require!(max_fee_bps <= MAX_DYNAMIC_FEE_BPS, AmmError::FeeTooHigh);
+
+        let state = &mut ctx.accounts.state;
+        state.dynamic_fee_enabled = enabled;
+        state.min_fee_bps = min_fee_bps;
+        state.max_fee_bps = max_fee_bps;
+        state.volatility_fee_factor_bps = volatility_fee_factor_bps;
+        state.oracle_fee_factor_bps = oracle_fee_factor_bps;
+        state.fee_oracle = fee_oracle;
+
+        emit!(DynamicFeeConfigured {
+            pool: state.key(),
+            enabled,
+            min_fee_bps,
+            max_fee_bps,
+            volatility_fee_factor_bps,
+            oracle_fee_factor_bps,
+            fee_oracle,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the pool-wide slot-window caps (admin only). Trades within
+    /// `slot_window` slots share one budget of spot price movement and
+    /// volume; `max_window_volume` of 0 leaves volume uncapped
+    pub fn configure_slot_limits(
+        ctx: Context<AdminAction>,
+        slot_window: u64,
+        max_window_price_impact_bps: u16,
+        max_window_volume: u64,
+    ) -> Result<()> {
+        require!(
+            slot_window > 0 && slot_window <= MAX_SLOT_WINDOW,
+            AmmError::InvalidSlotLimits
+        );
+        require!(
+            max_window_price_impact_bps > 0 && max_window_price_impact_bps <= 10000,
+            AmmError::InvalidSlotLimits
+        );
+
+        let state = &mut ctx.accounts.state;
+        state.slot_window = slot_window;
+        state.max_window_price_impact_bps = max_window_price_impact_bps;
+        state.max_window_volume = max_window_volume;
+
+        emit!(SlotLimitsConfigured {
+            pool: state.key(),
+            slot_window,
+            max_window_price_impact_bps,
+            max_window_volume,
+        });
+
+        Ok(())
+    }
+
+    /// Exempt a market maker's key from the slot-window caps and give it its
+    /// own daily volume quota, 0 for unlimited (admin only)
+    pub fn add_market_maker(
+        ctx: Context<AddMarketMaker>,
+        market_maker: Pubkey,
+        daily_volume_quota: u64,
+    ) -> Result<()> {
+        let entry = &mut ctx.accounts.market_maker_entry;
+        entry.pool = ctx.accounts.state.key();
+        entry.market_maker = market_maker;
+        entry.daily_volume_quota = daily_volume_quota;
+        entry.bump = ctx.bumps.market_maker_entry;
+
+        emit!(MarketMakerAdded {
+            pool: entry.pool,
+            market_maker,
+            daily_volume_quota,
+        });
+
+        Ok(())
+    }
+
+    /// Set the rolling daily volume quota for each wallet tier (admin only).
+    /// Every wallet starts in tier 0; a quota of 0 leaves the tier unlimited
+    pub fn configure_volume_quotas(
+        ctx: Context<AdminAction>,
+        volume_quota_tiers: [u64; VOLUME_QUOTA_TIERS],
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.volume_quota_tiers = volume_quota_tiers;
+
+        emit!(VolumeQuotasConfigured {
+            pool: state.key(),
+            volume_quota_tiers,
+        });
+
+        Ok(())
+    }
+
+    /// Move a wallet into a volume quota tier (admin only)
+    pub fn set_user_tier(ctx: Context<SetUserTier>, tier: u8) -> Result<()> {
+        require!((tier as usize) < VOLUME_QUOTA_TIERS, AmmError::InvalidQuotaTier);
+
+        ctx.accounts.user_trade_state.tier = tier;
+
+        emit!(UserTierSet {
+            pool: ctx.accounts.state.key(),
+            user: ctx.accounts.user.key(),
+            tier,
+        });
+
+        Ok(())
+    }
+
+    /// Drop a market maker from the allowlist, refunding the entry's rent
+    /// to governance (admin only)
+    pub fn remove_market_maker(ctx: Context<RemoveMarketMaker>) -> Result<()> {
+        emit!(MarketMakerRemoved {
+            pool: ctx.accounts.state.key(),
+            market_maker: ctx.accounts.market_maker_entry.market_maker,
+        });
+
+        Ok(())
+    }
+
+    /// Sweep accrued protocol fees into the protocol treasury (permissionless)
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        let pfrt_amount = ctx.accounts.state.protocol_fees_pfrt;
+        let nfrt_amount = ctx.accounts.state.protocol_fees_nfrt;
+        require!(pfrt_amount > 0 || nfrt_amount > 0, AmmError::NoProtocolFees);
+
+        let state_key = ctx.accounts.state.key();
+        let seeds = &[
+            b"authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.bumps.authority],
+        ];
+
+        if pfrt_amount > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.pfrt_treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.pfrt_treasury_vault.to_account_info(),
+                        from: ctx.accounts.pfrt_vault.to_account_info(),
+                        mint: ctx.accounts.pfrt_mint.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                pfrt_amount,
+            )?;
+        }
+
+        if nfrt_amount > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.nfrt_treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.nfrt_treasury_vault.to_account_info(),
+                        from: ctx.accounts.nfrt_vault.to_account_info(),
+                        mint: ctx.accounts.nfrt_mint.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                nfrt_amount,
+            )?;
+        }
+
+        let state = &mut ctx.accounts.state;
+        state.protocol_fees_pfrt = 0;
+        state.protocol_fees_nfrt = 0;
+
+        emit!(ProtocolFeesCollected {
+            pool: state_key,
+            pfrt_amount,
+            nfrt_amount,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper functions
+fn update_twap(state: &mut AmmState, observations: &mut ObservationBuffer) -> Result<()> {
+    let clock = Clock::get()?;
+    let time_elapsed = clock.unix_timestamp - state.last_twap_update;
+    if time_elapsed <= 0 {
+        return Ok(());
+    }
+
+    if state.pfrt_balance == 0 || state.nfrt_balance == 0 {
+        // Nothing to observe while the pool is empty; keep the latest sample current
+        state.last_twap_update = clock.unix_timestamp;
+        observations.observations[observations.index as usize].timestamp = clock.unix_timestamp;
+        return Ok(());
+    }
+
+    // Cumulative prices only ever grow and are compared by difference, so wrap like Uniswap
+    let (pfrt_price, nfrt_price) = spot_prices(state, state.pfrt_balance, state.nfrt_balance)?;
+    state.pfrt_price_cumulative = state
+        .pfrt_price_cumulative
+        .wrapping_add(pfrt_price.wrapping_mul(time_elapsed as u128));
+    state.nfrt_price_cumulative = state
+        .nfrt_price_cumulative
+        .wrapping_add(nfrt_price.wrapping_mul(time_elapsed as u128));
+    state.last_twap_update = clock.unix_timestamp;
+
+    observations.write(
+        clock.unix_timestamp,
+        state.pfrt_price_cumulative,
+        state.nfrt_price_cumulative,
+    );
+
+    // Refresh the stored TWAP over the configured window, or as much history as the ring holds
+    let oldest = observations.oldest().timestamp;
+    let seconds_ago = std::cmp::min(state.twap_window, clock.unix_timestamp - oldest);
+    if seconds_ago > 0 {
+        let (pfrt_twap, nfrt_twap) =
+            consult_twap(state, observations, clock.unix_timestamp, seconds_ago as u32)?;
+        state.pfrt_twap = pfrt_twap;
+        state.nfrt_twap = nfrt_twap;
+    }
+
+    Ok(())
+}
+
+// Spot prices of each side in terms of the other, scaled by 1e6
+fn spot_prices(state: &AmmState, pfrt_balance: u64, nfrt_balance: u64) -> Result<(u128, u128)> {
+    match state.curve {
+        CurveType::ConstantProduct => Ok((
+            (nfrt_balance as u128 * 1_000_000) / pfrt_balance as u128,
+            (pfrt_balance as u128 * 1_000_000) / nfrt_balance as u128,
+        )),
+        CurveType::Logit { .. } => {
+            // PFRT/NFRT price ratio is e^((nfrt - pfrt) / b)
+            let logit = logit_fp(pfrt_balance, nfrt_balance, state.curve_liquidity)?;
+            let scale = FP_ONE / 1_000_000;
+            Ok((exp_fp(logit)? / scale, exp_fp(-logit)? / scale))
+        }
+    }
+}
+
+/// TWAP of both prices over the `seconds_ago` seconds before `now`
+pub fn consult_twap(
+    state: &AmmState,
+    observations: &ObservationBuffer,
+    now: i64,
+    seconds_ago: u32,
+) -> Result<(u64, u64)> {
+    require!(seconds_ago > 0, AmmError::InvalidAmount);
+
+    let (pfrt_now, nfrt_now) = observations.observe(state, now)?;
+    let (pfrt_then, nfrt_then) = observations.observe(state, now - seconds_ago as i64)?;
+
+    Ok((
+        (pfrt_now.wrapping_sub(pfrt_then) / seconds_ago as u128) as u64,
+        (nfrt_now.wrapping_sub(nfrt_then) / seconds_ago as u128) as u64,
+    ))
+}
+
+// Amount withheld by a Token-2022 transfer-fee extension, zero for other mints
+fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| error!(AmmError::InvalidAmount)),
+        Err(_) => Ok(0),
+    }
+}
+
+// Fee to add to `amount` so that `amount` arrives after a Token-2022 transfer fee
+fn inverse_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| error!(AmmError::InvalidAmount)),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Trading fee for the next swap. In dynamic mode the base fee rises with the
+/// spot price's deviation from TWAP and from the funding-rate oracle's fair
+/// value, clamped to the governance min/max
+pub fn effective_fee_bps(state: &AmmState, fee_oracle: Option<&OracleState>) -> Result<u16> {
+    if !state.dynamic_fee_enabled {
+        return Ok(state.trading_fee_bps);
+    }
+
+    let mut fee_bps = state.trading_fee_bps as u64;
+    if state.pfrt_balance > 0 && state.nfrt_balance > 0 {
+        let (pfrt_price, nfrt_price) = spot_prices(state, state.pfrt_balance, state.nfrt_balance)?;
+
+        // Volatility: how far the pool has recently moved away from its TWAP
+        if state.pfrt_twap > 0 && state.nfrt_twap > 0 {
+            let volatility_bps = std::cmp::max(
+                deviation_bps(pfrt_price, state.pfrt_twap),
+                deviation_bps(nfrt_price, state.nfrt_twap),
+            );
+            fee_bps = fee_bps.saturating_add(
+                volatility_bps.saturating_mul(state.volatility_fee_factor_bps as u64) / 10000,
+            );
+        }
+
+        // Oracle divergence: the settlement payout implied by the current
+        // funding rate, as a PFRT/NFRT price ratio
+        if state.fee_oracle != Pubkey::default() {
+            let oracle = fee_oracle.ok_or(AmmError::FeeOracleRequired)?;
+            let payout_bps = calculate_pfrt_payout_bps(oracle.effective_funding_rate() as i64).clamp(1, 9999) as u128;
+            let fair_price = payout_bps * 1_000_000 / (10000 - payout_bps);
+            let oracle_deviation_bps = deviation_bps(pfrt_price, fair_price as u64);
+            fee_bps = fee_bps.saturating_add(
+                oracle_deviation_bps.saturating_mul(state.oracle_fee_factor_bps as u64) / 10000,
+            );
+        }
+    }
+
+    Ok(fee_bps.clamp(state.min_fee_bps as u64, state.max_fee_bps as u64) as u16)
+}
+
+// Output and trading fee for `amount_in` at `fee_bps`, before any transfer fee on either leg
+pub fn calculate_swap_amount(
+    state: &AmmState,
+    amount_in: u64,
+    fee_bps: u16,
+    is_pfrt_to_nfrt: bool,
+) -> Result<(u64, u64)> {
+    let fee = (amount_in as u128 * fee_bps as u128) / 10000;
+    let amount_in_after_fee = amount_in - fee as u64;
+
+    let amount_out = match state.curve {
+        CurveType::ConstantProduct => {
+            if is_pfrt_to_nfrt {
+                // PFRT -> NFRT
+                let new_pfrt_balance = state.pfrt_balance + amount_in_after_fee;
+                let new_nfrt_balance = (state.pfrt_balance as u128 * state.nfrt_balance as u128) / new_pfrt_balance as u128;
+                state.nfrt_balance - new_nfrt_balance as u64
+            } else {
+                // NFRT -> PFRT
+                let new_nfrt_balance = state.nfrt_balance + amount_in_after_fee;
+                let new_pfrt_balance = (state.pfrt_balance as u128 * state.nfrt_balance as u128) / new_nfrt_balance as u128;
+                state.pfrt_balance - new_pfrt_balance as u64
+            }
+        }
+        CurveType::Logit { .. } => {
+            let (reserve_in, reserve_out) = if is_pfrt_to_nfrt {
+                (state.pfrt_balance, state.nfrt_balance)
+            } else {
+                (state.nfrt_balance, state.pfrt_balance)
+            };
+            logit_amount_out(reserve_in, reserve_out, amount_in_after_fee, state.curve_liquidity)?
+        }
+    };
+
+    Ok((amount_out, fee as u64))
+}
+
+// Input (including the trading fee) needed for `amount_out`, rounded in the pool's favour
+fn calculate_swap_amount_in(
+    state: &AmmState,
+    amount_out: u64,
+    fee_bps: u16,
+    is_pfrt_to_nfrt: bool,
+) -> Result<(u64, u64)> {
+    let (reserve_in, reserve_out) = if is_pfrt_to_nfrt {
+        (state.pfrt_balance, state.nfrt_balance)
+    } else {
+        (state.nfrt_balance, state.pfrt_balance)
+    };
+    require!(amount_out < reserve_out, AmmError::InsufficientLiquidity);
+
+    let amount_in_after_fee = match state.curve {
+        CurveType::ConstantProduct => {
+            let numerator = reserve_in as u128 * amount_out as u128;
+            let denominator = (reserve_out - amount_out) as u128;
+            u64::try_from(numerator.div_ceil(denominator))
+                .map_err(|_| AmmError::InsufficientLiquidity)?
+        }
+        CurveType::Logit { .. } => {
+            logit_amount_in(reserve_in, reserve_out, amount_out, state.curve_liquidity)?
+        }
+    };
+
+    let fee_denominator = 10000 - fee_bps as u128;
+    let mut amount_in = u64::try_from((amount_in_after_fee as u128 * 10000).div_ceil(fee_denominator))
+        .map_err(|_| AmmError::InsufficientLiquidity)?;
+
+    // Fixed-point curves can round against the pool; step the input up until
+    // the forward quote covers amount_out, so exact-out never beats exact-in
+    let mut step = amount_in / INVERSE_STEP_DIVISOR + 1;
+    for _ in 0..MAX_INVERSE_STEPS {
+        let (quoted_out, fee) = calculate_swap_amount(state, amount_in, fee_bps, is_pfrt_to_nfrt)?;
+        if quoted_out >= amount_out {
+            return Ok((amount_in, fee));
+        }
+        amount_in = amount_in.checked_add(step).ok_or(AmmError::InsufficientLiquidity)?;
+        step = step.saturating_mul(4);
+    }
+
+    err!(AmmError::CurveOutOfRange)
+}
+
+// Reject trades whose price impact exceeds max_slippage_bps or that would push
+// the spot price too far from the stored TWAP. The rejection event is logged
+// before the error so monitoring can see what was attempted.
+fn enforce_price_bounds(
+    state: &AmmState,
+    pool: Pubkey,
+    user: Pubkey,
+    amount_in: u64,
+    fee: u64,
+    amount_out: u64,
+    is_pfrt_to_nfrt: bool,
+) -> Result<()> {
+    let (reserve_in, reserve_out) = if is_pfrt_to_nfrt {
+        (state.pfrt_balance, state.nfrt_balance)
+    } else {
+        (state.nfrt_balance, state.pfrt_balance)
+    };
+    require!(reserve_in > 0 && reserve_out > 0, AmmError::InsufficientLiquidity);
+
+    let price_impact_bps = price_impact_bps(state, amount_in, fee, amount_out, is_pfrt_to_nfrt)?;
+    if price_impact_bps > state.max_slippage_bps as u64 {
+        emit!(SwapRejected {
+            pool,
+            user,
+            amount_in,
+            amount_out,
+            is_pfrt_to_nfrt,
+            reason: SwapRejectionReason::PriceImpact,
+            observed_bps: price_impact_bps,
+            limit_bps: state.max_slippage_bps,
+        });
+        return err!(AmmError::PriceImpactTooHigh);
+    }
+
+    // TWAP deviation of the post-trade spot prices
+    if state.pfrt_twap > 0 && state.nfrt_twap > 0 {
+        let (new_pfrt_balance, new_nfrt_balance) = if is_pfrt_to_nfrt {
+            (state.pfrt_balance + amount_in, state.nfrt_balance - amount_out)
+        } else {
+            (state.pfrt_balance - amount_out, state.nfrt_balance + amount_in)
+        };
+        require!(new_pfrt_balance > 0 && new_nfrt_balance > 0, AmmError::InsufficientLiquidity);
+
+        let (pfrt_price, nfrt_price) = spot_prices(state, new_pfrt_balance, new_nfrt_balance)?;
+        let deviation_bps = std::cmp::max(
+            deviation_bps(pfrt_price, state.pfrt_twap),
+            deviation_bps(nfrt_price, state.nfrt_twap),
+        );
+        if deviation_bps > state.max_twap_deviation_bps as u64 {
+            emit!(SwapRejected {
+                pool,
+                user,
+                amount_in,
+                amount_out,
+                is_pfrt_to_nfrt,
+                reason: SwapRejectionReason::TwapDeviation,
+                observed_bps: deviation_bps,
+                limit_bps: state.max_twap_deviation_bps,
+            });
+            return err!(AmmError::TwapDeviationExceeded);
+        }
+    }
+
+    Ok(())
+}
+
+// Shortfall of the execution price against the pre-trade spot price
+fn price_impact_bps(
+    state: &AmmState,
+    amount_in: u64,
+    fee: u64,
+    amount_out: u64,
+    is_pfrt_to_nfrt: bool,
+) -> Result<u64> {
+    let (pfrt_price, nfrt_price) = spot_prices(state, state.pfrt_balance, state.nfrt_balance)?;
+    let price_in = if is_pfrt_to_nfrt { pfrt_price } else { nfrt_price };
+    let spot_out = ((amount_in - fee) as u128 * price_in) / 1_000_000;
+    Ok((spot_out.saturating_sub(amount_out as u128) * 10000)
+        .checked_div(spot_out)
+        .unwrap_or(0) as u64)
+}
+
+// LP tokens minted for a deposit the vaults received in full. The first
+// deposit mints the geometric mean less MINIMUM_LIQUIDITY, which is locked
+// forever so the share price can't be inflated from a dust deposit
+fn liquidity_for_deposit(state: &AmmState, pfrt_received: u64, nfrt_received: u64) -> Result<u64> {
+    if state.total_liquidity == 0 {
+        let liquidity = (pfrt_received as u128 * nfrt_received as u128).integer_sqrt() as u64;
+        require!(liquidity > MINIMUM_LIQUIDITY, AmmError::InsufficientLiquidity);
+        Ok(liquidity - MINIMUM_LIQUIDITY)
+    } else {
+        // Proportional liquidity
+        let pfrt_liquidity = (pfrt_received as u128 * state.total_liquidity as u128) / state.pfrt_balance as u128;
+        let nfrt_liquidity = (nfrt_received as u128 * state.total_liquidity as u128) / state.nfrt_balance as u128;
+        Ok(std::cmp::min(pfrt_liquidity, nfrt_liquidity) as u64)
+    }
+}
+
+// Pro rata share of both reserves for burning `liquidity`
+fn withdrawal_amounts(state: &AmmState, liquidity: u64) -> Result<(u64, u64)> {
+    let pfrt_amount = ((liquidity as u128 * state.pfrt_balance as u128) / state.total_liquidity as u128) as u64;
+    let nfrt_amount = ((liquidity as u128 * state.nfrt_balance as u128) / state.total_liquidity as u128) as u64;
+    require!(pfrt_amount > 0 && nfrt_amount > 0, AmmError::InvalidAmount);
+    Ok((pfrt_amount, nfrt_amount))
+}
+
+// Keep the logit curve's liquidity parameter proportional to pool liquidity so
+// adding or removing liquidity leaves the price unchanged
+fn rescale_curve_liquidity(state: &mut AmmState, previous_liquidity: u64) -> Result<()> {
+    if let CurveType::Logit { depth_bps } = state.curve {
+        state.curve_liquidity = if previous_liquidity == 0 || state.curve_liquidity == 0 {
+            ((state.pfrt_balance as u128 + state.nfrt_balance as u128) * depth_bps as u128 / 10000) as u64
+        } else {
+            (state.curve_liquidity as u128 * state.total_liquidity as u128 / previous_liquidity as u128) as u64
+        };
+        require!(state.curve_liquidity > 0, AmmError::InsufficientLiquidity);
+    }
+    Ok(())
+}
+
+fn deviation_bps(price: u128, twap: u64) -> u64 {
+    let twap = twap as u128;
+    std::cmp::min(price.abs_diff(twap) * 10000 / twap, u64::MAX as u128) as u64
+}
+
+// Protocol's share of a trading fee, held out of the reserves
+fn protocol_fee_for(state: &AmmState, fee: u64) -> Result<u64> {
+    u64::try_from(fee as u128 * state.protocol_fee_share_bps as u128 / 10000)
+        .map_err(|_| error!(AmmError::InvalidAmount))
+}
+
+// Split a swap's trading fee between LPs and the protocol, returning the protocol share
+fn accrue_fees(state: &mut AmmState, fee: u64, is_pfrt_to_nfrt: bool) -> Result<u64> {
+    let protocol_fee = protocol_fee_for(state, fee)?;
+    let lp_fee = fee - protocol_fee;
+
+    let (protocol_fees, cumulative_lp_fees, cumulative_protocol_fees) = if is_pfrt_to_nfrt {
+        (
+            &mut state.protocol_fees_pfrt,
+            &mut state.cumulative_lp_fees_pfrt,
+            &mut state.cumulative_protocol_fees_pfrt,
+        )
+    } else {
+        (
+            &mut state.protocol_fees_nfrt,
+            &mut state.cumulative_lp_fees_nfrt,
+            &mut state.cumulative_protocol_fees_nfrt,
+        )
+    };
+    *protocol_fees = protocol_fees.checked_add(protocol_fee).ok_or(AmmError::InvalidAmount)?;
+    *cumulative_lp_fees = cumulative_lp_fees.saturating_add(lp_fee);
+    *cumulative_protocol_fees = cumulative_protocol_fees.saturating_add(protocol_fee);
+
+    Ok(protocol_fee)
+}
+
+fn check_daily_volume_limit(
+    state: &mut AmmState,
+    trade_size: u64,
+    current_time: i64,
+) -> Result<()> {
+    // Reset daily volume if 24 hours passed
+    if current_time >= state.last_volume_reset + 86400 {
+        state.daily_volume = 0;
+        state.last_volume_reset = current_time;
+    }
+
+    require!(
+        state.daily_volume + trade_size <= state.daily_volume_limit,
+        AmmError::DailyVolumeLimitExceeded
+    );
+
+    Ok(())
+}
+
+// Settle long-term orders before a pool interaction. The TWAMM account may
+// be left out only while the pool has no open orders
+fn settle_long_term_orders(
+    state: &mut AmmState,
+    twamm: Option<&AccountLoader<TwammState>>,
+    now: i64,
+) -> Result<()> {
+    match twamm {
+        Some(twamm) => execute_virtual_orders(state, &mut *twamm.load_mut()?, now),
+        None => {
+            require!(state.open_long_term_orders == 0, AmmError::TwammAccountRequired);
+            Ok(())
+        }
+    }
+}
+
+/// Copy of the pool as a pool interaction at `now` would price against it,
+/// with the long-term order streams settled. The TWAMM account may be left
+/// out only while the pool has no open orders
+pub fn settled_pool_state(
+    state: &AmmState,
+    twamm: Option<&TwammState>,
+    now: i64,
+) -> Result<AmmState> {
+    let mut state = state.clone();
+    match twamm {
+        Some(twamm) => simulate_virtual_orders(&mut state, twamm, now)?,
+        None => require!(state.open_long_term_orders == 0, AmmError::TwammAccountRequired),
+    }
+    Ok(state)
+}
+
+// Run the long-term order streams from their last settlement up to `now`,
+// one order interval at a time so streams that expire at an interval
+// boundary stop selling there. Settlement stops short at a segment that
+// would push the curve out of range, leaving the rest of the streams
+// pending until trades bring the price back, so the pool keeps working
+fn execute_virtual_orders(state: &mut AmmState, twamm: &mut TwammState, now: i64) -> Result<()> {
+    let mut time = twamm.last_virtual_order_time;
+    while time < now {
+        // Nothing is streaming, so no expiry can fall in the gap
+        if twamm.pfrt_sell_rate == 0 && twamm.nfrt_sell_rate == 0 {
+            break;
+        }
+
+        let next_boundary = (time / TWAMM_ORDER_INTERVAL + 1) * TWAMM_ORDER_INTERVAL;
+        let segment_end = std::cmp::min(next_boundary, now);
+        let elapsed = (segment_end - time) as u128;
+
+        let pfrt_in = (twamm.pfrt_sell_rate * elapsed / SELL_RATE_SCALE) as u64;
+        let nfrt_in = (twamm.nfrt_sell_rate * elapsed / SELL_RATE_SCALE) as u64;
+        let (settled, nfrt_out, pfrt_out) = match virtual_segment(state, pfrt_in, nfrt_in) {
+            Some(segment) => segment,
+            None => {
+                twamm.last_virtual_order_time = time;
+                return Ok(());
+            }
+        };
+        *state = settled;
+        if pfrt_in > 0 {
+            twamm.pfrt_earnings_per_rate +=
+                nfrt_out as u128 * EARNINGS_PER_RATE_SCALE / twamm.pfrt_sell_rate;
+        }
+        if nfrt_in > 0 {
+            twamm.nfrt_earnings_per_rate +=
+                pfrt_out as u128 * EARNINGS_PER_RATE_SCALE / twamm.nfrt_sell_rate;
+        }
+
+        if segment_end == next_boundary {
+            twamm.expire_streams(next_boundary);
+        }
+        time = segment_end;
+    }
+    twamm.last_virtual_order_time = now;
+
+    Ok(())
+}
+
+// Trade the streams through `state` as execute_virtual_orders would,
+// leaving the order book untouched
+fn simulate_virtual_orders(state: &mut AmmState, twamm: &TwammState, now: i64) -> Result<()> {
+    let mut pfrt_sell_rate = twamm.pfrt_sell_rate;
+    let mut nfrt_sell_rate = twamm.nfrt_sell_rate;
+    let mut time = twamm.last_virtual_order_time;
+    while time < now && (pfrt_sell_rate > 0 || nfrt_sell_rate > 0) {
+        let next_boundary = (time / TWAMM_ORDER_INTERVAL + 1) * TWAMM_ORDER_INTERVAL;
+        let segment_end = std::cmp::min(next_boundary, now);
+        let elapsed = (segment_end - time) as u128;
+
+        let pfrt_in = (pfrt_sell_rate * elapsed / SELL_RATE_SCALE) as u64;
+        let nfrt_in = (nfrt_sell_rate * elapsed / SELL_RATE_SCALE) as u64;
+        match virtual_segment(state, pfrt_in, nfrt_in) {
+            Some((settled, _, _)) => *state = settled,
+            None => break,
+        }
+
+        let bucket = &twamm.buckets[TwammState::bucket_index(next_boundary)];
+        if segment_end == next_boundary && bucket.expiry == next_boundary {
+            pfrt_sell_rate -= bucket.pfrt_rate_ending;
+            nfrt_sell_rate -= bucket.nfrt_rate_ending;
+        }
+        time = segment_end;
+    }
+
+    Ok(())
+}
+
+// Trade one segment of both streams through a copy of the pool, returning
+// it with the NFRT and PFRT bought, or None if the segment can't be priced
+// or would leave the curve out of range
+fn virtual_segment(state: &AmmState, pfrt_in: u64, nfrt_in: u64) -> Option<(AmmState, u64, u64)> {
+    let mut settled = state.clone();
+    let nfrt_out = if pfrt_in > 0 {
+        execute_virtual_swap(&mut settled, pfrt_in, true).ok()?
+    } else {
+        0
+    };
+    let pfrt_out = if nfrt_in > 0 {
+        execute_virtual_swap(&mut settled, nfrt_in, false).ok()?
+    } else {
+        0
+    };
+    if settled.pfrt_balance == 0 || settled.nfrt_balance == 0 {
+        return None;
+    }
+    spot_prices(&settled, settled.pfrt_balance, settled.nfrt_balance).ok()?;
+    Some((settled, nfrt_out, pfrt_out))
+}
+
+// Trade a stream segment through the curve at the base fee. The input is
+// already in the vault and the output stays there for the orders to claim
+fn execute_virtual_swap(state: &mut AmmState, amount_in: u64, is_pfrt_to_nfrt: bool) -> Result<u64> {
+    let (amount_out, fee) =
+        calculate_swap_amount(state, amount_in, state.trading_fee_bps, is_pfrt_to_nfrt)?;
+    settle_swap_reserves(state, amount_in, fee, amount_out, is_pfrt_to_nfrt)?;
+
+    Ok(amount_out)
+}
+
+// Book a priced swap against the reserves. The protocol's share of `fee`
+// is held out of them and returned
+fn settle_swap_reserves(
+    state: &mut AmmState,
+    amount_in: u64,
+    fee: u64,
+    amount_out: u64,
+    is_pfrt_to_nfrt: bool,
+) -> Result<u64> {
+    let protocol_fee = accrue_fees(state, fee, is_pfrt_to_nfrt)?;
+    let reserve_in = amount_in - protocol_fee;
+    if is_pfrt_to_nfrt {
+        state.pfrt_balance += reserve_in;
+        state.nfrt_balance -= amount_out;
+    } else {
+        state.nfrt_balance += reserve_in;
+        state.pfrt_balance -= amount_out;
+    }
+
+    Ok(protocol_fee)
+}
+
+// Transfer out of a pool vault, signed by the pool authority
+#[allow(clippy::too_many_arguments)]
+fn transfer_from_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    pool: Pubkey,
+    authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let seeds = &[b"authority".as_ref(), pool.as_ref(), &[authority_bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// Close a limit order's escrow, signed by the pool authority
+fn close_escrow<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    pool: Pubkey,
+    authority_bump: u8,
+) -> Result<()> {
+    let seeds = &[b"authority".as_ref(), pool.as_ref(), &[authority_bump]];
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination: destination.clone(),
+            authority: authority.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))
+}
+
+// Charge a trade against the wallet's rolling daily quota: its market maker
+// entry's quota if it has one, otherwise its tier's. The previous day's
+// volume decays linearly as the current day fills, so the window rolls
+// instead of resetting all at once.
+fn check_user_volume_quota(
+    state: &AmmState,
+    user_trade_state: Option<&mut UserTradeState>,
+    market_maker: Option<&MarketMaker>,
+    trade_size: u64,
+    current_time: i64,
+) -> Result<()> {
+    let quota = match market_maker {
+        Some(entry) => entry.daily_volume_quota,
+        None => match &user_trade_state {
+            Some(user_trade_state) => state.volume_quota_tiers[user_trade_state.tier as usize],
+            None => state.volume_quota_tiers[0],
+        },
+    };
+    if quota == 0 {
+        return Ok(());
+    }
+    let user_trade_state = user_trade_state.ok_or(AmmError::UserTradeStateRequired)?;
+
+    let elapsed = current_time - user_trade_state.quota_window_start;
+    if elapsed >= 2 * SECONDS_PER_DAY {
+        user_trade_state.previous_window_volume = 0;
+        user_trade_state.quota_window_volume = 0;
+        user_trade_state.quota_window_start = current_time;
+    } else if elapsed >= SECONDS_PER_DAY {
+        user_trade_state.previous_window_volume = user_trade_state.quota_window_volume;
+        user_trade_state.quota_window_volume = 0;
+        user_trade_state.quota_window_start += SECONDS_PER_DAY;
+    }
+
+    let remaining_weight = (SECONDS_PER_DAY - (current_time - user_trade_state.quota_window_start)) as u128;
+    let rolling_volume = (user_trade_state.previous_window_volume as u128 * remaining_weight
+        / SECONDS_PER_DAY as u128) as u64
+        + user_trade_state.quota_window_volume;
+    let remaining = quota.saturating_sub(rolling_volume);
+    if trade_size > remaining {
+        msg!("Remaining daily volume quota: {}", remaining);
+        return err!(AmmError::VolumeQuotaExceeded);
+    }
+
+    user_trade_state.quota_window_volume += trade_size;
+
+    Ok(())
+}
+
+// Charge a trade against the pool's slot window. Price movement and volume
+// accumulate across every signer in the window, so splitting a trade over
+// several wallets doesn't get around the caps. Allowlisted market makers
+// are neither capped nor counted.
+#[allow(clippy::too_many_arguments)]
+fn enforce_slot_window(
+    state: &mut AmmState,
+    pool: Pubkey,
+    user: Pubkey,
+    amount_in: u64,
+    amount_out: u64,
+    is_pfrt_to_nfrt: bool,
+    slot: u64,
+    exempt: bool,
+) -> Result<()> {
+    if slot >= state.window_start_slot.saturating_add(state.slot_window) {
+        state.window_start_slot = slot;
+        state.window_price_movement_bps = 0;
+        state.window_volume = 0;
+    }
+
+    if exempt {
+        return Ok(());
+    }
+
+    // Spot price movement of this trade, whichever leg moves further
+    let (new_pfrt_balance, new_nfrt_balance) = if is_pfrt_to_nfrt {
+        (state.pfrt_balance + amount_in, state.nfrt_balance - amount_out)
+    } else {
+        (state.pfrt_balance - amount_out, state.nfrt_balance + amount_in)
+    };
+    require!(new_pfrt_balance > 0 && new_nfrt_balance > 0, AmmError::InsufficientLiquidity);
+
+    let (pfrt_before, nfrt_before) = spot_prices(state, state.pfrt_balance, state.nfrt_balance)?;
+    let (pfrt_after, nfrt_after) = spot_prices(state, new_pfrt_balance, new_nfrt_balance)?;
+    let movement_bps = std::cmp::max(
+        deviation_bps(pfrt_after, pfrt_before as u64),
+        deviation_bps(nfrt_after, nfrt_before as u64),
+    );
+
+    let window_movement_bps = state.window_price_movement_bps.saturating_add(movement_bps);
+    if window_movement_bps > state.max_window_price_impact_bps as u64 {
+        emit!(SwapRejected {
+            pool,
+            user,
+            amount_in,
+            amount_out,
+            is_pfrt_to_nfrt,
+            reason: SwapRejectionReason::SlotPriceImpact,
+            observed_bps: window_movement_bps,
+            limit_bps: state.max_window_price_impact_bps,
+        });
+        return err!(AmmError::SlotPriceImpactExceeded);
+    }
+
+    let window_volume = state.window_volume.saturating_add(amount_in);
+    require!(
+        state.max_window_volume == 0 || window_volume <= state.max_window_volume,
+        AmmError::SlotVolumeExceeded
+    );
+
+    state.window_price_movement_bps = window_movement_bps;
+    state.window_volume = window_volume;
+
+    Ok(())
+}
+
+// Curve math
+//
+// The logit curve is an LMSR market maker over the pool's inventory. PFRT and
+// NFRT redeem for one unit of collateral together, so their collateral prices
+// are p(PFRT) = sigmoid((nfrt - pfrt) / b) and p(NFRT) = 1 - p(PFRT). Trades
+// preserve e^(-pfrt/b) + e^(-nfrt/b), which concentrates depth around the
+// price the LPs deposited at instead of spreading it over prices that can't occur.
+// All values below are fixed point with FP_ONE = 1.
+
+// (reserve_out - reserve_in) / b, bounded so prices stay representable
+fn logit_fp(reserve_in: u64, reserve_out: u64, curve_liquidity: u64) -> Result<i128> {
+    require!(curve_liquidity > 0, AmmError::InsufficientLiquidity);
+    let logit = (reserve_out as i128 - reserve_in as i128) * FP_ONE as i128 / curve_liquidity as i128;
+    require!(logit.abs() <= MAX_LOGIT * FP_ONE as i128, AmmError::CurveOutOfRange);
+    Ok(logit)
+}
+
+// out = b * ln(1 + e^((reserve_out - reserve_in) / b) * (1 - e^(-amount_in / b)))
+fn logit_amount_out(reserve_in: u64, reserve_out: u64, amount_in: u64, curve_liquidity: u64) -> Result<u64> {
+    let ratio = exp_fp(logit_fp(reserve_in, reserve_out, curve_liquidity)?)?;
+    let decay = exp_fp(-(amount_in as i128 * FP_ONE as i128 / curve_liquidity as i128))?;
+    let growth = ratio
+        .checked_mul(FP_ONE - decay)
+        .ok_or(AmmError::CurveOutOfRange)?
+        / FP_ONE;
+    let log = ln_fp(FP_ONE + growth)?;
+    let amount_out = (curve_liquidity as u128 * log as u128) / FP_ONE;
+    require!(amount_out < reserve_out as u128, AmmError::InsufficientLiquidity);
+    Ok(amount_out as u64)
+}
+
+// Inverse of logit_amount_out:
+// in = -b * ln(1 - (e^(amount_out / b) - 1) / e^((reserve_out - reserve_in) / b))
+fn logit_amount_in(reserve_in: u64, reserve_out: u64, amount_out: u64, curve_liquidity: u64) -> Result<u64> {
+    let ratio = exp_fp(logit_fp(reserve_in, reserve_out, curve_liquidity)?)?;
+    let exponent = (amount_out as i128 * FP_ONE as i128 + curve_liquidity as i128 - 1) / curve_liquidity as i128;
+    let growth = exp_fp(exponent)? - FP_ONE;
+    let depletion = growth
+        .checked_mul(FP_ONE)
+        .ok_or(AmmError::CurveOutOfRange)?
+        .div_ceil(ratio);
+    require!(depletion < FP_ONE, AmmError::InsufficientLiquidity);
+
+    let log = -ln_fp(FP_ONE - depletion)?;
+    let amount_in = (curve_liquidity as u128 * log as u128).div_ceil(FP_ONE);
+    u64::try_from(amount_in).map_err(|_| error!(AmmError::CurveOutOfRange))
+}
+
+// e^x
+fn exp_fp(x: i128) -> Result<u128> {
+    require!(x <= 2 * MAX_LOGIT * FP_ONE as i128, AmmError::CurveOutOfRange);
+    if x < -2 * MAX_LOGIT * FP_ONE as i128 {
+        return Ok(0);
+    }
+
+    // e^x = 2^k * e^r with r in [0, ln 2)
+    let k = x.div_euclid(LN2_FP as i128);
+    let r = x.rem_euclid(LN2_FP as i128) as u128;
+
+    let mut sum = FP_ONE;
+    let mut term = FP_ONE;
+    for i in 1..=EXP_TERMS {
+        term = term * r / (i * FP_ONE);
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+
+    Ok(if k >= 0 { sum << k } else { sum >> -k })
+}
+
+// ln x for x > 0
+fn ln_fp(x: u128) -> Result<i128> {
+    require!(x > 0, AmmError::CurveOutOfRange);
+
+    // x = 2^k * m with m in [1, 2)
+    let mut k: i128 = 0;
+    let mut m = x;
+    while m >= 2 * FP_ONE {
+        m >>= 1;
+        k += 1;
+    }
+    while m < FP_ONE {
+        m <<= 1;
+        k -= 1;
+    }
+
+    // ln m = 2 * atanh((m - 1) / (m + 1))
+    let z = (m - FP_ONE) * FP_ONE / (m + FP_ONE);
+    let z2 = z * z / FP_ONE;
+    let mut term = z;
+    let mut sum = 0u128;
+    let mut n = 1u128;
+    while term > 0 {
+        sum += term / n;
+        term = term * z2 / FP_ONE;
+        n += 2;
+    }
+
+    Ok(2 * sum as i128 + k * LN2_FP as i128)
+}
+
+// Account structs
+#[derive(Accounts)]
+pub struct InitializeFactory<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<Factory>(),
+        seeds = [b"factory"],
+        bump
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<PoolListing>(),
+        seeds = [b"pool_listing", factory.pool_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool_listing: Account<'info, PoolListing>,
+    
+    #[account(
+        init,
+        payer = user,
         space = 8 + std::mem::size_of::<AmmState>(),
-        seeds = [b"amm_state"],
+        seeds = [b"pool", pfrt_mint.key().as_ref(), nfrt_mint.key().as_ref()],
+        bump
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = user,
+        token::mint = pfrt_mint,
+        token::authority = authority,
+        seeds = [b"pfrt_vault", state.key().as_ref()],
+        bump
+    )]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = user,
+        token::mint = nfrt_mint,
+        token::authority = authority,
+        seeds = [b"nfrt_vault", state.key().as_ref()],
+        bump
+    )]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = user,
+        mint::decimals = pfrt_mint.decimals,
+        mint::authority = authority,
+        seeds = [b"lp_mint", state.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<ObservationBuffer>(),
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    pub governance: Signer<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault,
+        has_one = lp_mint
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, token::mint = lp_mint)]
+    pub user_lp_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault,
+        has_one = lp_mint
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_lp_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    // Optional per-wallet record; traders who omit it pay no rent
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserTradeState>(),
+        seeds = [b"user_trade", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    // Only read when the pool prices oracle divergence into its fee
+    #[account(address = state.fee_oracle @ AmmError::InvalidFeeOracle)]
+    pub fee_oracle: Option<Box<Account<'info, OracleState>>>,
+    
+    // The signer's allowlist entry, exempting the trade from slot-window caps
+    #[account(
+        seeds = [b"market_maker", state.key().as_ref(), user.key().as_ref()],
+        bump = market_maker.bump
+    )]
+    pub market_maker: Option<Account<'info, MarketMaker>>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+}
+
+impl<'info> Swap<'info> {
+    // Move tokens for a priced swap and record it against the pool and user.
+    // Returns the protocol's share of `fee`, which is held out of the reserves
+    #[allow(clippy::too_many_arguments)]
+    fn execute_swap(
+        &mut self,
+        authority_bump: u8,
+        amount_in: u64,
+        amount_in_received: u64,
+        fee: u64,
+        amount_out: u64,
+        is_pfrt_to_nfrt: bool,
+        slot: u64,
+    ) -> Result<u64> {
+        let (user_from, vault_in, mint_in, vault_out, user_to, mint_out) = if is_pfrt_to_nfrt {
+            (&self.user_pfrt_account, &self.pfrt_vault, &self.pfrt_mint, &self.nfrt_vault, &self.user_nfrt_account, &self.nfrt_mint)
+        } else {
+            (&self.user_nfrt_account, &self.nfrt_vault, &self.nfrt_mint, &self.pfrt_vault, &self.user_pfrt_account, &self.pfrt_mint)
+        };
+
+        // Transfer input from user to vault
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: user_from.to_account_info(),
+                    mint: mint_in.to_account_info(),
+                    to: vault_in.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ),
+            amount_in,
+            mint_in.decimals,
+        )?;
+
+        // Transfer output from vault to user
+        let state_key = self.state.key();
+        let seeds = &[
+            b"authority".as_ref(),
+            state_key.as_ref(),
+            &[authority_bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: vault_out.to_account_info(),
+                    mint: mint_out.to_account_info(),
+                    to: user_to.to_account_info(),
+                    authority: self.authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount_out,
+            mint_out.decimals,
+        )?;
+
+        let state = &mut self.state;
+        let protocol_fee =
+            settle_swap_reserves(state, amount_in_received, fee, amount_out, is_pfrt_to_nfrt)?;
+
+        // Update trade tracking
+        if let Some(user_trade_state) = &mut self.user_trade_state {
+            user_trade_state.last_trade_slot = slot;
+        }
+
+        // Update daily volume
+        state.daily_volume += amount_in;
+
+        Ok(protocol_fee)
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeTwamm<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<TwammState>(),
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: AccountLoader<'info, TwammState>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLongTermOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: AccountLoader<'info, TwammState>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LongTermOrder>(),
+        seeds = [b"long_term_order", state.key().as_ref(), user.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub order: Account<'info, LongTermOrder>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLongTermOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: AccountLoader<'info, TwammState>,
+    
+    #[account(
+        mut,
+        seeds = [b"long_term_order", state.key().as_ref(), order.owner.as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        constraint = order.owner == user.key() @ AmmError::NotOrderOwner
+    )]
+    pub order: Account<'info, LongTermOrder>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLongTermOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: AccountLoader<'info, TwammState>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"long_term_order", state.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, LongTermOrder>,
+    
+    /// CHECK: Order owner receiving the rent; checked by the order's seeds
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = owner_pfrt_account.owner == owner.key() @ AmmError::NotOrderOwner)]
+    pub owner_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = owner_nfrt_account.owner == owner.key() @ AmmError::NotOrderOwner)]
+    pub owner_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    #[account(
+        constraint = input_mint.key() == state.pfrt_mint
+            || input_mint.key() == state.nfrt_mint @ AmmError::InvalidMintPair
+    )]
+    pub input_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + std::mem::size_of::<LimitOrder>(),
+        seeds = [b"limit_order", state.key().as_ref(), user.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    
+    #[account(
+        init,
+        payer = user,
+        token::mint = input_mint,
+        token::authority = authority,
+        seeds = [b"limit_order_escrow", order.key().as_ref()],
+        bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_input_account: InterfaceAccount<'info, TokenAccount>,
+    
+    // Created here so keepers can charge fills to the owner's volume quota
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserTradeState>(),
+        seeds = [b"user_trade", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
+        has_one = pfrt_vault,
+        has_one = nfrt_vault
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"limit_order", state.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    
+    /// CHECK: Order owner receiving the rent; checked by the order's seeds
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"limit_order_escrow", order.key().as_ref()],
+        bump = order.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = owner_output_account.owner == owner.key() @ AmmError::NotOrderOwner)]
+    pub owner_output_account: InterfaceAccount<'info, TokenAccount>,
+    
+    // Required while a volume quota applies to the owner
+    #[account(
+        mut,
+        seeds = [b"user_trade", state.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub owner_trade_state: Option<Account<'info, UserTradeState>>,
+    
+    #[account(mut)]
+    pub keeper_output_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub keeper: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(address = state.fee_oracle @ AmmError::InvalidFeeOracle)]
+    pub fee_oracle: Option<Box<Account<'info, OracleState>>>,
+    
+    #[account(
+        mut,
+        seeds = [b"twamm", state.key().as_ref()],
+        bump
+    )]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = authority
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"authority", state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used as authority
+    pub authority: AccountInfo<'info>,
+    
+    #[account(address = order.input_mint)]
+    pub input_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"limit_order", state.key().as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    
+    /// CHECK: Order owner receiving the rent; checked by the order's seeds
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"limit_order_escrow", order.key().as_ref()],
+        bump = order.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = owner_input_account.owner == owner.key() @ AmmError::NotOrderOwner)]
+    pub owner_input_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Consult<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        seeds = [b"observations", state.key().as_ref()],
+        bump
+    )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(address = state.fee_oracle @ AmmError::InvalidFeeOracle)]
+    pub fee_oracle: Option<Box<Account<'info, OracleState>>>,
+
+    #[account(seeds = [b"twamm", state.key().as_ref()], bump)]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+}
+
+impl<'info> Quote<'info> {
+    fn settled_state(&self) -> Result<AmmState> {
+        let twamm = self.twamm.as_ref().map(|t| t.load()).transpose()?;
+        settled_pool_state(&self.state, twamm.as_deref(), Clock::get()?.unix_timestamp)
+    }
+}
+
+#[derive(Accounts)]
+pub struct IncreaseObservationCardinality<'info> {
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
+    )]
+    pub state: Account<'info, AmmState>,
+    
+    #[account(
+        mut,
+        seeds = [b"observations", state.key().as_ref()],
         bump
     )]
+    pub observations: AccountLoader<'info, ObservationBuffer>,
+    
+}
+
+#[derive(Accounts)]
+pub struct AdminAction<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
+    )]
     pub state: Account<'info, AmmState>,
     
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(market_maker: Pubkey)]
+pub struct AddMarketMaker<'info> {
     #[account(
-        seeds = [b"authority", pfrt_mint.key().as_ref()],
-        bump
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = governance
     )]
-    /// CHECK: This is a PDA used as authority
-    pub authority: AccountInfo<'info>,
-    
-    pub pfrt_mint: Account<'info, Mint>,
-    pub nfrt_mint: Account<'info, Mint>,
+    pub factory: Account<'info, Factory>,
     
     #[account(
-        init,
-        payer = user,
-        token::mint = pfrt_mint,
-        token::authority = authority,
-        seeds = [b"pfrt_vault"],
-        bump
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
     )]
-    pub pfrt_vault: Account<'info, TokenAccount>,
+    pub state: Account<'info, AmmState>,
     
     #[account(
         init,
-        payer = user,
-        token::mint = nfrt_mint,
-        token::authority = authority,
-        seeds = [b"nfrt_vault"],
+        payer = governance,
+        space = 8 + std::mem::size_of::<MarketMaker>(),
+        seeds = [b"market_maker", state.key().as_ref(), market_maker.as_ref()],
         bump
     )]
-    pub nfrt_vault: Account<'info, TokenAccount>,
+    pub market_maker_entry: Account<'info, MarketMaker>,
     
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub governance: Signer<'info>,
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AddLiquidity<'info> {
+pub struct SetUserTier<'info> {
     #[account(
-        mut,
-        seeds = [b"amm_state"],
-        bump,
-        has_one = pfrt_vault,
-        has_one = nfrt_vault
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+    
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
     )]
     pub state: Account<'info, AmmState>,
     
-    #[account(mut)]
-    pub pfrt_vault: Account<'info, TokenAccount>,
+    /// CHECK: Wallet being assigned a tier; only its key is used
+    pub user: UncheckedAccount<'info>,
     
-    #[account(mut)]
-    pub nfrt_vault: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = governance,
+        space = 8 + std::mem::size_of::<UserTradeState>(),
+        seeds = [b"user_trade", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Account<'info, UserTradeState>,
     
     #[account(mut)]
-    pub user_pfrt_account: Account<'info, TokenAccount>,
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMarketMaker<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
     
-    #[account(mut)]
-    pub user_nfrt_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump
+    )]
+    pub state: Account<'info, AmmState>,
     
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        close = governance,
+        seeds = [b"market_maker", state.key().as_ref(), market_maker_entry.market_maker.as_ref()],
+        bump = market_maker_entry.bump
+    )]
+    pub market_maker_entry: Account<'info, MarketMaker>,
+    
+    #[account(mut)]
+    pub governance: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Swap<'info> {
+pub struct CollectProtocolFees<'info> {
     #[account(
         mut,
-        seeds = [b"amm_state"],
-        bump,
+        seeds = [b"pool", state.pfrt_mint.as_ref(), state.nfrt_mint.as_ref()],
+        bump = state.bump,
         has_one = authority,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint,
         has_one = pfrt_vault,
         has_one = nfrt_vault
     )]
     pub state: Account<'info, AmmState>,
     
     #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + std::mem::size_of::<UserTradeState>(),
-        seeds = [b"user_trade", user.key().as_ref()],
-        bump
-    )]
-    pub user_trade_state: Account<'info, UserTradeState>,
-    
-    #[account(
-        seeds = [b"authority", state.pfrt_mint.as_ref()],
+        seeds = [b"authority", state.key().as_ref()],
         bump
     )]
     /// CHECK: This is a PDA used as authority
     pub authority: AccountInfo<'info>,
     
-    #[account(mut)]
-    pub pfrt_vault: Account<'info, TokenAccount>,
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
     
     #[account(mut)]
-    pub nfrt_vault: Account<'info, TokenAccount>,
+    pub pfrt_vault: InterfaceAccount<'info, TokenAccount>,
     
     #[account(mut)]
-    pub user_pfrt_account: Account<'info, TokenAccount>,
+    pub nfrt_vault: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut)]
-    pub user_nfrt_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"vault_info", pfrt_mint.key().as_ref()],
+        bump = pfrt_treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = pfrt_treasury_vault_info.vault == pfrt_treasury_vault.key()
+    )]
+    pub pfrt_treasury_vault_info: Account<'info, VaultInfo>,
     
     #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-}
-
-#[derive(Accounts)]
-pub struct AdminAction<'info> {
+    pub pfrt_treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    
     #[account(
         mut,
-        seeds = [b"amm_state"],
-        bump,
-        has_one = authority
+        seeds = [b"vault_info", nfrt_mint.key().as_ref()],
+        bump = nfrt_treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = nfrt_treasury_vault_info.vault == nfrt_treasury_vault.key()
     )]
-    pub state: Account<'info, AmmState>,
+    pub nfrt_treasury_vault_info: Account<'info, VaultInfo>,
+    
+    #[account(mut)]
+    pub nfrt_treasury_vault: InterfaceAccount<'info, TokenAccount>,
     
-    pub authority: Signer<'info>,
+    pub treasury_program: Program<'info, ZiroDeltaTreasury>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 // Data structures
+#[account]
+pub struct Factory {
+    pub governance: Pubkey,
+    pub pool_count: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct PoolListing {
+    pub index: u64,
+    pub pool: Pubkey,
+    pub pfrt_mint: Pubkey,
+    pub nfrt_mint: Pubkey,
+    pub epoch_id: u64,
+    pub bump: u8,
+}
+
 #[account]
 pub struct AmmState {
+    pub epoch_id: u64,
+    pub bump: u8,
     pub authority: Pubkey,
     pub pfrt_mint: Pubkey,
     pub nfrt_mint: Pubkey,
     pub pfrt_vault: Pubkey,
     pub nfrt_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    
+    // Pricing curve, and the logit curve's liquidity parameter b
+    pub curve: CurveType,
+    pub curve_liquidity: u64,
     
     // Balances
     pub pfrt_balance: u64,
@@ -524,6 +3233,7 @@ pub struct AmmState {
     pub max_trade_size: u64,
     pub daily_volume_limit: u64,
     pub max_slippage_bps: u16,
+    pub max_twap_deviation_bps: u16,
     
     // Security state
     pub trading_paused: bool,
@@ -537,14 +3247,371 @@ pub struct AmmState {
     pub last_twap_update: i64,
     pub pfrt_twap: u64,
     pub nfrt_twap: u64,
+    
+    // Dynamic fees: trading_fee_bps is the base, raised by TWAP and oracle
+    // deviation and clamped to [min_fee_bps, max_fee_bps]
+    pub dynamic_fee_enabled: bool,
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+    pub volatility_fee_factor_bps: u16,
+    pub oracle_fee_factor_bps: u16,
+    pub fee_oracle: Pubkey,
+    
+    // Fee split: share of trading_fee_bps taken by the protocol, fees held
+    // in the vaults awaiting collection, and lifetime totals per leg
+    pub protocol_fee_share_bps: u16,
+    pub protocol_fees_pfrt: u64,
+    pub protocol_fees_nfrt: u64,
+    pub cumulative_lp_fees_pfrt: u64,
+    pub cumulative_lp_fees_nfrt: u64,
+    pub cumulative_protocol_fees_pfrt: u64,
+    pub cumulative_protocol_fees_nfrt: u64,
+    
+    // Pool-wide caps on spot price movement and volume across all trades
+    // in a window of slot_window slots, and the current window's totals
+    pub slot_window: u64,
+    pub max_window_price_impact_bps: u16,
+    pub max_window_volume: u64,
+    pub window_start_slot: u64,
+    pub window_price_movement_bps: u64,
+    pub window_volume: u64,
+    
+    // Rolling daily volume quota per wallet tier, 0 for unlimited
+    pub volume_quota_tiers: [u64; VOLUME_QUOTA_TIERS],
+    
+    // Long-term orders not yet cancelled or closed
+    pub open_long_term_orders: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurveType {
+    /// x * y = k
+    ConstantProduct,
+    /// LMSR over complementary tokens; b starts at `depth_bps` of deposited
+    /// reserves (2500 matches constant-product depth at the deposit price)
+    Logit { depth_bps: u16 },
+}
+
+/// Ring buffer of cumulative price samples, Uniswap v3 style
+#[account(zero_copy)]
+pub struct ObservationBuffer {
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+    pub pool: Pubkey,
+    pub index: u16,
+    pub cardinality: u16,
+    pub cardinality_next: u16,
+    pub _padding: [u8; 10],
+}
+
+#[zero_copy]
+pub struct Observation {
+    pub pfrt_price_cumulative: u128,
+    pub nfrt_price_cumulative: u128,
+    pub timestamp: i64,
+    pub _padding: [u8; 8],
+}
+
+impl ObservationBuffer {
+    fn write(&mut self, timestamp: i64, pfrt_price_cumulative: u128, nfrt_price_cumulative: u128) {
+        // Grow into the extra slots once the current ring is full
+        if self.index + 1 == self.cardinality && self.cardinality_next > self.cardinality {
+            self.cardinality = self.cardinality_next;
+        }
+        self.index = (self.index + 1) % self.cardinality;
+
+        let observation = &mut self.observations[self.index as usize];
+        observation.timestamp = timestamp;
+        observation.pfrt_price_cumulative = pfrt_price_cumulative;
+        observation.nfrt_price_cumulative = nfrt_price_cumulative;
+    }
+
+    fn oldest_index(&self) -> u16 {
+        let next = (self.index + 1) % self.cardinality;
+        // Slots are zeroed until the ring first wraps
+        if self.observations[next as usize].timestamp == 0 {
+            0
+        } else {
+            next
+        }
+    }
+
+    fn oldest(&self) -> &Observation {
+        &self.observations[self.oldest_index() as usize]
+    }
+
+    // Cumulative prices at `target`, interpolating between samples
+    fn observe(&self, state: &AmmState, target: i64) -> Result<(u128, u128)> {
+        let latest = &self.observations[self.index as usize];
+
+        if target >= latest.timestamp {
+            // Extrapolate at the current spot price since the latest sample
+            let elapsed = (target - latest.timestamp) as u128;
+            if elapsed == 0 || state.pfrt_balance == 0 || state.nfrt_balance == 0 {
+                return Ok((latest.pfrt_price_cumulative, latest.nfrt_price_cumulative));
+            }
+            let (pfrt_price, nfrt_price) = spot_prices(state, state.pfrt_balance, state.nfrt_balance)?;
+            return Ok((
+                latest
+                    .pfrt_price_cumulative
+                    .wrapping_add(pfrt_price.wrapping_mul(elapsed)),
+                latest
+                    .nfrt_price_cumulative
+                    .wrapping_add(nfrt_price.wrapping_mul(elapsed)),
+            ));
+        }
+
+        let oldest = self.oldest();
+        require!(target >= oldest.timestamp, AmmError::ObservationTooOld);
+
+        // Binary search the ring in chronological order for the samples around target
+        let oldest_index = self.oldest_index();
+        let len = if oldest_index == 0 { self.index + 1 } else { self.cardinality };
+        let at = |i: u16| &self.observations[((oldest_index + i) % self.cardinality) as usize];
+
+        let (mut lo, mut hi) = (0u16, len - 1);
+        while hi - lo > 1 {
+            let mid = (lo + hi) / 2;
+            if at(mid).timestamp <= target {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let (before, after) = (at(lo), at(hi));
+        if target == before.timestamp {
+            return Ok((before.pfrt_price_cumulative, before.nfrt_price_cumulative));
+        }
+
+        let span = (after.timestamp - before.timestamp) as u128;
+        let delta = (target - before.timestamp) as u128;
+        Ok((
+            before.pfrt_price_cumulative.wrapping_add(
+                after.pfrt_price_cumulative.wrapping_sub(before.pfrt_price_cumulative) / span * delta,
+            ),
+            before.nfrt_price_cumulative.wrapping_add(
+                after.nfrt_price_cumulative.wrapping_sub(before.nfrt_price_cumulative) / span * delta,
+            ),
+        ))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TwapQuote {
+    pub pfrt_twap: u64,
+    pub nfrt_twap: u64,
+    pub seconds_ago: u32,
+}
+
+// Amounts are what the user sends or receives, net of Token-2022 transfer fees
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub fee_bps: u16,
+    pub protocol_fee: u64,
+    pub price_impact_bps: u64,
+    pub pfrt_balance_after: u64,
+    pub nfrt_balance_after: u64,
+}
+
+// Deposited amounts are what the vaults receive
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AddLiquidityQuote {
+    pub pfrt_amount: u64,
+    pub nfrt_amount: u64,
+    pub liquidity: u64,
+    pub total_liquidity_after: u64,
+    pub pfrt_balance_after: u64,
+    pub nfrt_balance_after: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RemoveLiquidityQuote {
+    pub liquidity: u64,
+    pub pfrt_amount: u64,
+    pub nfrt_amount: u64,
+    pub total_liquidity_after: u64,
+    pub pfrt_balance_after: u64,
+    pub nfrt_balance_after: u64,
 }
 
 #[account]
 pub struct UserTradeState {
     pub last_trade_slot: u64,
+    
+    // Volume quota tier, and volume in the current and previous quota days
+    pub tier: u8,
+    pub quota_window_start: i64,
+    pub quota_window_volume: u64,
+    pub previous_window_volume: u64,
+}
+
+/// TWAMM order book: the aggregate sell rate of each stream (tokens per
+/// second, scaled by SELL_RATE_SCALE) and the output earned per unit of
+/// sell rate, plus a ring of expiry buckets one order interval apart
+#[account(zero_copy)]
+pub struct TwammState {
+    pub pfrt_sell_rate: u128,
+    pub nfrt_sell_rate: u128,
+    pub pfrt_earnings_per_rate: u128,
+    pub nfrt_earnings_per_rate: u128,
+    pub buckets: [TwammBucket; TWAMM_BUCKETS],
+    pub pool: Pubkey,
+    pub last_virtual_order_time: i64,
+    pub _padding: [u8; 8],
+}
+
+/// Streams ending at one interval boundary, and the earnings per rate at
+/// that boundary once it has passed
+#[zero_copy]
+pub struct TwammBucket {
+    pub pfrt_rate_ending: u128,
+    pub nfrt_rate_ending: u128,
+    pub pfrt_earnings_at_expiry: u128,
+    pub nfrt_earnings_at_expiry: u128,
+    pub expiry: i64,
+    pub orders: u64,
+}
+
+impl TwammState {
+    fn bucket_index(expiry: i64) -> usize {
+        (expiry / TWAMM_ORDER_INTERVAL) as usize % TWAMM_BUCKETS
+    }
+
+    // Add a stream ending at `expiry`, returning the earnings per rate the
+    // order starts from. A bucket is only reused once every order from its
+    // previous expiry has been closed
+    fn open_stream(&mut self, expiry: i64, sell_rate: u128, is_pfrt_to_nfrt: bool) -> Result<u128> {
+        let bucket = &mut self.buckets[Self::bucket_index(expiry)];
+        if bucket.expiry != expiry {
+            require!(bucket.orders == 0, AmmError::TwammBucketBusy);
+            bucket.pfrt_rate_ending = 0;
+            bucket.nfrt_rate_ending = 0;
+            bucket.pfrt_earnings_at_expiry = 0;
+            bucket.nfrt_earnings_at_expiry = 0;
+            bucket.expiry = expiry;
+        }
+        bucket.orders += 1;
+
+        if is_pfrt_to_nfrt {
+            bucket.pfrt_rate_ending += sell_rate;
+            self.pfrt_sell_rate += sell_rate;
+            Ok(self.pfrt_earnings_per_rate)
+        } else {
+            bucket.nfrt_rate_ending += sell_rate;
+            self.nfrt_sell_rate += sell_rate;
+            Ok(self.nfrt_earnings_per_rate)
+        }
+    }
+
+    // Drop an order's stream, returning the input it has left unsold as of
+    // the last settlement
+    fn close_stream(&mut self, order: &LongTermOrder) -> u64 {
+        let now = self.last_virtual_order_time;
+        let bucket = &mut self.buckets[Self::bucket_index(order.expiry)];
+        bucket.orders -= 1;
+        if now >= order.expiry {
+            return 0;
+        }
+
+        if order.is_pfrt_to_nfrt {
+            bucket.pfrt_rate_ending -= order.sell_rate;
+            self.pfrt_sell_rate -= order.sell_rate;
+        } else {
+            bucket.nfrt_rate_ending -= order.sell_rate;
+            self.nfrt_sell_rate -= order.sell_rate;
+        }
+        (order.sell_rate * (order.expiry - now) as u128 / SELL_RATE_SCALE) as u64
+    }
+
+    // Stop the streams ending at `boundary` and record their final earnings
+    fn expire_streams(&mut self, boundary: i64) {
+        let bucket = &mut self.buckets[Self::bucket_index(boundary)];
+        if bucket.expiry != boundary {
+            return;
+        }
+        self.pfrt_sell_rate -= bucket.pfrt_rate_ending;
+        self.nfrt_sell_rate -= bucket.nfrt_rate_ending;
+        bucket.pfrt_earnings_at_expiry = self.pfrt_earnings_per_rate;
+        bucket.nfrt_earnings_at_expiry = self.nfrt_earnings_per_rate;
+    }
+
+    // Output owed to an order since its last claim; expects the streams to
+    // be settled up to now
+    fn claim_proceeds(&self, order: &mut LongTermOrder) -> u64 {
+        let earnings_per_rate = if order.expiry <= self.last_virtual_order_time {
+            let bucket = &self.buckets[Self::bucket_index(order.expiry)];
+            if order.is_pfrt_to_nfrt {
+                bucket.pfrt_earnings_at_expiry
+            } else {
+                bucket.nfrt_earnings_at_expiry
+            }
+        } else if order.is_pfrt_to_nfrt {
+            self.pfrt_earnings_per_rate
+        } else {
+            self.nfrt_earnings_per_rate
+        };
+
+        let proceeds = order.sell_rate * (earnings_per_rate - order.earnings_checkpoint)
+            / EARNINGS_PER_RATE_SCALE;
+        order.earnings_checkpoint = earnings_per_rate;
+        proceeds as u64
+    }
+}
+
+/// A TWAMM order selling its input at `sell_rate` from `start_time` until
+/// `expiry`
+#[account]
+pub struct LongTermOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub is_pfrt_to_nfrt: bool,
+    pub sell_rate: u128,
+    pub start_time: i64,
+    pub expiry: i64,
+    pub earnings_checkpoint: u128,
+    pub bump: u8,
+}
+
+/// Resting limit order; `amount` of `input_mint` sits in the order's escrow
+/// until filled or cancelled
+#[account]
+pub struct LimitOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub input_mint: Pubkey,
+    pub is_pfrt_to_nfrt: bool,
+    pub amount: u64,
+    pub limit_price: u64,
+    pub expiry: i64,
+    pub bump: u8,
+    pub escrow_bump: u8,
+}
+
+/// Allowlist entry exempting a market maker from a pool's slot-window caps
+/// and replacing its tier quota with its own
+#[account]
+pub struct MarketMaker {
+    pub pool: Pubkey,
+    pub market_maker: Pubkey,
+    pub daily_volume_quota: u64,
+    pub bump: u8,
 }
 
 // Events
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub pfrt_mint: Pubkey,
+    pub nfrt_mint: Pubkey,
+    pub epoch_id: u64,
+    pub index: u64,
+}
+
 #[event]
 pub struct LiquidityAdded {
     pub user: Pubkey,
@@ -553,13 +3620,154 @@ pub struct LiquidityAdded {
     pub liquidity_minted: u64,
 }
 
+#[event]
+pub struct LiquidityRemoved {
+    pub user: Pubkey,
+    pub pfrt_amount: u64,
+    pub nfrt_amount: u64,
+    pub liquidity_burned: u64,
+}
+
 #[event]
 pub struct SwapEvent {
     pub user: Pubkey,
     pub amount_in: u64,
     pub amount_out: u64,
     pub fee: u64,
+    pub fee_bps: u16,
+    pub protocol_fee: u64,
+    pub is_pfrt_to_nfrt: bool,
+}
+
+#[event]
+pub struct DynamicFeeConfigured {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+    pub volatility_fee_factor_bps: u16,
+    pub oracle_fee_factor_bps: u16,
+    pub fee_oracle: Pubkey,
+}
+
+#[event]
+pub struct SlotLimitsConfigured {
+    pub pool: Pubkey,
+    pub slot_window: u64,
+    pub max_window_price_impact_bps: u16,
+    pub max_window_volume: u64,
+}
+
+#[event]
+pub struct MarketMakerAdded {
+    pub pool: Pubkey,
+    pub market_maker: Pubkey,
+    pub daily_volume_quota: u64,
+}
+
+#[event]
+pub struct MarketMakerRemoved {
+    pub pool: Pubkey,
+    pub market_maker: Pubkey,
+}
+
+#[event]
+pub struct VolumeQuotasConfigured {
+    pub pool: Pubkey,
+    pub volume_quota_tiers: [u64; VOLUME_QUOTA_TIERS],
+}
+
+#[event]
+pub struct UserTierSet {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+}
+
+#[event]
+pub struct LongTermOrderPlaced {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub is_pfrt_to_nfrt: bool,
+    pub amount: u64,
+    pub sell_rate: u128,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct LongTermOrderWithdrawn {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub proceeds: u64,
+}
+
+#[event]
+pub struct LongTermOrderCancelled {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub proceeds: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct LimitOrderPlaced {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub is_pfrt_to_nfrt: bool,
+    pub amount: u64,
+    pub limit_price: u64,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct LimitOrderFilled {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub keeper: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub keeper_fee: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+}
+
+#[event]
+pub struct LimitOrderCancelled {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct SwapRejected {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
     pub is_pfrt_to_nfrt: bool,
+    pub reason: SwapRejectionReason,
+    pub observed_bps: u64,
+    pub limit_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwapRejectionReason {
+    PriceImpact,
+    TwapDeviation,
+    SlotPriceImpact,
+}
+
+#[event]
+pub struct ObservationCardinalityIncreased {
+    pub pool: Pubkey,
+    pub previous: u16,
+    pub next: u16,
 }
 
 #[event]
@@ -572,6 +3780,13 @@ pub struct TradingResumed {
     pub authority: Pubkey,
 }
 
+#[event]
+pub struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub pfrt_amount: u64,
+    pub nfrt_amount: u64,
+}
+
 // Errors
 #[error_code]
 pub enum AmmError {
@@ -593,8 +3808,111 @@ pub enum AmmError {
     SlippageTooHigh,
     #[msg("Insufficient liquidity")]
     InsufficientLiquidity,
+    #[msg("PFRT and NFRT mints must differ")]
+    InvalidMintPair,
+    #[msg("Price impact exceeds max_slippage_bps")]
+    PriceImpactTooHigh,
+    #[msg("Post-trade price deviates too far from TWAP")]
+    TwapDeviationExceeded,
+    #[msg("TWAP deviation bound too high")]
+    DeviationBoundTooHigh,
+    #[msg("Requested lookback is older than the oldest observation")]
+    ObservationTooOld,
+    #[msg("Observation cardinality exceeds buffer capacity")]
+    CardinalityTooHigh,
+    #[msg("Invalid curve parameters")]
+    InvalidCurve,
+    #[msg("Price is outside the curve's supported range")]
+    CurveOutOfRange,
+    #[msg("Protocol fee share exceeds the maximum")]
+    ProtocolFeeTooHigh,
+    #[msg("No protocol fees to collect")]
+    NoProtocolFees,
+    #[msg("Minimum fee exceeds maximum fee")]
+    InvalidFeeBounds,
+    #[msg("Pool's fee oracle account is required")]
+    FeeOracleRequired,
+    #[msg("Account is not the pool's fee oracle")]
+    InvalidFeeOracle,
+    #[msg("Pool-wide price movement in this slot window exceeds the cap")]
+    SlotPriceImpactExceeded,
+    #[msg("Pool-wide volume in this slot window exceeds the cap")]
+    SlotVolumeExceeded,
+    #[msg("Invalid slot-window limits")]
+    InvalidSlotLimits,
+    #[msg("Per-wallet daily volume quota exceeded")]
+    VolumeQuotaExceeded,
+    #[msg("User trade account is required while volume quotas apply")]
+    UserTradeStateRequired,
+    #[msg("Invalid volume quota tier")]
+    InvalidQuotaTier,
+    #[msg("Pool's TWAMM account is required while it has open long-term orders")]
+    TwammAccountRequired,
+    #[msg("Long-term order duration out of range")]
+    InvalidOrderDuration,
+    #[msg("Expiry slot still holds unclosed orders from a past interval")]
+    TwammBucketBusy,
+    #[msg("Long-term orders would sell faster than the curve can absorb")]
+    LongTermOrderTooLarge,
+    #[msg("Long-term orders are waiting for the pool price to return to range")]
+    TwammStalled,
+    #[msg("Signer is not the order's owner")]
+    NotOrderOwner,
+    #[msg("Pool price has not reached the order's limit")]
+    LimitPriceNotReached,
+    #[msg("Limit order has expired")]
+    LimitOrderExpired,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
 }
 
+// Liquidity locked on the first deposit
+const MINIMUM_LIQUIDITY: u64 = 1000;
+
+// Allowed spot price deviation from TWAP
+const DEFAULT_MAX_TWAP_DEVIATION_BPS: u16 = 500; // 5%
+const MAX_TWAP_DEVIATION_BPS: u16 = 5000; // 50%
+
+const MAX_TRADING_FEE_BPS: u16 = 100; // 1% base fee
+const MAX_DYNAMIC_FEE_BPS: u16 = 1000; // 10% ceiling for the dynamic max
+
+// Protocol share of the trading fee is capped at half
+const MAX_PROTOCOL_FEE_SHARE_BPS: u16 = 5000;
+
+// Pool-wide slot-window caps
+const DEFAULT_SLOT_WINDOW: u64 = 1;
+const MAX_SLOT_WINDOW: u64 = 150; // ~1 minute of slots
+const DEFAULT_MAX_WINDOW_PRICE_IMPACT_BPS: u16 = 2000; // 20%
+
+// Per-wallet rolling volume quotas
+const VOLUME_QUOTA_TIERS: usize = 4;
+const SECONDS_PER_DAY: i64 = 86400;
+
+// TWAMM orders expire on hourly boundaries, up to TWAMM_BUCKETS - 1 hours out
+const TWAMM_ORDER_INTERVAL: i64 = 3600;
+const TWAMM_BUCKETS: usize = 120;
+const SELL_RATE_SCALE: u128 = 1_000_000;
+const EARNINGS_PER_RATE_SCALE: u128 = 1_000_000_000_000;
+
+// Limit prices are output per input scaled by 1e6; keepers earn 0.1% of a fill
+const LIMIT_PRICE_SCALE: u128 = 1_000_000;
+const KEEPER_FEE_BPS: u64 = 10;
+
+// Observation ring buffer sizing
+const OBSERVATION_CAPACITY: usize = 200;
+const DEFAULT_OBSERVATION_CARDINALITY: u16 = 16;
+
+// Logit curve fixed point
+const FP_ONE: u128 = 1_000_000_000_000;
+const LN2_FP: u128 = 693_147_180_560;
+const EXP_TERMS: u128 = 30;
+const MAX_LOGIT: i128 = 20; // prices within e^±20 of each other
+const MAX_CURVE_DEPTH_BPS: u16 = 50_000; // b up to 5x deposited reserves
+
+// Exact-output search: initial step is amount_in / 1e10, growing 4x per step
+const INVERSE_STEP_DIVISOR: u64 = 10_000_000_000;
+const MAX_INVERSE_STEPS: usize = 8;
+
 // Integer square root implementation
 trait IntegerSqrt {
     fn integer_sqrt(self) -> Self;
@@ -81,6 +81,8 @@ describe('ziro_delta_amm', () => {
     await program.rpc.addLiquidity(new anchor.BN(1000000000), new anchor.BN(1000000000), {
       accounts: {
         state: state.publicKey,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
         pfrtVault: pfrtVault,
         nfrtVault: nfrtVault,
         userPfrtAccount: userPfrtAccount,
@@ -111,6 +113,8 @@ describe('ziro_delta_amm', () => {
     await program.rpc.swap(new anchor.BN(100000000), new anchor.BN(90000000), {
       accounts: {
         state: state.publicKey,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
         fromVault: pfrtVault,
         toVault: nfrtVault,
         userFromAccount: userPfrtAccount,
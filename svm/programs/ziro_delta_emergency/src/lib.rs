@@ -91,7 +91,7 @@ pub mod ziro_delta_emergency {
         // Record guardian vote
         emergency_vote.guardian = guardian_account.pubkey;
         emergency_vote.vote_time = clock.unix_timestamp;
-        emergency_vote.reason = reason;
+        emergency_vote.reason = reason.clone();
         emergency_vote.severity = severity;
 
         guardian_account.emergency_votes_cast += 1;
@@ -1,5 +1,6 @@
 
 use anchor_lang::prelude::*;
+use ziro_delta_oracle::OracleState;
 
 declare_id!("EGhRAxyabjzJqMTRxmAPbbpY9GWXtQJwyqH59LezZCLw");
 
@@ -15,6 +16,7 @@ pub mod ziro_delta_epoch_manager {
         epoch_manager.epoch_duration = epoch_duration;
         epoch_manager.last_epoch_end = Clock::get()?.slot;
         epoch_manager.authority = ctx.accounts.authority.key();
+        epoch_manager.current_epoch = 0;
         Ok(())
     }
 
@@ -27,20 +29,68 @@ pub mod ziro_delta_epoch_manager {
         );
 
         epoch_manager.last_epoch_end = Clock::get()?.slot;
+        epoch_manager.current_epoch += 1;
 
         Ok(())
     }
 
+    /// Settle the current epoch and record the payout ratio consumed by
+    /// redemptions. The authority only chooses when to settle: the rate is
+    /// the funding-rate oracle's, the same feed the AMM prices against, and
+    /// must have been updated within MAX_ORACLE_AGE of settlement.
     pub fn settle_epoch(ctx: Context<SettleEpoch>) -> Result<()> {
-        // Settle the epoch
+        let epoch_manager = &ctx.accounts.epoch_manager;
+        let oracle_state = &ctx.accounts.oracle_state;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.slot >= epoch_manager.last_epoch_end + epoch_manager.epoch_duration,
+            ZiroDeltaError::EpochNotEnded
+        );
+        require!(
+            clock.unix_timestamp - oracle_state.last_update_time <= MAX_ORACLE_AGE,
+            ZiroDeltaError::StaleOracle
+        );
+
+        let funding_rate = oracle_state.effective_funding_rate() as i64;
+        require!(
+            funding_rate.unsigned_abs() <= MAX_FUNDING_RATE_BPS as u64,
+            ZiroDeltaError::InvalidFundingRate
+        );
+
+        let pfrt_payout_bps = calculate_pfrt_payout_bps(funding_rate);
+
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.epoch_manager = epoch_manager.key();
+        settlement.epoch_id = epoch_manager.current_epoch;
+        settlement.funding_rate = funding_rate;
+        settlement.pfrt_payout_bps = pfrt_payout_bps;
+        settlement.nfrt_payout_bps = BPS_DENOMINATOR - pfrt_payout_bps;
+        settlement.settled_at = clock.unix_timestamp;
+
+        emit!(EpochSettled {
+            epoch_manager: settlement.epoch_manager,
+            epoch_id: settlement.epoch_id,
+            funding_rate,
+            pfrt_payout_bps: settlement.pfrt_payout_bps,
+            nfrt_payout_bps: settlement.nfrt_payout_bps,
+        });
 
         Ok(())
     }
 }
 
+// Positive funding pays PFRT, negative funding pays NFRT. A rate of
+// MAX_FUNDING_RATE_BPS in either direction pays the whole pair to one leg.
+pub fn calculate_pfrt_payout_bps(funding_rate: i64) -> u16 {
+    let half = (BPS_DENOMINATOR / 2) as i64;
+    let payout = half + funding_rate * half / MAX_FUNDING_RATE_BPS;
+    payout.clamp(0, BPS_DENOMINATOR as i64) as u16
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = user, space = 8 + 8 + 8 + 32)]
+    #[account(init, payer = user, space = 8 + 8 + 8 + 32 + 8)]
     pub epoch_manager: Account<'info, EpochManager>,
     pub authority: AccountInfo<'info>,
     #[account(mut)]
@@ -59,7 +109,23 @@ pub struct StartNewEpoch<'info> {
 pub struct SettleEpoch<'info> {
     #[account(mut, has_one = authority)]
     pub epoch_manager: Account<'info, EpochManager>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<EpochSettlement>(),
+        seeds = [
+            b"settlement",
+            epoch_manager.key().as_ref(),
+            epoch_manager.current_epoch.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub settlement: Account<'info, EpochSettlement>,
+    #[account(seeds = [b"oracle_state"], bump, seeds::program = ziro_delta_oracle::ID)]
+    pub oracle_state: Account<'info, OracleState>,
+    #[account(mut)]
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
@@ -67,10 +133,39 @@ pub struct EpochManager {
     pub epoch_duration: u64,
     pub last_epoch_end: u64,
     pub authority: Pubkey,
+    pub current_epoch: u64,
+}
+
+/// Settlement record for a single epoch, read by the minting program
+#[account]
+pub struct EpochSettlement {
+    pub epoch_manager: Pubkey,
+    pub epoch_id: u64,
+    pub funding_rate: i64,
+    pub pfrt_payout_bps: u16,
+    pub nfrt_payout_bps: u16,
+    pub settled_at: i64,
+}
+
+#[event]
+pub struct EpochSettled {
+    pub epoch_manager: Pubkey,
+    pub epoch_id: u64,
+    pub funding_rate: i64,
+    pub pfrt_payout_bps: u16,
+    pub nfrt_payout_bps: u16,
 }
 
 #[error_code]
 pub enum ZiroDeltaError {
     #[msg("Epoch has not ended yet")]
     EpochNotEnded,
+    #[msg("Funding rate out of bounds")]
+    InvalidFundingRate,
+    #[msg("Oracle funding rate is stale")]
+    StaleOracle,
 }
+
+pub const BPS_DENOMINATOR: u16 = 10000;
+pub const MAX_FUNDING_RATE_BPS: i64 = 1000; // 10%
+pub const MAX_ORACLE_AGE: i64 = 3600; // seconds
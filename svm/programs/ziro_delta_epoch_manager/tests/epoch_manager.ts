@@ -1,6 +1,7 @@
 
 const anchor = require('@project-serum/anchor');
 const { SystemProgram } = anchor.web3;
+const { assert } = require('chai');
 
 describe('ziro_delta_epoch_manager', () => {
 
@@ -9,6 +10,7 @@ describe('ziro_delta_epoch_manager', () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.ZiroDeltaEpochManager;
+  const oracleProgram = anchor.workspace.ZiroDeltaOracle;
 
   const epochManager = anchor.web3.Keypair.generate();
   const authority = anchor.web3.Keypair.generate();
@@ -39,12 +41,58 @@ describe('ziro_delta_epoch_manager', () => {
   });
 
   it('Settles an epoch', async () => {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(authority.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+    // The settled rate comes from the funding-rate oracle
+    const [oracleState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("oracle_state")],
+      oracleProgram.programId
+    );
+    await oracleProgram.rpc.initialize(3, new anchor.BN(900), 500, {
+      accounts: {
+        state: oracleState,
+        authority: authority.publicKey,
+        user: authority.publicKey,
+        systemProgram: SystemProgram.programId,
+      },
+      signers: [authority],
+    });
+    await oracleProgram.rpc.emergencyUpdateFundingRate(500, {
+      accounts: {
+        state: oracleState,
+        authority: authority.publicKey,
+      },
+      signers: [authority],
+    });
+
+    // Wait for the epoch to end
+    await new Promise(resolve => setTimeout(resolve, 2000));
+
+    const manager = await program.account.epochManager.fetch(epochManager.publicKey);
+    const [settlement] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("settlement"),
+        epochManager.publicKey.toBuffer(),
+        manager.currentEpoch.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
     await program.rpc.settleEpoch({
       accounts: {
         epochManager: epochManager.publicKey,
+        settlement,
+        oracleState,
         authority: authority.publicKey,
+        systemProgram: SystemProgram.programId,
       },
       signers: [authority],
     });
+
+    const record = await program.account.epochSettlement.fetch(settlement);
+    assert.equal(record.pfrtPayoutBps, 7500);
+    assert.equal(record.nfrtPayoutBps, 2500);
   });
 });
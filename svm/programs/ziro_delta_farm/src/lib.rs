@@ -0,0 +1,641 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use ziro_delta_amm::{AmmState, Factory};
+
+declare_id!("BCZvQo6FWxXaWR4gHdQmGAJ22JQ33c7sTkiMsGcZMzXj");
+
+/// Liquidity mining for AMM LPs. LP tokens staked in a pool's farm earn up
+/// to MAX_REWARD_TOKENS reward streams at once (ZDLT and any other mint),
+/// each emitted at a governance-set rate over time-bounded campaigns and
+/// shared out pro rata with a reward-per-share accumulator.
+#[program]
+pub mod ziro_delta_farm {
+    use super::*;
+
+    /// Create the farm for an AMM pool's LP token (AMM governance only)
+    pub fn initialize_farm(ctx: Context<InitializeFarm>) -> Result<()> {
+        let farm = &mut ctx.accounts.farm;
+        farm.pool = ctx.accounts.pool.key();
+        farm.lp_mint = ctx.accounts.lp_mint.key();
+        farm.lp_vault = ctx.accounts.lp_vault.key();
+        farm.total_staked = 0;
+        farm.reward_count = 0;
+        farm.rewards = [RewardInfo::default(); MAX_REWARD_TOKENS];
+        farm.bump = ctx.bumps.farm;
+
+        emit!(FarmInitialized {
+            farm: farm.key(),
+            pool: farm.pool,
+            lp_mint: farm.lp_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Register a reward token and create its vault (AMM governance only)
+    pub fn add_reward_token(ctx: Context<AddRewardToken>) -> Result<()> {
+        let farm = &mut ctx.accounts.farm;
+        let reward_mint = ctx.accounts.reward_mint.key();
+        require!(
+            (farm.reward_count as usize) < MAX_REWARD_TOKENS,
+            FarmError::TooManyRewardTokens
+        );
+        require!(
+            !farm.active_rewards().iter().any(|reward| reward.reward_mint == reward_mint),
+            FarmError::DuplicateRewardToken
+        );
+
+        let reward_index = farm.reward_count;
+        farm.rewards[reward_index as usize] = RewardInfo {
+            reward_mint,
+            reward_vault: ctx.accounts.reward_vault.key(),
+            ..RewardInfo::default()
+        };
+        farm.reward_count += 1;
+
+        emit!(RewardTokenAdded {
+            farm: farm.key(),
+            reward_mint,
+            reward_index,
+        });
+
+        Ok(())
+    }
+
+    /// Fund and start a campaign emitting `emission_per_second` of a reward
+    /// token from `start_time` (no earlier than now) until `end_time`. A
+    /// token's next campaign can start once its current one has ended, and
+    /// is funded first from emissions that went unclaimed while nothing was
+    /// staked (AMM governance only)
+    pub fn start_campaign(
+        ctx: Context<StartCampaign>,
+        reward_index: u8,
+        emission_per_second: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let farm = &mut ctx.accounts.farm;
+        farm.update_rewards(now)?;
+
+        let start_time = std::cmp::max(start_time, now);
+        require!(
+            emission_per_second > 0 && end_time > start_time,
+            FarmError::InvalidCampaign
+        );
+
+        let reward = &mut farm.rewards[reward_index as usize];
+        require!(now >= reward.end_time, FarmError::CampaignActive);
+
+        let funding = emission_per_second
+            .checked_mul((end_time - start_time) as u64)
+            .ok_or(FarmError::Overflow)?;
+        let rolled_over = std::cmp::min(reward.unallocated, funding);
+        reward.unallocated -= rolled_over;
+        reward.emission_per_second = emission_per_second;
+        reward.start_time = start_time;
+        reward.end_time = end_time;
+        reward.last_update_time = start_time;
+
+        let reward_mint = &ctx.accounts.reward_mint;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_account.to_account_info(),
+                    mint: reward_mint.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.governance.to_account_info(),
+                },
+            ),
+            funding - rolled_over,
+            reward_mint.decimals,
+        )?;
+
+        emit!(CampaignStarted {
+            farm: farm.key(),
+            reward_mint: reward_mint.key(),
+            reward_index,
+            emission_per_second,
+            start_time,
+            end_time,
+            rolled_over,
+        });
+
+        Ok(())
+    }
+
+    /// Stake LP tokens, settling rewards earned on the existing stake first
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        require!(amount > 0, FarmError::InvalidAmount);
+
+        let farm = &mut ctx.accounts.farm;
+        farm.update_rewards(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.position;
+        if position.owner == Pubkey::default() {
+            position.farm = farm.key();
+            position.owner = ctx.accounts.user.key();
+            position.bump = ctx.bumps.position;
+        }
+        position.settle(farm)?;
+
+        let lp_mint = &ctx.accounts.lp_mint;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_lp_account.to_account_info(),
+                    mint: lp_mint.to_account_info(),
+                    to: ctx.accounts.lp_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+            lp_mint.decimals,
+        )?;
+
+        position.amount = position.amount.checked_add(amount).ok_or(FarmError::Overflow)?;
+        farm.total_staked = farm.total_staked.checked_add(amount).ok_or(FarmError::Overflow)?;
+
+        emit!(Staked {
+            farm: farm.key(),
+            user: position.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw staked LP tokens; earned rewards stay claimable
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        require!(amount > 0, FarmError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.position.amount,
+            FarmError::InsufficientStake
+        );
+
+        let farm = &mut ctx.accounts.farm;
+        farm.update_rewards(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.position;
+        position.settle(farm)?;
+        position.amount -= amount;
+        farm.total_staked -= amount;
+
+        transfer_from_farm(
+            &ctx.accounts.token_program,
+            &ctx.accounts.lp_vault,
+            &ctx.accounts.lp_mint,
+            &ctx.accounts.user_lp_account,
+            farm,
+            amount,
+        )?;
+
+        emit!(Unstaked {
+            farm: farm.key(),
+            user: position.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Claim everything earned in one reward token
+    pub fn claim(ctx: Context<Claim>, reward_index: u8) -> Result<()> {
+        let farm = &mut ctx.accounts.farm;
+        farm.update_rewards(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.position;
+        position.settle(farm)?;
+
+        let amount = position.rewards_owed[reward_index as usize];
+        require!(amount > 0, FarmError::NothingToClaim);
+        position.rewards_owed[reward_index as usize] = 0;
+
+        transfer_from_farm(
+            &ctx.accounts.token_program,
+            &ctx.accounts.reward_vault,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.user_reward_account,
+            farm,
+            amount,
+        )?;
+
+        emit!(RewardClaimed {
+            farm: farm.key(),
+            user: position.owner,
+            reward_mint: ctx.accounts.reward_mint.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper functions
+
+// Transfer out of a farm-owned vault, signed by the farm PDA
+fn transfer_from_farm<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    farm: &Account<'info, Farm>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"farm".as_ref(), farm.pool.as_ref(), &[farm.bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// Account structs
+#[derive(Accounts)]
+pub struct InitializeFarm<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        seeds::program = ziro_delta_amm::ID,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+
+    #[account(has_one = lp_mint @ FarmError::PoolMismatch)]
+    pub pool: Box<Account<'info, AmmState>>,
+
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = governance,
+        space = 8 + std::mem::size_of::<Farm>(),
+        seeds = [b"farm", pool.key().as_ref()],
+        bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(
+        init,
+        payer = governance,
+        token::mint = lp_mint,
+        token::authority = farm,
+        seeds = [b"lp_vault", farm.key().as_ref()],
+        bump
+    )]
+    pub lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AddRewardToken<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        seeds::program = ziro_delta_amm::ID,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = governance,
+        token::mint = reward_mint,
+        token::authority = farm,
+        seeds = [b"reward_vault", farm.key().as_ref(), reward_mint.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(reward_index: u8)]
+pub struct StartCampaign<'info> {
+    #[account(
+        seeds = [b"factory"],
+        bump = factory.bump,
+        seeds::program = ziro_delta_amm::ID,
+        has_one = governance
+    )]
+    pub factory: Account<'info, Factory>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        constraint = reward_index < farm.reward_count @ FarmError::InvalidRewardIndex
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(address = farm.rewards[reward_index as usize].reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, address = farm.rewards[reward_index as usize].reward_vault)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub funder_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub governance: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        has_one = lp_mint,
+        has_one = lp_vault
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<StakePosition>(),
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, StakePosition>>,
+
+    #[account(mut)]
+    pub user_lp_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        has_one = lp_mint,
+        has_one = lp_vault
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub lp_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Box<Account<'info, StakePosition>>,
+
+    #[account(mut)]
+    pub user_lp_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(reward_index: u8)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        seeds = [b"farm", farm.pool.as_ref()],
+        bump = farm.bump,
+        constraint = reward_index < farm.reward_count @ FarmError::InvalidRewardIndex
+    )]
+    pub farm: Box<Account<'info, Farm>>,
+
+    #[account(address = farm.rewards[reward_index as usize].reward_mint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut, address = farm.rewards[reward_index as usize].reward_vault)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", farm.key().as_ref(), user.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Box<Account<'info, StakePosition>>,
+
+    #[account(mut)]
+    pub user_reward_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Data structures
+#[account]
+pub struct Farm {
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub lp_vault: Pubkey,
+    pub total_staked: u64,
+    pub reward_count: u8,
+    pub rewards: [RewardInfo; MAX_REWARD_TOKENS],
+    pub bump: u8,
+}
+
+/// One reward stream: its current campaign, the reward earned per staked
+/// LP token since the farm opened, scaled by REWARD_PER_SHARE_SCALE, and
+/// emissions left in the vault while nothing was staked
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardInfo {
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+    pub emission_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub last_update_time: i64,
+    pub reward_per_share: u128,
+    pub unallocated: u64,
+}
+
+impl Farm {
+    fn active_rewards(&self) -> &[RewardInfo] {
+        &self.rewards[..self.reward_count as usize]
+    }
+
+    // Accrue every reward stream up to `now`, clamped to its campaign.
+    // Emissions while nothing is staked are set aside for the next campaign
+    fn update_rewards(&mut self, now: i64) -> Result<()> {
+        let total_staked = self.total_staked as u128;
+        for reward in self.rewards[..self.reward_count as usize].iter_mut() {
+            let accrue_until = std::cmp::min(now, reward.end_time);
+            if accrue_until <= reward.last_update_time {
+                continue;
+            }
+
+            let emitted = reward.emission_per_second as u128
+                * (accrue_until - reward.last_update_time) as u128;
+            match emitted
+                .checked_mul(REWARD_PER_SHARE_SCALE)
+                .ok_or(FarmError::Overflow)?
+                .checked_div(total_staked)
+            {
+                Some(per_share) => {
+                    reward.reward_per_share = reward
+                        .reward_per_share
+                        .checked_add(per_share)
+                        .ok_or(FarmError::Overflow)?;
+                }
+                None => {
+                    reward.unallocated = u64::try_from(emitted)
+                        .ok()
+                        .and_then(|emitted| reward.unallocated.checked_add(emitted))
+                        .ok_or(FarmError::Overflow)?;
+                }
+            }
+            reward.last_update_time = accrue_until;
+        }
+
+        Ok(())
+    }
+}
+
+#[account]
+pub struct StakePosition {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reward_per_share_paid: [u128; MAX_REWARD_TOKENS],
+    pub rewards_owed: [u64; MAX_REWARD_TOKENS],
+    pub bump: u8,
+}
+
+impl StakePosition {
+    // Credit what the current stake earned since it was last settled
+    fn settle(&mut self, farm: &Farm) -> Result<()> {
+        for (index, reward) in farm.active_rewards().iter().enumerate() {
+            let earned = self.amount as u128
+                * (reward.reward_per_share - self.reward_per_share_paid[index])
+                / REWARD_PER_SHARE_SCALE;
+            let earned = u64::try_from(earned).map_err(|_| FarmError::Overflow)?;
+            self.rewards_owed[index] = self.rewards_owed[index]
+                .checked_add(earned)
+                .ok_or(FarmError::Overflow)?;
+            self.reward_per_share_paid[index] = reward.reward_per_share;
+        }
+
+        Ok(())
+    }
+}
+
+// Events
+#[event]
+pub struct FarmInitialized {
+    pub farm: Pubkey,
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+#[event]
+pub struct RewardTokenAdded {
+    pub farm: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_index: u8,
+}
+
+#[event]
+pub struct CampaignStarted {
+    pub farm: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_index: u8,
+    pub emission_per_second: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub rolled_over: u64,
+}
+
+#[event]
+pub struct Staked {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct Unstaked {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RewardClaimed {
+    pub farm: Pubkey,
+    pub user: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+}
+
+// Errors
+#[error_code]
+pub enum FarmError {
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("LP mint does not belong to the pool")]
+    PoolMismatch,
+    #[msg("Farm already has the maximum number of reward tokens")]
+    TooManyRewardTokens,
+    #[msg("Reward token already registered")]
+    DuplicateRewardToken,
+    #[msg("Invalid reward index")]
+    InvalidRewardIndex,
+    #[msg("Campaign must emit a positive amount over a future window")]
+    InvalidCampaign,
+    #[msg("Reward token's current campaign has not ended")]
+    CampaignActive,
+    #[msg("Unstake amount exceeds the staked balance")]
+    InsufficientStake,
+    #[msg("No rewards to claim")]
+    NothingToClaim,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
+
+// Concurrent reward streams per farm
+const MAX_REWARD_TOKENS: usize = 4;
+
+// Fixed point for reward_per_share
+const REWARD_PER_SHARE_SCALE: u128 = 1_000_000_000_000;
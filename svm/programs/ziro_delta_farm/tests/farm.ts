@@ -0,0 +1,415 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import { ZiroDeltaFarm } from "../target/types/ziro_delta_farm";
+import { ZiroDeltaAmm } from "../target/types/ziro_delta_amm";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { assert } from "chai";
+
+describe("ziro-delta-farm", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.ZiroDeltaFarm as Program<ZiroDeltaFarm>;
+  const ammProgram = anchor.workspace.ZiroDeltaAmm as Program<ZiroDeltaAmm>;
+
+  const findAddress = async (seeds: Buffer[], programId: anchor.web3.PublicKey) =>
+    (await anchor.web3.PublicKey.findProgramAddress(seeds, programId))[0];
+
+  const owner = anchor.web3.Keypair.generate();
+  const governance = anchor.web3.Keypair.generate();
+  const user = anchor.web3.Keypair.generate();
+
+  const ONE = 1_000_000_000;
+  const EMISSION = ONE;
+
+  let lpToken: Token;
+  let zdltMint: Token;
+  let bonusMint: Token;
+
+  let factory: anchor.web3.PublicKey;
+  let pool: anchor.web3.PublicKey;
+  let farm: anchor.web3.PublicKey;
+  let lpVault: anchor.web3.PublicKey;
+  let zdltVault: anchor.web3.PublicKey;
+  let bonusVault: anchor.web3.PublicKey;
+
+  let ownerLpAccount: anchor.web3.PublicKey;
+  let userLpAccount: anchor.web3.PublicKey;
+  let ownerZdltAccount: anchor.web3.PublicKey;
+  let userZdltAccount: anchor.web3.PublicKey;
+  let governanceZdltAccount: anchor.web3.PublicKey;
+  let governanceBonusAccount: anchor.web3.PublicKey;
+
+  const positionOf = (wallet: anchor.web3.PublicKey) =>
+    findAddress([Buffer.from("stake"), farm.toBuffer(), wallet.toBuffer()], program.programId);
+
+  const stakeAccounts = async (wallet: anchor.web3.Keypair, lpAccount: anchor.web3.PublicKey) => ({
+    farm,
+    lpMint: lpToken.publicKey,
+    lpVault,
+    position: await positionOf(wallet.publicKey),
+    userLpAccount: lpAccount,
+    user: wallet.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const claimAccounts = async (
+    wallet: anchor.web3.Keypair,
+    rewardMint: Token,
+    rewardVault: anchor.web3.PublicKey,
+    rewardAccount: anchor.web3.PublicKey
+  ) => ({
+    farm,
+    rewardMint: rewardMint.publicKey,
+    rewardVault,
+    position: await positionOf(wallet.publicKey),
+    userRewardAccount: rewardAccount,
+    user: wallet.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  before(async () => {
+    for (const wallet of [owner, governance, user]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(wallet.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL)
+      );
+    }
+
+    const pfrtMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    const nfrtMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    zdltMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    bonusMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+
+    const ownerPfrtAccount = await pfrtMint.createAccount(owner.publicKey);
+    const ownerNfrtAccount = await nfrtMint.createAccount(owner.publicKey);
+    await pfrtMint.mintTo(ownerPfrtAccount, owner, [], 100_000 * ONE);
+    await nfrtMint.mintTo(ownerNfrtAccount, owner, [], 100_000 * ONE);
+
+    ownerZdltAccount = await zdltMint.createAccount(owner.publicKey);
+    userZdltAccount = await zdltMint.createAccount(user.publicKey);
+    governanceZdltAccount = await zdltMint.createAccount(governance.publicKey);
+    governanceBonusAccount = await bonusMint.createAccount(governance.publicKey);
+    await zdltMint.mintTo(governanceZdltAccount, owner, [], 1_000 * ONE);
+    await bonusMint.mintTo(governanceBonusAccount, owner, [], 1_000 * ONE);
+
+    // AMM pool whose LP token the farm stakes
+    factory = await findAddress([Buffer.from("factory")], ammProgram.programId);
+    pool = await findAddress(
+      [Buffer.from("pool"), pfrtMint.publicKey.toBuffer(), nfrtMint.publicKey.toBuffer()],
+      ammProgram.programId
+    );
+    const poolAuthority = await findAddress([Buffer.from("authority"), pool.toBuffer()], ammProgram.programId);
+    const pfrtVault = await findAddress([Buffer.from("pfrt_vault"), pool.toBuffer()], ammProgram.programId);
+    const nfrtVault = await findAddress([Buffer.from("nfrt_vault"), pool.toBuffer()], ammProgram.programId);
+    const lpMint = await findAddress([Buffer.from("lp_mint"), pool.toBuffer()], ammProgram.programId);
+    const observations = await findAddress([Buffer.from("observations"), pool.toBuffer()], ammProgram.programId);
+
+    await ammProgram.methods
+      .initializeFactory(governance.publicKey)
+      .accounts({
+        factory,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await ammProgram.methods
+      .createPool(
+        new anchor.BN(0),
+        30,
+        new anchor.BN(10_000 * ONE),
+        new anchor.BN(1_000_000 * ONE),
+        500,
+        { constantProduct: {} }
+      )
+      .accounts({
+        factory,
+        poolListing: await findAddress(
+          [Buffer.from("pool_listing"), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+          ammProgram.programId
+        ),
+        state: pool,
+        authority: poolAuthority,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        pfrtVault,
+        nfrtVault,
+        lpMint,
+        observations,
+        governance: governance.publicKey,
+        user: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner, governance])
+      .rpc();
+
+    lpToken = new Token(provider.connection, lpMint, TOKEN_PROGRAM_ID, owner);
+    ownerLpAccount = await lpToken.createAccount(owner.publicKey);
+    userLpAccount = await lpToken.createAccount(user.publicKey);
+
+    await ammProgram.methods
+      .addLiquidity(new anchor.BN(100_000 * ONE), new anchor.BN(100_000 * ONE), new anchor.BN(0))
+      .accounts({
+        state: pool,
+        authority: poolAuthority,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        lpMint,
+        observations,
+        pfrtVault,
+        nfrtVault,
+        userPfrtAccount: ownerPfrtAccount,
+        userNfrtAccount: ownerNfrtAccount,
+        userLpAccount: ownerLpAccount,
+        user: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    // The user LPs a quarter of what the owner does
+    await lpToken.transfer(ownerLpAccount, userLpAccount, owner, [], 20_000 * ONE);
+
+    farm = await findAddress([Buffer.from("farm"), pool.toBuffer()], program.programId);
+    lpVault = await findAddress([Buffer.from("lp_vault"), farm.toBuffer()], program.programId);
+    zdltVault = await findAddress(
+      [Buffer.from("reward_vault"), farm.toBuffer(), zdltMint.publicKey.toBuffer()],
+      program.programId
+    );
+    bonusVault = await findAddress(
+      [Buffer.from("reward_vault"), farm.toBuffer(), bonusMint.publicKey.toBuffer()],
+      program.programId
+    );
+  });
+
+  it("Creates a farm with ZDLT and bonus reward tokens", async () => {
+    await program.methods
+      .initializeFarm()
+      .accounts({
+        factory,
+        pool,
+        lpMint: lpToken.publicKey,
+        farm,
+        lpVault,
+        governance: governance.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([governance])
+      .rpc();
+
+    for (const [rewardMint, rewardVault] of [
+      [zdltMint, zdltVault],
+      [bonusMint, bonusVault],
+    ] as [Token, anchor.web3.PublicKey][]) {
+      await program.methods
+        .addRewardToken()
+        .accounts({
+          factory,
+          farm,
+          rewardMint: rewardMint.publicKey,
+          rewardVault,
+          governance: governance.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([governance])
+        .rpc();
+    }
+
+    const farmState = await program.account.farm.fetch(farm);
+    assert.equal(farmState.rewardCount, 2);
+    assert.ok(farmState.lpMint.equals(lpToken.publicKey));
+    assert.ok(farmState.rewards[0].rewardMint.equals(zdltMint.publicKey));
+    assert.ok(farmState.rewards[1].rewardMint.equals(bonusMint.publicKey));
+  });
+
+  it("Splits emissions pro rata between stakers", async () => {
+    await program.methods
+      .stake(new anchor.BN(60_000 * ONE))
+      .accounts({ ...(await stakeAccounts(owner, ownerLpAccount)), systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([owner])
+      .rpc();
+    await program.methods
+      .stake(new anchor.BN(20_000 * ONE))
+      .accounts({ ...(await stakeAccounts(user, userLpAccount)), systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([user])
+      .rpc();
+
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .startCampaign(0, new anchor.BN(EMISSION), new anchor.BN(0), new anchor.BN(now + 10))
+      .accounts({
+        factory,
+        farm,
+        rewardMint: zdltMint.publicKey,
+        rewardVault: zdltVault,
+        funderAccount: governanceZdltAccount,
+        governance: governance.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([governance])
+      .rpc();
+    const funded = (await zdltMint.getAccountInfo(zdltVault)).amount;
+
+    // A live campaign cannot be replaced
+    try {
+      await program.methods
+        .startCampaign(0, new anchor.BN(EMISSION), new anchor.BN(0), new anchor.BN(now + 100))
+        .accounts({
+          factory,
+          farm,
+          rewardMint: zdltMint.publicKey,
+          rewardVault: zdltVault,
+          funderAccount: governanceZdltAccount,
+          governance: governance.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([governance])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "CampaignActive");
+    }
+
+    // Let the campaign run out
+    await new Promise((resolve) => setTimeout(resolve, 15000));
+
+    await program.methods
+      .claim(0)
+      .accounts(await claimAccounts(owner, zdltMint, zdltVault, ownerZdltAccount))
+      .signers([owner])
+      .rpc();
+    await program.methods
+      .claim(0)
+      .accounts(await claimAccounts(user, zdltMint, zdltVault, userZdltAccount))
+      .signers([user])
+      .rpc();
+
+    const ownerRewards = (await zdltMint.getAccountInfo(ownerZdltAccount)).amount;
+    const userRewards = (await zdltMint.getAccountInfo(userZdltAccount)).amount;
+    const dust = (await zdltMint.getAccountInfo(zdltVault)).amount;
+    assert.ok(userRewards.gtn(0));
+    assert.ok(ownerRewards.eq(userRewards.muln(3)));
+    // Everything funded was paid out but rounding dust
+    assert.ok(ownerRewards.add(userRewards).add(dust).eq(funded));
+    assert.ok(dust.ltn(1000));
+
+    try {
+      await program.methods
+        .claim(0)
+        .accounts(await claimAccounts(user, zdltMint, zdltVault, userZdltAccount))
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "NothingToClaim");
+    }
+  });
+
+  it("Runs a second reward token's campaign alongside and keeps rewards across unstake", async () => {
+    const userBonusAccount = await bonusMint.createAccount(user.publicKey);
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .startCampaign(1, new anchor.BN(EMISSION), new anchor.BN(0), new anchor.BN(now + 5))
+      .accounts({
+        factory,
+        farm,
+        rewardMint: bonusMint.publicKey,
+        rewardVault: bonusVault,
+        funderAccount: governanceBonusAccount,
+        governance: governance.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([governance])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 7000));
+
+    try {
+      await program.methods
+        .unstake(new anchor.BN(20_000 * ONE + 1))
+        .accounts(await stakeAccounts(user, userLpAccount))
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InsufficientStake");
+    }
+
+    await program.methods
+      .unstake(new anchor.BN(20_000 * ONE))
+      .accounts(await stakeAccounts(user, userLpAccount))
+      .signers([user])
+      .rpc();
+
+    const lp = await lpToken.getAccountInfo(userLpAccount);
+    assert.equal(lp.amount.toString(), new anchor.BN(20_000 * ONE).toString());
+    const position = await program.account.stakePosition.fetch(await positionOf(user.publicKey));
+    assert.equal(position.amount.toNumber(), 0);
+
+    await program.methods
+      .claim(1)
+      .accounts(await claimAccounts(user, bonusMint, bonusVault, userBonusAccount))
+      .signers([user])
+      .rpc();
+    assert.ok((await bonusMint.getAccountInfo(userBonusAccount)).amount.gtn(0));
+
+    try {
+      await program.methods
+        .claim(2)
+        .accounts(await claimAccounts(user, bonusMint, bonusVault, userBonusAccount))
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidRewardIndex");
+    }
+  });
+
+  it("Rolls emissions from an empty farm into the next campaign", async () => {
+    await program.methods
+      .unstake(new anchor.BN(60_000 * ONE))
+      .accounts(await stakeAccounts(owner, ownerLpAccount))
+      .signers([owner])
+      .rpc();
+
+    const campaignAccounts = {
+      factory,
+      farm,
+      rewardMint: zdltMint.publicKey,
+      rewardVault: zdltVault,
+      funderAccount: governanceZdltAccount,
+      governance: governance.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+    let now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .startCampaign(0, new anchor.BN(EMISSION), new anchor.BN(0), new anchor.BN(now + 3))
+      .accounts(campaignAccounts)
+      .signers([governance])
+      .rpc();
+    const empty = (await program.account.farm.fetch(farm)).rewards[0];
+    const stranded = empty.endTime.sub(empty.startTime).muln(EMISSION);
+
+    // Nobody is staked while it runs
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+
+    const funderBefore = (await zdltMint.getAccountInfo(governanceZdltAccount)).amount;
+    now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .startCampaign(0, new anchor.BN(EMISSION), new anchor.BN(0), new anchor.BN(now + 20))
+      .accounts(campaignAccounts)
+      .signers([governance])
+      .rpc();
+    const funderAfter = (await zdltMint.getAccountInfo(governanceZdltAccount)).amount;
+
+    const next = (await program.account.farm.fetch(farm)).rewards[0];
+    const funding = next.endTime.sub(next.startTime).muln(EMISSION);
+    assert.ok(funderBefore.sub(funderAfter).eq(funding.sub(stranded)));
+    assert.equal(next.unallocated.toNumber(), 0);
+  });
+});
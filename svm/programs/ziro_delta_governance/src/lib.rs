@@ -1,5 +1,8 @@
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
 declare_id!("FtQkWEAekK8hcjvFxLS5uXwkkidbDdpqowX88VGZfAvM");
 
@@ -7,64 +10,129 @@ declare_id!("FtQkWEAekK8hcjvFxLS5uXwkkidbDdpqowX88VGZfAvM");
 pub mod ziro_delta_governance {
     use super::*;
 
+    /// Votes are cast by escrowing `governance_mint` tokens. A proposal
+    /// passes with more weight for than against once at least
+    /// `quorum_fraction` percent of the token supply has voted.
     pub fn initialize(
         ctx: Context<Initialize>,
         voting_delay: u64,
         voting_period: u64,
         quorum_fraction: u64,
     ) -> Result<()> {
+        require!(
+            quorum_fraction > 0 && quorum_fraction <= 100,
+            ZiroDeltaError::InvalidQuorum
+        );
+
         let governance = &mut ctx.accounts.governance;
         governance.voting_delay = voting_delay;
         governance.voting_period = voting_period;
         governance.quorum_fraction = quorum_fraction;
         governance.authority = ctx.accounts.authority.key();
+        governance.governance_mint = ctx.accounts.governance_mint.key();
         Ok(())
     }
 
+    /// Open a proposal. `instruction`, if any, is invoked when the proposal
+    /// passes, signed by the governance authority PDA that other programs
+    /// accept as governance.
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
         title: String,
         description: String,
+        instruction: Option<ProposalInstruction>,
     ) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         proposal.title = title;
         proposal.description = description;
         proposal.proposer = ctx.accounts.proposer.key();
-        proposal.start_block = Clock::get()?.slot;
+        proposal.start_block = Clock::get()?.slot + ctx.accounts.governance.voting_delay;
         proposal.end_block = proposal.start_block + ctx.accounts.governance.voting_period;
+        proposal.executed = false;
+        proposal.instruction = instruction;
         Ok(())
     }
 
+    /// Vote once per proposal with `amount` governance tokens, which stay
+    /// in escrow until voting ends.
     pub fn vote(
         ctx: Context<Vote>,
         vote: VoteOption,
+        amount: u64,
     ) -> Result<()> {
-        let proposal = &mut ctx.accounts.proposal;
-        let voter = &mut ctx.accounts.voter;
-
+        let slot = Clock::get()?.slot;
+        require!(amount > 0, ZiroDeltaError::InvalidAmount);
         require!(
-            Clock::get()?.slot >= proposal.start_block,
+            slot >= ctx.accounts.proposal.start_block,
             ZiroDeltaError::VotingNotStarted
         );
         require!(
-            Clock::get()?.slot < proposal.end_block,
+            slot < ctx.accounts.proposal.end_block,
             ZiroDeltaError::VotingEnded
         );
 
+        // Weigh the vote by what the escrow received, net of any transfer fee
+        let escrow_before = ctx.accounts.vote_escrow.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.voter_token_account.to_account_info(),
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.vote_escrow.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.governance_mint.decimals,
+        )?;
+        ctx.accounts.vote_escrow.reload()?;
+        let weight = ctx.accounts.vote_escrow.amount - escrow_before;
+
+        let proposal = &mut ctx.accounts.proposal;
+        let voter = &mut ctx.accounts.voter;
         voter.proposal = proposal.key();
         voter.voter = ctx.accounts.user.key();
         voter.vote = vote;
+        voter.weight = weight;
 
         match vote {
-            VoteOption::For => proposal.for_votes += 1,
-            VoteOption::Against => proposal.against_votes += 1,
+            VoteOption::For => proposal.for_votes += weight,
+            VoteOption::Against => proposal.against_votes += weight,
         }
 
         Ok(())
     }
 
+    /// Return a vote's escrowed tokens once voting on its proposal has ended.
+    pub fn withdraw_votes(ctx: Context<WithdrawVotes>) -> Result<()> {
+        require!(
+            Clock::get()?.slot >= ctx.accounts.proposal.end_block,
+            ZiroDeltaError::VotingNotEnded
+        );
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vote_escrow.to_account_info(),
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: ctx.accounts.governance.to_account_info(),
+                },
+                &[&[b"governance", &[ctx.bumps.governance]]],
+            ),
+            ctx.accounts.voter.weight,
+            ctx.accounts.governance_mint.decimals,
+        )?;
+
+        Ok(())
+    }
+
+    /// Execute a passed proposal once. Its instruction's accounts, followed
+    /// by the target program, are passed as remaining accounts.
     pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
-        let proposal = &ctx.accounts.proposal;
+        let proposal = &mut ctx.accounts.proposal;
         let governance = &ctx.accounts.governance;
 
         require!(
@@ -72,7 +140,9 @@ pub mod ziro_delta_governance {
             ZiroDeltaError::VotingNotEnded
         );
 
-        let quorum_votes = (governance.quorum_fraction * 1) / 100;
+        let quorum_votes = (ctx.accounts.governance_mint.supply as u128
+            * governance.quorum_fraction as u128
+            / 100) as u64;
 
         require!(
             proposal.for_votes + proposal.against_votes >= quorum_votes,
@@ -83,7 +153,28 @@ pub mod ziro_delta_governance {
             ZiroDeltaError::ProposalFailed
         );
 
-        // Execute the proposal
+        proposal.executed = true;
+
+        if let Some(instruction) = &proposal.instruction {
+            let instruction = Instruction {
+                program_id: instruction.program_id,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|account| AccountMeta {
+                        pubkey: account.pubkey,
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: instruction.data.clone(),
+            };
+            invoke_signed(
+                &instruction,
+                ctx.remaining_accounts,
+                &[&[b"governance_authority", &[ctx.bumps.governance_authority]]],
+            )?;
+        }
 
         Ok(())
     }
@@ -91,20 +182,39 @@ pub mod ziro_delta_governance {
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
-    #[account(init, payer = user, space = 8 + 8 + 8 + 8 + 32)]
+    #[account(init, payer = user, space = 8 + 8 + 8 + 8 + 32 + 32, seeds = [b"governance"], bump)]
     pub governance: Account<'info, Governance>,
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    // Owned by the governance state rather than the governance authority,
+    // so passed proposals can't move escrowed votes
+    #[account(
+        init,
+        payer = user,
+        token::mint = governance_mint,
+        token::authority = governance,
+        seeds = [b"vote_escrow"],
+        bump
+    )]
+    pub vote_escrow: InterfaceAccount<'info, TokenAccount>,
     pub authority: AccountInfo<'info>,
     #[account(mut)]
     pub user: Signer<'info>,
     pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
+#[instruction(title: String, description: String, instruction: Option<ProposalInstruction>)]
 pub struct CreateProposal<'info> {
-    #[account(init, payer = proposer, space = 8 + 32 + 32 + 32 + 8 + 8)]
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::space(&title, &description, &instruction)
+    )]
     pub proposal: Account<'info, Proposal>,
     #[account(mut)]
     pub proposer: Signer<'info>,
+    #[account(seeds = [b"governance"], bump)]
     pub governance: Account<'info, Governance>,
     pub system_program: Program<'info, System>,
 }
@@ -113,18 +223,59 @@ pub struct CreateProposal<'info> {
 pub struct Vote<'info> {
     #[account(mut)]
     pub proposal: Account<'info, Proposal>,
-    #[account(init, payer = user, space = 8 + 32 + 32 + 1)]
+    #[account(seeds = [b"governance"], bump, has_one = governance_mint)]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 1 + 8,
+        seeds = [b"vote", proposal.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
     pub voter: Account<'info, Voter>,
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = governance_mint, token::authority = user)]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vote_escrow"], bump)]
+    pub vote_escrow: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
     pub user: Signer<'info>,
     pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct ExecuteProposal<'info> {
+pub struct WithdrawVotes<'info> {
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"governance"], bump, has_one = governance_mint)]
+    pub governance: Account<'info, Governance>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"vote", proposal.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub voter: Account<'info, Voter>,
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = governance_mint)]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vote_escrow"], bump)]
+    pub vote_escrow: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, constraint = !proposal.executed @ ZiroDeltaError::ProposalAlreadyExecuted)]
     pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"governance"], bump, has_one = governance_mint)]
     pub governance: Account<'info, Governance>,
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, seeds = [b"governance_authority"], bump)]
+    /// CHECK: PDA that signs executed proposal instructions
+    pub governance_authority: UncheckedAccount<'info>,
 }
 
 #[account]
@@ -133,6 +284,7 @@ pub struct Governance {
     pub voting_period: u64,
     pub quorum_fraction: u64,
     pub authority: Pubkey,
+    pub governance_mint: Pubkey,
 }
 
 #[account]
@@ -144,6 +296,32 @@ pub struct Proposal {
     pub end_block: u64,
     pub for_votes: u64,
     pub against_votes: u64,
+    pub executed: bool,
+    pub instruction: Option<ProposalInstruction>,
+}
+
+impl Proposal {
+    pub fn space(title: &str, description: &str, instruction: &Option<ProposalInstruction>) -> usize {
+        let instruction_len = instruction.as_ref().map_or(0, |instruction| {
+            32 + 4 + instruction.accounts.len() * (32 + 1 + 1) + 4 + instruction.data.len()
+        });
+        8 + 4 + title.len() + 4 + description.len() + 32 + 8 * 4 + 1 + 1 + instruction_len
+    }
+}
+
+/// Instruction a proposal executes as the governance authority
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccount>,
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
 }
 
 #[account]
@@ -151,9 +329,10 @@ pub struct Voter {
     pub proposal: Pubkey,
     pub voter: Pubkey,
     pub vote: VoteOption,
+    pub weight: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum VoteOption {
     For,
     Against,
@@ -171,4 +350,10 @@ pub enum ZiroDeltaError {
     QuorumNotMet,
     #[msg("Proposal failed")]
     ProposalFailed,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Quorum must be between 1 and 100 percent of supply")]
+    InvalidQuorum,
+    #[msg("Invalid amount")]
+    InvalidAmount,
 }
@@ -1,6 +1,7 @@
-
 const anchor = require('@project-serum/anchor');
 const { SystemProgram } = anchor.web3;
+const { Token, TOKEN_PROGRAM_ID } = require("@solana/spl-token");
+const { assert } = require('chai');
 
 describe('ziro_delta_governance', () => {
 
@@ -10,56 +11,192 @@ describe('ziro_delta_governance', () => {
 
   const program = anchor.workspace.ZiroDeltaGovernance;
 
-  const governance = anchor.web3.Keypair.generate();
   const authority = anchor.web3.Keypair.generate();
   const proposal = anchor.web3.Keypair.generate();
-  const voter = anchor.web3.Keypair.generate();
+  const recipient = anchor.web3.Keypair.generate();
+
+  let governance;
+  let governanceAuthority;
+  let governanceMint;
+  let voteEscrow;
+  let voterTokenAccount;
+  let voter;
 
   it('Is initialized!', async () => {
-    await program.rpc.initialize(new anchor.BN(0), new anchor.BN(100), new anchor.BN(50), {
+    [governance] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance")],
+      program.programId
+    );
+    [governanceAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance_authority")],
+      program.programId
+    );
+    [voteEscrow] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote_escrow")],
+      program.programId
+    );
+
+    governanceMint = await Token.createMint(
+      provider.connection,
+      provider.wallet.payer,
+      provider.wallet.publicKey,
+      null,
+      9,
+      TOKEN_PROGRAM_ID
+    );
+    voterTokenAccount = await governanceMint.createAccount(provider.wallet.publicKey);
+    await governanceMint.mintTo(voterTokenAccount, provider.wallet.payer, [], 1000);
+
+    try {
+      await program.rpc.initialize(new anchor.BN(0), new anchor.BN(10), new anchor.BN(0), {
+        accounts: {
+          governance,
+          governanceMint: governanceMint.publicKey,
+          voteEscrow,
+          authority: authority.publicKey,
+          user: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidQuorum");
+    }
+
+    await program.rpc.initialize(new anchor.BN(0), new anchor.BN(10), new anchor.BN(50), {
       accounts: {
-        governance: governance.publicKey,
+        governance,
+        governanceMint: governanceMint.publicKey,
+        voteEscrow,
         authority: authority.publicKey,
         user: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
       },
-      signers: [governance, authority],
     });
   });
 
   it('Creates a proposal', async () => {
-    await program.rpc.createProposal("Test Proposal", "This is a test proposal.", {
-      accounts: {
-        proposal: proposal.publicKey,
-        proposer: provider.wallet.publicKey,
-        governance: governance.publicKey,
-        systemProgram: SystemProgram.programId,
-      },
-      signers: [proposal],
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(governanceAuthority, anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+    // Pay the recipient out of the governance authority once passed
+    const transfer = SystemProgram.transfer({
+      fromPubkey: governanceAuthority,
+      toPubkey: recipient.publicKey,
+      lamports: anchor.web3.LAMPORTS_PER_SOL / 10,
     });
+
+    await program.rpc.createProposal(
+      "Test Proposal",
+      "This is a test proposal.",
+      {
+        programId: transfer.programId,
+        accounts: transfer.keys.map((key) => ({
+          pubkey: key.pubkey,
+          isSigner: key.isSigner,
+          isWritable: key.isWritable,
+        })),
+        data: transfer.data,
+      },
+      {
+        accounts: {
+          proposal: proposal.publicKey,
+          proposer: provider.wallet.publicKey,
+          governance,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [proposal],
+      }
+    );
   });
 
   it('Votes on a proposal', async () => {
-    await program.rpc.vote({ for: {} }, {
+    [voter] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote"), proposal.publicKey.toBuffer(), provider.wallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const voteAccounts = {
       accounts: {
         proposal: proposal.publicKey,
-        voter: voter.publicKey,
+        governance,
+        voter,
+        governanceMint: governanceMint.publicKey,
+        voterTokenAccount,
+        voteEscrow,
         user: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
       },
-      signers: [voter],
-    });
+    };
+
+    // Less than half the supply doesn't reach quorum on its own, so the
+    // proposal only passes if this wallet can't vote again
+    await program.rpc.vote({ for: {} }, new anchor.BN(600), voteAccounts);
+
+    try {
+      await program.rpc.vote({ for: {} }, new anchor.BN(400), voteAccounts);
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.notEqual(err.message, "Should have failed");
+    }
+
+    const proposalAccount = await program.account.proposal.fetch(proposal.publicKey);
+    assert.equal(proposalAccount.forVotes.toNumber(), 600);
+    const escrow = await governanceMint.getAccountInfo(voteEscrow);
+    assert.equal(escrow.amount.toNumber(), 600);
   });
 
   it('Executes a proposal', async () => {
     // Wait for the voting period to end
-    await new Promise(resolve => setTimeout(resolve, 2000));
+    await new Promise(resolve => setTimeout(resolve, 5000));
 
-    await program.rpc.executeProposal({
+    const executeAccounts = {
       accounts: {
         proposal: proposal.publicKey,
-        governance: governance.publicKey,
+        governance,
+        governanceMint: governanceMint.publicKey,
+        governanceAuthority,
+      },
+      remainingAccounts: [
+        { pubkey: governanceAuthority, isSigner: false, isWritable: true },
+        { pubkey: recipient.publicKey, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+    };
+
+    await program.rpc.executeProposal(executeAccounts);
+
+    const balance = await provider.connection.getBalance(recipient.publicKey);
+    assert.equal(balance, anchor.web3.LAMPORTS_PER_SOL / 10);
+
+    try {
+      await program.rpc.executeProposal(executeAccounts);
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalAlreadyExecuted");
+    }
+  });
+
+  it('Returns escrowed votes after voting ends', async () => {
+    await program.rpc.withdrawVotes({
+      accounts: {
+        proposal: proposal.publicKey,
+        governance,
+        voter,
+        governanceMint: governanceMint.publicKey,
+        voterTokenAccount,
+        voteEscrow,
+        user: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
       },
     });
+
+    const account = await governanceMint.getAccountInfo(voterTokenAccount);
+    assert.equal(account.amount.toNumber(), 1000);
+    assert.isNull(await provider.connection.getAccountInfo(voter));
   });
 });
@@ -1,5 +1,20 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_spl::token_2022::spl_token_2022::extension::metadata_pointer;
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{self, spl_token_2022, InitializeMint2, Token2022};
+use anchor_spl::token_interface::{
+    self, Burn, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface, TransferChecked,
+};
+use spl_token_metadata_interface::state::TokenMetadata;
+use ziro_delta_epoch_manager::{EpochManager, EpochSettlement};
+use ziro_delta_treasury::program::ZiroDeltaTreasury;
+use ziro_delta_treasury::VaultInfo;
 
 declare_id!("3RurkF7fVRSGgEKspbKsLey8wMtjMrZ7yjmi1b1pbX8r");
 
@@ -7,13 +22,116 @@ declare_id!("3RurkF7fVRSGgEKspbKsLey8wMtjMrZ7yjmi1b1pbX8r");
 pub mod ziro_delta_minting {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    /// Initialize the minting state and register the existing PFRT/NFRT pair
+    /// as the series for `epoch_id`, backed by the existing collateral vault
+    pub fn initialize(ctx: Context<Initialize>, epoch_id: u64) -> Result<()> {
         let state = &mut ctx.accounts.state;
         state.owner = *ctx.accounts.owner.key;
-        state.pfrt_mint = *ctx.accounts.pfrt_mint.to_account_info().key;
-        state.nfrt_mint = *ctx.accounts.nfrt_mint.to_account_info().key;
-        state.collateral_vault = *ctx.accounts.collateral_vault.to_account_info().key;
+        state.collateral_mint = ctx.accounts.collateral_vault.mint;
         state.paused = false;
+        state.epoch_manager = ctx.accounts.epoch_manager.key();
+        state.mint_authority_bump = ctx.bumps.mint_authority;
+        state.vault_authority_bump = ctx.bumps.vault_authority;
+        state.series_count = 1;
+        state.collateral_count = 1;
+        state.supply_nonce = 0;
+
+        let series = &mut ctx.accounts.series;
+        series.state = state.key();
+        series.epoch_id = epoch_id;
+        series.pfrt_mint = ctx.accounts.pfrt_mint.key();
+        series.nfrt_mint = ctx.accounts.nfrt_mint.key();
+        series.supply_cap = u64::MAX;
+        series.index = 0;
+        series.bump = ctx.bumps.series;
+
+        let collateral_config = &mut ctx.accounts.collateral_config;
+        collateral_config.state = state.key();
+        collateral_config.mint = ctx.accounts.collateral_mint.key();
+        collateral_config.collateral_vault = ctx.accounts.collateral_vault.key();
+        collateral_config.decimals = ctx.accounts.collateral_mint.decimals;
+        collateral_config.supply_cap = u64::MAX;
+        collateral_config.total_deposited = 0;
+        collateral_config.enabled = true;
+        collateral_config.bump = ctx.bumps.collateral_config;
+
+        let fee_config = &mut ctx.accounts.fee_config;
+        fee_config.governance = ctx.accounts.governance.key();
+        fee_config.mint_fee_bps = DEFAULT_MINT_FEE_BPS;
+        fee_config.redeem_fee_bps = 0;
+        fee_config.early_redemption_fee_bps = 0;
+        fee_config.tiers = Vec::new();
+        fee_config.bump = ctx.bumps.fee_config;
+
+        let limit_config = &mut ctx.accounts.limit_config;
+        limit_config.governance = ctx.accounts.governance.key();
+        limit_config.default_series_supply_cap = u64::MAX;
+        limit_config.user_mint_limit = u64::MAX;
+        limit_config.user_mint_window = DEFAULT_USER_MINT_WINDOW;
+        limit_config.bump = ctx.bumps.limit_config;
+
+        // Mints and vault created directly under the PDAs need no migration
+        let mint_authority = COption::Some(ctx.accounts.mint_authority.key());
+        state.authorities_migrated = ctx.accounts.pfrt_mint.mint_authority == mint_authority
+            && ctx.accounts.nfrt_mint.mint_authority == mint_authority
+            && ctx.accounts.collateral_vault.owner == ctx.accounts.vault_authority.key();
+        Ok(())
+    }
+
+    /// One-time handover of the existing mint and vault authorities to the program PDAs
+    pub fn migrate_authorities(ctx: Context<MigrateAuthorities>) -> Result<()> {
+        require!(
+            !ctx.accounts.state.authorities_migrated,
+            ZiroDeltaError::AuthoritiesAlreadyMigrated
+        );
+
+        let mint_authority = ctx.accounts.mint_authority.key();
+        let vault_authority = ctx.accounts.vault_authority.key();
+
+        token_interface::set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.current_mint_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.pfrt_mint.to_account_info(),
+                },
+            ),
+            AuthorityType::MintTokens,
+            Some(mint_authority),
+        )?;
+
+        token_interface::set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.current_mint_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.nfrt_mint.to_account_info(),
+                },
+            ),
+            AuthorityType::MintTokens,
+            Some(mint_authority),
+        )?;
+
+        token_interface::set_authority(
+            CpiContext::new(
+                ctx.accounts.collateral_token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.current_vault_authority.to_account_info(),
+                    account_or_mint: ctx.accounts.collateral_vault.to_account_info(),
+                },
+            ),
+            AuthorityType::AccountOwner,
+            Some(vault_authority),
+        )?;
+
+        ctx.accounts.state.authorities_migrated = true;
+
+        emit!(AuthoritiesMigrated {
+            state: ctx.accounts.state.key(),
+            mint_authority,
+            vault_authority,
+        });
+
         Ok(())
     }
 
@@ -22,80 +140,749 @@ pub mod ziro_delta_minting {
         Ok(())
     }
 
-    pub fn mint(ctx: Context<MintTokens>, amount: u64) -> Result<()> {
-        require!(!ctx.accounts.state.paused, ZiroDeltaError::Paused);
+    /// Let `verify_reserves` pause the protocol instead of failing when it
+    /// detects insolvency (owner only)
+    pub fn set_auto_pause(ctx: Context<SetPaused>, enabled: bool) -> Result<()> {
+        ctx.accounts.state.auto_pause_on_insolvency = enabled;
+        Ok(())
+    }
+
+    /// Permissionless proof of reserves, paged over series so it fits in a
+    /// transaction however many series have been opened. `remaining_accounts`
+    /// holds one group of [series, pfrt_mint, nfrt_mint, settlement] for each
+    /// series from index `series_start` onwards, in opening order. A page
+    /// that stops short of the last series adds its liabilities to the
+    /// reserve checkpoint. The page that reaches it also holds one group of
+    /// [collateral_config, collateral_vault, strategy_config] for every
+    /// registered collateral, and checks that outstanding PFRT/NFRT across
+    /// all pages is covered by vault balances plus strategy principal.
+    /// Settlement and strategy config may be uninitialised PDAs. A check
+    /// restarts from `series_start` 0 and fails if supply changes mid-way.
+    pub fn verify_reserves<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyReserves<'info>>,
+        series_start: u16,
+    ) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let series_count = ctx.accounts.state.series_count;
+        let supply_nonce = ctx.accounts.state.supply_nonce;
+        require!(series_start < series_count, ZiroDeltaError::InvalidReserveAccounts);
+
+        let checkpoint = &mut ctx.accounts.reserve_checkpoint;
+        if series_start == 0 {
+            checkpoint.state = state_key;
+            checkpoint.liabilities = 0;
+            checkpoint.supply_nonce = supply_nonce;
+            checkpoint.bump = ctx.bumps.reserve_checkpoint;
+        } else {
+            require!(
+                checkpoint.next_series == series_start,
+                ZiroDeltaError::InvalidReserveAccounts
+            );
+            require!(
+                checkpoint.supply_nonce == supply_nonce,
+                ZiroDeltaError::StaleReserveCheckpoint
+            );
+        }
+
+        // Every remaining series and every collateral must be accounted for
+        // on the final page, so callers can't understate liabilities or
+        // reserves by leaving groups out
+        let remaining_series = (series_count - series_start) as usize;
+        let final_len =
+            remaining_series * 4 + ctx.accounts.state.collateral_count as usize * 3;
+        let is_final = ctx.remaining_accounts.len() == final_len;
+        let series_len = if is_final {
+            remaining_series * 4
+        } else {
+            ctx.remaining_accounts.len()
+        };
+        require!(
+            series_len > 0 && series_len % 4 == 0 && series_len <= remaining_series * 4,
+            ZiroDeltaError::InvalidReserveAccounts
+        );
+        let (series_accounts, collateral_accounts) = ctx.remaining_accounts.split_at(series_len);
+
+        let mut liabilities = checkpoint.liabilities;
+        for (i, group) in series_accounts.chunks(4).enumerate() {
+            let series = Account::<Series>::try_from(&group[0])?;
+            let pfrt_mint = InterfaceAccount::<Mint>::try_from(&group[1])?;
+            let nfrt_mint = InterfaceAccount::<Mint>::try_from(&group[2])?;
+            let series_key = Pubkey::create_program_address(
+                &[
+                    b"series",
+                    state_key.as_ref(),
+                    series.epoch_id.to_le_bytes().as_ref(),
+                    &[series.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(ZiroDeltaError::InvalidReserveAccounts))?;
+            require!(
+                series.key() == series_key
+                    && series.state == state_key
+                    && series.pfrt_mint == pfrt_mint.key()
+                    && series.nfrt_mint == nfrt_mint.key()
+                    && series.index as usize == series_start as usize + i,
+                ZiroDeltaError::InvalidReserveAccounts
+            );
+
+            let (settlement_key, _) = Pubkey::find_program_address(
+                &[
+                    b"settlement",
+                    ctx.accounts.state.epoch_manager.as_ref(),
+                    series.epoch_id.to_le_bytes().as_ref(),
+                ],
+                &ziro_delta_epoch_manager::ID,
+            );
+            require_keys_eq!(
+                group[3].key(),
+                settlement_key,
+                ZiroDeltaError::InvalidReserveAccounts
+            );
+
+            // Before settlement each pair is backed by one unit of collateral;
+            // afterwards each leg is owed its settled payout
+            let outstanding = if group[3].data_is_empty() {
+                pfrt_mint.supply.max(nfrt_mint.supply)
+            } else {
+                let settlement = Account::<EpochSettlement>::try_from(&group[3])?;
+                calculate_settled_payout(
+                    pfrt_mint.supply,
+                    nfrt_mint.supply,
+                    settlement.pfrt_payout_bps,
+                    settlement.nfrt_payout_bps,
+                )?
+            };
+            liabilities = liabilities
+                .checked_add(to_reserve_units(outstanding, pfrt_mint.decimals)?)
+                .ok_or(ZiroDeltaError::Overflow)?;
+        }
+
+        if !is_final {
+            let checkpoint = &mut ctx.accounts.reserve_checkpoint;
+            checkpoint.liabilities = liabilities;
+            checkpoint.next_series = series_start + (series_len / 4) as u16;
+            return Ok(());
+        }
+        // Any later check has to start over
+        ctx.accounts.reserve_checkpoint.next_series = 0;
+
+        let mut reserves: u128 = 0;
+        let mut seen_collateral = Vec::new();
+        for group in collateral_accounts.chunks(3) {
+            let collateral_config = Account::<CollateralConfig>::try_from(&group[0])?;
+            let collateral_vault = InterfaceAccount::<TokenAccount>::try_from(&group[1])?;
+            let config_key = Pubkey::create_program_address(
+                &[
+                    b"collateral",
+                    state_key.as_ref(),
+                    collateral_config.mint.as_ref(),
+                    &[collateral_config.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| error!(ZiroDeltaError::InvalidReserveAccounts))?;
+            require!(
+                collateral_config.key() == config_key
+                    && collateral_config.state == state_key
+                    && collateral_config.collateral_vault == collateral_vault.key()
+                    && !seen_collateral.contains(&collateral_config.mint),
+                ZiroDeltaError::InvalidReserveAccounts
+            );
+            seen_collateral.push(collateral_config.mint);
+
+            let (strategy_key, _) = Pubkey::find_program_address(
+                &[b"strategy", state_key.as_ref(), collateral_config.mint.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(
+                group[2].key(),
+                strategy_key,
+                ZiroDeltaError::InvalidReserveAccounts
+            );
+            let allocated = if group[2].data_is_empty() {
+                0
+            } else {
+                Account::<StrategyConfig>::try_from(&group[2])?.allocated
+            };
+
+            let collateral = (collateral_vault.amount as u128)
+                .checked_add(allocated as u128)
+                .ok_or(ZiroDeltaError::Overflow)?;
+            reserves = reserves
+                .checked_add(to_reserve_units_u128(collateral, collateral_config.decimals)?)
+                .ok_or(ZiroDeltaError::Overflow)?;
+        }
+
+        let solvent = reserves >= liabilities;
+        let state = &mut ctx.accounts.state;
+        if !solvent {
+            require!(state.auto_pause_on_insolvency, ZiroDeltaError::Insolvent);
+            state.paused = true;
+        }
+
+        emit!(ReservesReport {
+            state: state_key,
+            liabilities,
+            reserves,
+            solvent,
+            paused: state.paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Accept a new collateral mint with its own PDA vault (owner only)
+    pub fn add_collateral(ctx: Context<AddCollateral>, supply_cap: u64) -> Result<()> {
+        let collateral_config = &mut ctx.accounts.collateral_config;
+        collateral_config.state = ctx.accounts.state.key();
+        collateral_config.mint = ctx.accounts.collateral_mint.key();
+        collateral_config.collateral_vault = ctx.accounts.collateral_vault.key();
+        collateral_config.decimals = ctx.accounts.collateral_mint.decimals;
+        collateral_config.supply_cap = supply_cap;
+        collateral_config.total_deposited = 0;
+        collateral_config.enabled = true;
+        collateral_config.bump = ctx.bumps.collateral_config;
+
+        let state = &mut ctx.accounts.state;
+        state.collateral_count = state
+            .collateral_count
+            .checked_add(1)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        emit!(CollateralUpdated {
+            mint: collateral_config.mint,
+            supply_cap,
+            enabled: true,
+        });
+
+        Ok(())
+    }
+
+    /// Update the supply cap or enabled flag of a collateral (owner only).
+    /// Disabled collateral can still be redeemed but not minted against.
+    pub fn update_collateral(
+        ctx: Context<UpdateCollateral>,
+        new_supply_cap: Option<u64>,
+        new_enabled: Option<bool>,
+    ) -> Result<()> {
+        let collateral_config = &mut ctx.accounts.collateral_config;
+
+        if let Some(supply_cap) = new_supply_cap {
+            collateral_config.supply_cap = supply_cap;
+        }
+
+        if let Some(enabled) = new_enabled {
+            collateral_config.enabled = enabled;
+        }
+
+        emit!(CollateralUpdated {
+            mint: collateral_config.mint,
+            supply_cap: collateral_config.supply_cap,
+            enabled: collateral_config.enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Update the fee schedule (governance only)
+    pub fn update_fee_config(
+        ctx: Context<UpdateFeeConfig>,
+        new_mint_fee_bps: Option<u16>,
+        new_redeem_fee_bps: Option<u16>,
+        new_early_redemption_fee_bps: Option<u16>,
+        new_tiers: Option<Vec<FeeTier>>,
+    ) -> Result<()> {
+        let fee_config = &mut ctx.accounts.fee_config;
+
+        if let Some(mint_fee_bps) = new_mint_fee_bps {
+            require!(mint_fee_bps <= MAX_MINT_FEE_BPS, ZiroDeltaError::FeeTooHigh);
+            fee_config.mint_fee_bps = mint_fee_bps;
+        }
+
+        if let Some(redeem_fee_bps) = new_redeem_fee_bps {
+            require!(redeem_fee_bps <= MAX_REDEEM_FEE_BPS, ZiroDeltaError::FeeTooHigh);
+            fee_config.redeem_fee_bps = redeem_fee_bps;
+        }
+
+        if let Some(early_redemption_fee_bps) = new_early_redemption_fee_bps {
+            require!(
+                early_redemption_fee_bps <= MAX_EARLY_REDEMPTION_FEE_BPS,
+                ZiroDeltaError::FeeTooHigh
+            );
+            fee_config.early_redemption_fee_bps = early_redemption_fee_bps;
+        }
+
+        if let Some(tiers) = new_tiers {
+            validate_fee_tiers(&tiers)?;
+            fee_config.tiers = tiers;
+        }
+
+        emit!(FeeConfigUpdated {
+            governance: ctx.accounts.governance.key(),
+            mint_fee_bps: fee_config.mint_fee_bps,
+            redeem_fee_bps: fee_config.redeem_fee_bps,
+            early_redemption_fee_bps: fee_config.early_redemption_fee_bps,
+            tiers: fee_config.tiers.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Update the per-wallet rolling mint limit and the supply cap given to
+    /// newly opened series (governance only)
+    pub fn update_mint_limits(
+        ctx: Context<UpdateMintLimits>,
+        new_user_mint_limit: Option<u64>,
+        new_user_mint_window: Option<i64>,
+        new_default_series_supply_cap: Option<u64>,
+    ) -> Result<()> {
+        let limit_config = &mut ctx.accounts.limit_config;
+
+        if let Some(user_mint_limit) = new_user_mint_limit {
+            limit_config.user_mint_limit = user_mint_limit;
+        }
+
+        if let Some(user_mint_window) = new_user_mint_window {
+            require!(user_mint_window > 0, ZiroDeltaError::InvalidMintWindow);
+            limit_config.user_mint_window = user_mint_window;
+        }
+
+        if let Some(default_series_supply_cap) = new_default_series_supply_cap {
+            limit_config.default_series_supply_cap = default_series_supply_cap;
+        }
+
+        emit!(MintLimitsUpdated {
+            governance: ctx.accounts.governance.key(),
+            user_mint_limit: limit_config.user_mint_limit,
+            user_mint_window: limit_config.user_mint_window,
+            default_series_supply_cap: limit_config.default_series_supply_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Update the outstanding PFRT/NFRT cap of a single series (governance only)
+    pub fn update_series_supply_cap(
+        ctx: Context<UpdateSeriesSupplyCap>,
+        epoch_id: u64,
+        supply_cap: u64,
+    ) -> Result<()> {
+        ctx.accounts.series.supply_cap = supply_cap;
+
+        emit!(SeriesSupplyCapUpdated {
+            epoch_id,
+            supply_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Point a collateral at a yield strategy program and bound how much of
+    /// its vault may be deployed there (governance only)
+    pub fn configure_strategy(
+        ctx: Context<ConfigureStrategy>,
+        max_allocation_bps: u16,
+    ) -> Result<()> {
+        require!(
+            max_allocation_bps <= MAX_STRATEGY_ALLOCATION_BPS,
+            ZiroDeltaError::AllocationTooHigh
+        );
+
+        let strategy_config = &mut ctx.accounts.strategy_config;
+        let strategy_program = ctx.accounts.strategy_program.key();
+        let strategy_state = ctx.accounts.strategy_state.key();
+        require!(
+            strategy_config.allocated == 0
+                || (strategy_config.strategy_program == strategy_program
+                    && strategy_config.strategy_state == strategy_state),
+            ZiroDeltaError::StrategyInUse
+        );
+
+        strategy_config.state = ctx.accounts.state.key();
+        strategy_config.collateral_mint = ctx.accounts.collateral_config.mint;
+        strategy_config.strategy_program = strategy_program;
+        strategy_config.strategy_state = strategy_state;
+        strategy_config.strategy_vault = ctx.accounts.strategy_vault.key();
+        strategy_config.max_allocation_bps = max_allocation_bps;
+        strategy_config.bump = ctx.bumps.strategy_config;
+
+        emit!(StrategyConfigured {
+            collateral_mint: strategy_config.collateral_mint,
+            strategy_program,
+            max_allocation_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Deploy idle collateral into the strategy (governance only)
+    pub fn allocate_to_strategy(ctx: Context<AllocateToStrategy>, amount: u64) -> Result<()> {
         require!(amount > 0, ZiroDeltaError::InvalidAmount);
 
-        let fee = amount
-            .checked_mul(MINT_FEE_BPS)
-            .and_then(|n| n.checked_div(10000))
+        let strategy_config = &ctx.accounts.strategy_config;
+        let allocated = strategy_config
+            .allocated
+            .checked_add(amount)
             .ok_or(ZiroDeltaError::Overflow)?;
-        let net_amount = amount
-            .checked_sub(fee)
+        let total_collateral = (ctx.accounts.collateral_vault.amount as u128)
+            + strategy_config.allocated as u128;
+        require!(
+            allocated as u128 * 10000
+                <= total_collateral * strategy_config.max_allocation_bps as u128,
+            ZiroDeltaError::AllocationTooHigh
+        );
+
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+        invoke_strategy(
+            &ctx.accounts.strategy_program,
+            ctx.accounts.strategy_accounts(),
+            "strategy_deposit",
+            Some(amount),
+            &[&vault_seeds[..]],
+        )?;
+
+        ctx.accounts.strategy_config.allocated = allocated;
+
+        emit!(StrategyAllocated {
+            collateral_mint: ctx.accounts.strategy_config.collateral_mint,
+            amount,
+            allocated,
+        });
+
+        Ok(())
+    }
+
+    /// Pull principal back from the strategy into the collateral vault (governance only)
+    pub fn withdraw_from_strategy(ctx: Context<StrategyOperation>, amount: u64) -> Result<()> {
+        require!(amount > 0, ZiroDeltaError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.strategy_config.allocated,
+            ZiroDeltaError::InvalidAmount
+        );
+
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+        let vault_balance_before = ctx.accounts.collateral_vault.amount;
+        invoke_strategy(
+            &ctx.accounts.strategy_program,
+            ctx.accounts.strategy_accounts(),
+            "strategy_withdraw",
+            Some(amount),
+            &[&vault_seeds[..]],
+        )?;
+        ctx.accounts.collateral_vault.reload()?;
+        let received = ctx
+            .accounts
+            .collateral_vault
+            .amount
+            .checked_sub(vault_balance_before)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        // Only principal that actually came back leaves the allocation
+        let strategy_config = &mut ctx.accounts.strategy_config;
+        strategy_config.allocated = strategy_config.allocated.saturating_sub(received);
+
+        emit!(StrategyWithdrawn {
+            collateral_mint: strategy_config.collateral_mint,
+            amount: received,
+            allocated: strategy_config.allocated,
+        });
+
+        Ok(())
+    }
+
+    /// Collect strategy yield and forward it to the protocol treasury (permissionless)
+    pub fn harvest_strategy(ctx: Context<HarvestStrategy>) -> Result<()> {
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+
+        let vault_balance_before = ctx.accounts.collateral_vault.amount;
+        invoke_strategy(
+            &ctx.accounts.strategy_program,
+            ctx.accounts.strategy_accounts(),
+            "strategy_harvest",
+            None,
+            &[&vault_seeds[..]],
+        )?;
+        ctx.accounts.collateral_vault.reload()?;
+        let harvested = ctx
+            .accounts
+            .collateral_vault
+            .amount
+            .checked_sub(vault_balance_before)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        if harvested > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.treasury_vault.to_account_info(),
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        mint: ctx.accounts.collateral_mint.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                        token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                harvested,
+            )?;
+        }
+
+        let strategy_config = &mut ctx.accounts.strategy_config;
+        strategy_config.total_harvested = strategy_config
+            .total_harvested
+            .checked_add(harvested)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        emit!(StrategyHarvested {
+            collateral_mint: strategy_config.collateral_mint,
+            amount: harvested,
+            total_harvested: strategy_config.total_harvested,
+        });
+
+        Ok(())
+    }
+
+    /// Open a new PFRT/NFRT series with its own PDA mints. New series are
+    /// Token-2022 mints carrying on-chain name/symbol metadata for the epoch.
+    pub fn open_series(ctx: Context<OpenSeries>, epoch_id: u64) -> Result<()> {
+        require!(
+            epoch_id >= ctx.accounts.epoch_manager.current_epoch,
+            ZiroDeltaError::InvalidEpoch
+        );
+
+        create_series_mint(
+            ctx.accounts,
+            &ctx.accounts.pfrt_mint,
+            b"pfrt_mint",
+            ctx.bumps.pfrt_mint,
+            epoch_id,
+            format!("ZiroDelta PFRT Epoch {}", epoch_id),
+            format!("PFRT-{}", epoch_id),
+        )?;
+        create_series_mint(
+            ctx.accounts,
+            &ctx.accounts.nfrt_mint,
+            b"nfrt_mint",
+            ctx.bumps.nfrt_mint,
+            epoch_id,
+            format!("ZiroDelta NFRT Epoch {}", epoch_id),
+            format!("NFRT-{}", epoch_id),
+        )?;
+
+        let series = &mut ctx.accounts.series;
+        series.state = ctx.accounts.state.key();
+        series.epoch_id = epoch_id;
+        series.pfrt_mint = ctx.accounts.pfrt_mint.key();
+        series.nfrt_mint = ctx.accounts.nfrt_mint.key();
+        series.supply_cap = ctx.accounts.limit_config.default_series_supply_cap;
+        series.index = ctx.accounts.state.series_count;
+        series.bump = ctx.bumps.series;
+
+        let state = &mut ctx.accounts.state;
+        state.series_count = state
+            .series_count
+            .checked_add(1)
             .ok_or(ZiroDeltaError::Overflow)?;
 
-        // Transfer collateral from user to the vault
-        token::transfer(
+        emit!(SeriesOpened {
+            epoch_id,
+            pfrt_mint: series.pfrt_mint,
+            nfrt_mint: series.nfrt_mint,
+        });
+
+        Ok(())
+    }
+
+    pub fn mint(ctx: Context<MintTokens>, epoch_id: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ZiroDeltaError::Paused);
+        require!(amount > 0, ZiroDeltaError::InvalidAmount);
+        require!(
+            ctx.accounts.collateral_config.enabled,
+            ZiroDeltaError::CollateralDisabled
+        );
+
+        // Transfer collateral from user to the vault. Token-2022 collateral
+        // may withhold a transfer fee, so only what the vault received counts.
+        let vault_balance_before = ctx.accounts.collateral_vault.amount;
+        token_interface::transfer_checked(
             CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                ctx.accounts.collateral_token_program.to_account_info(),
+                TransferChecked {
                     from: ctx.accounts.user_collateral_account.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
                     to: ctx.accounts.collateral_vault.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
                 },
             ),
             amount,
+            ctx.accounts.collateral_mint.decimals,
         )?;
+        ctx.accounts.collateral_vault.reload()?;
+        let received = ctx
+            .accounts
+            .collateral_vault
+            .amount
+            .checked_sub(vault_balance_before)
+            .ok_or(ZiroDeltaError::Overflow)?;
 
-        // Transfer fee to the owner
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.collateral_vault.to_account_info(),
-                    to: ctx.accounts.owner_collateral_account.to_account_info(),
-                    authority: ctx.accounts.collateral_vault.to_account_info(),
-                },
-            ),
-            fee,
+        let collateral_decimals = ctx.accounts.collateral_config.decimals;
+        let pair_decimals = ctx.accounts.pfrt_mint.decimals;
+
+        let volume = record_volume(
+            Some(&mut ctx.accounts.user_mint_state),
+            normalize_amount(received, collateral_decimals, pair_decimals)?,
+        )?;
+        let fee_config = &ctx.accounts.fee_config;
+        let fee = calculate_fee(
+            received,
+            volume,
+            fee_config.mint_fee_bps,
+            &fee_config.tiers,
         )?;
+        let net_collateral = received
+            .checked_sub(fee)
+            .ok_or(ZiroDeltaError::Overflow)?;
+        let net_amount = normalize_amount(net_collateral, collateral_decimals, pair_decimals)?;
+        require!(net_amount > 0, ZiroDeltaError::InvalidAmount);
+
+        // Legs minted together but redeemed apart after settlement leave the
+        // larger supply as the outstanding pairs
+        let outstanding = ctx
+            .accounts
+            .pfrt_mint
+            .supply
+            .max(ctx.accounts.nfrt_mint.supply)
+            .checked_add(net_amount)
+            .ok_or(ZiroDeltaError::Overflow)?;
+        require!(
+            outstanding <= ctx.accounts.series.supply_cap,
+            ZiroDeltaError::SeriesSupplyCapExceeded
+        );
+
+        check_user_mint_limit(
+            &mut ctx.accounts.user_mint_state,
+            &ctx.accounts.limit_config,
+            net_amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let collateral_config = &mut ctx.accounts.collateral_config;
+        let total_deposited = collateral_config
+            .total_deposited
+            .checked_add(net_collateral)
+            .ok_or(ZiroDeltaError::Overflow)?;
+        require!(
+            total_deposited <= collateral_config.supply_cap,
+            ZiroDeltaError::CollateralCapExceeded
+        );
+        collateral_config.total_deposited = total_deposited;
+
+        let series_collateral = &mut ctx.accounts.series_collateral;
+        series_collateral.series = ctx.accounts.series.key();
+        series_collateral.collateral_mint = ctx.accounts.collateral_config.mint;
+        series_collateral.deposited = series_collateral
+            .deposited
+            .checked_add(net_collateral)
+            .ok_or(ZiroDeltaError::Overflow)?;
+        series_collateral.bump = ctx.bumps.series_collateral;
+
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+        let mint_seeds = &[
+            b"mint_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.mint_authority_bump],
+        ];
+
+        // Route the fee to the protocol treasury
+        if fee > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.treasury_vault.to_account_info(),
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        mint: ctx.accounts.collateral_mint.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                        token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                fee,
+            )?;
+        }
 
         // Mint PFRT and NFRT
-        token::mint_to(
-            CpiContext::new(
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 MintTo {
                     mint: ctx.accounts.pfrt_mint.to_account_info(),
                     to: ctx.accounts.user_pfrt_account.to_account_info(),
-                    authority: ctx.accounts.pfrt_mint_authority.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
                 },
+                &[&mint_seeds[..]],
             ),
             net_amount,
         )?;
 
-        token::mint_to(
-            CpiContext::new(
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 MintTo {
                     mint: ctx.accounts.nfrt_mint.to_account_info(),
                     to: ctx.accounts.user_nfrt_account.to_account_info(),
-                    authority: ctx.accounts.nfrt_mint_authority.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
                 },
+                &[&mint_seeds[..]],
             ),
             net_amount,
         )?;
 
+        ctx.accounts.state.supply_nonce = ctx.accounts.state.supply_nonce.wrapping_add(1);
+
         emit!(MintEvent {
             user: *ctx.accounts.user.key,
-            collateral_amount: amount,
+            epoch_id,
+            collateral_mint: ctx.accounts.collateral_config.mint,
+            collateral_amount: received,
             pfrt_amount: net_amount,
             nfrt_amount: net_amount,
+            fee,
         });
 
         Ok(())
     }
 
-    pub fn redeem(ctx: Context<RedeemTokens>, pfrt_amount: u64, nfrt_amount: u64) -> Result<()> {
+    pub fn redeem(
+        ctx: Context<RedeemTokens>,
+        epoch_id: u64,
+        pfrt_amount: u64,
+        nfrt_amount: u64,
+    ) -> Result<()> {
         require!(!ctx.accounts.state.paused, ZiroDeltaError::Paused);
         require!(
             pfrt_amount > 0 && nfrt_amount > 0,
@@ -107,7 +894,7 @@ pub mod ziro_delta_minting {
         );
 
         // Burn PFRT and NFRT
-        token::burn(
+        token_interface::burn(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Burn {
@@ -119,7 +906,7 @@ pub mod ziro_delta_minting {
             pfrt_amount,
         )?;
 
-        token::burn(
+        token_interface::burn(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Burn {
@@ -131,40 +918,546 @@ pub mod ziro_delta_minting {
             nfrt_amount,
         )?;
 
+        ctx.accounts.state.supply_nonce = ctx.accounts.state.supply_nonce.wrapping_add(1);
+
+        let gross_collateral = denormalize_amount(
+            pfrt_amount,
+            ctx.accounts.pfrt_mint.decimals,
+            ctx.accounts.collateral_config.decimals,
+        )?;
+        let volume = record_volume(ctx.accounts.user_mint_state.as_deref_mut(), pfrt_amount)?;
+
+        // Redeeming pairs before settlement pays the early-redemption fee;
+        // once the settlement record exists the regular redeem fee applies
+        let fee_config = &ctx.accounts.fee_config;
+        let base_fee_bps = if ctx.accounts.settlement.data_is_empty() {
+            fee_config.early_redemption_fee_bps
+        } else {
+            fee_config.redeem_fee_bps
+        };
+        let fee = calculate_fee(gross_collateral, volume, base_fee_bps, &fee_config.tiers)?;
+        let collateral_amount = gross_collateral
+            .checked_sub(fee)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        withdraw_series_collateral(
+            &mut ctx.accounts.collateral_config,
+            &mut ctx.accounts.series_collateral,
+            gross_collateral,
+        )?;
+
         // Transfer collateral back to the user
-        let collateral_amount = pfrt_amount;
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.collateral_token_program.to_account_info(),
+                TransferChecked {
                     from: ctx.accounts.collateral_vault.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
                     to: ctx.accounts.user_collateral_account.to_account_info(),
-                    authority: ctx.accounts.collateral_vault_authority.to_account_info(),
+                    authority: ctx.accounts.vault_authority.to_account_info(),
                 },
+                &[&vault_seeds[..]],
             ),
             collateral_amount,
+            ctx.accounts.collateral_mint.decimals,
         )?;
 
+        // Route the fee to the protocol treasury
+        if fee > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.treasury_vault.to_account_info(),
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        mint: ctx.accounts.collateral_mint.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                        token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                fee,
+            )?;
+        }
+
         emit!(RedeemEvent {
             user: *ctx.accounts.user.key,
+            epoch_id,
+            collateral_mint: ctx.accounts.collateral_config.mint,
+            pfrt_amount,
+            nfrt_amount,
+            collateral_amount,
+            fee,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem either leg independently once the epoch has been settled.
+    /// Each leg pays out collateral according to the settlement payout ratio.
+    pub fn redeem_settled(
+        ctx: Context<RedeemSettled>,
+        epoch_id: u64,
+        pfrt_amount: u64,
+        nfrt_amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.state.paused, ZiroDeltaError::Paused);
+        require!(
+            pfrt_amount > 0 || nfrt_amount > 0,
+            ZiroDeltaError::InvalidAmount
+        );
+
+        let settlement = &ctx.accounts.settlement;
+        let payout = calculate_settled_payout(
+            pfrt_amount,
+            nfrt_amount,
+            settlement.pfrt_payout_bps,
+            settlement.nfrt_payout_bps,
+        )?;
+        let gross_collateral = denormalize_amount(
+            payout,
+            ctx.accounts.pfrt_mint.decimals,
+            ctx.accounts.collateral_config.decimals,
+        )?;
+
+        let volume = record_volume(ctx.accounts.user_mint_state.as_deref_mut(), payout)?;
+        let fee_config = &ctx.accounts.fee_config;
+        let fee = calculate_fee(
+            gross_collateral,
+            volume,
+            fee_config.redeem_fee_bps,
+            &fee_config.tiers,
+        )?;
+        let collateral_amount = gross_collateral
+            .checked_sub(fee)
+            .ok_or(ZiroDeltaError::Overflow)?;
+
+        withdraw_series_collateral(
+            &mut ctx.accounts.collateral_config,
+            &mut ctx.accounts.series_collateral,
+            gross_collateral,
+        )?;
+
+        if pfrt_amount > 0 {
+            token_interface::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.pfrt_mint.to_account_info(),
+                        from: ctx.accounts.user_pfrt_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                pfrt_amount,
+            )?;
+        }
+
+        if nfrt_amount > 0 {
+            token_interface::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.nfrt_mint.to_account_info(),
+                        from: ctx.accounts.user_nfrt_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                nfrt_amount,
+            )?;
+        }
+
+        ctx.accounts.state.supply_nonce = ctx.accounts.state.supply_nonce.wrapping_add(1);
+
+        let state_key = ctx.accounts.state.key();
+        let vault_seeds = &[
+            b"vault_authority".as_ref(),
+            state_key.as_ref(),
+            &[ctx.accounts.state.vault_authority_bump],
+        ];
+
+        if collateral_amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.collateral_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        mint: ctx.accounts.collateral_mint.to_account_info(),
+                        to: ctx.accounts.user_collateral_account.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                collateral_amount,
+                ctx.accounts.collateral_mint.decimals,
+            )?;
+        }
+
+        // Route the fee to the protocol treasury
+        if fee > 0 {
+            ziro_delta_treasury::cpi::deposit(
+                CpiContext::new_with_signer(
+                    ctx.accounts.treasury_program.to_account_info(),
+                    ziro_delta_treasury::cpi::accounts::Deposit {
+                        vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                        vault: ctx.accounts.treasury_vault.to_account_info(),
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        mint: ctx.accounts.collateral_mint.to_account_info(),
+                        authority: ctx.accounts.vault_authority.to_account_info(),
+                        token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                fee,
+            )?;
+        }
+
+        emit!(SettledRedeemEvent {
+            user: *ctx.accounts.user.key,
+            epoch_id,
+            collateral_mint: ctx.accounts.collateral_config.mint,
             pfrt_amount,
             nfrt_amount,
             collateral_amount,
+            fee,
         });
 
         Ok(())
     }
 }
 
+// Creates a series mint PDA as a Token-2022 mint whose metadata pointer
+// refers to itself, then writes the epoch name/symbol into it.
+fn create_series_mint<'info>(
+    accounts: &OpenSeries<'info>,
+    mint: &UncheckedAccount<'info>,
+    prefix: &[u8],
+    bump: u8,
+    epoch_id: u64,
+    name: String,
+    symbol: String,
+) -> Result<()> {
+    let state_key = accounts.state.key();
+    let epoch_bytes = epoch_id.to_le_bytes();
+    let mint_seeds = &[prefix, state_key.as_ref(), epoch_bytes.as_ref(), &[bump]];
+    let authority_seeds = &[
+        b"mint_authority".as_ref(),
+        state_key.as_ref(),
+        &[accounts.state.mint_authority_bump],
+    ];
+
+    let metadata = TokenMetadata {
+        update_authority: Some(accounts.mint_authority.key()).try_into()?,
+        mint: mint.key(),
+        name,
+        symbol,
+        uri: String::new(),
+        additional_metadata: Vec::new(),
+    };
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::MetadataPointer,
+    ])?;
+    // Fund the metadata up front; the token program reallocs when writing it
+    let lamports = Rent::get()?.minimum_balance(space + metadata.tlv_size_of()?);
+
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: accounts.owner.to_account_info(),
+                to: mint.to_account_info(),
+            },
+            &[&mint_seeds[..]],
+        ),
+        lamports,
+        space as u64,
+        &token_2022::ID,
+    )?;
+
+    invoke(
+        &metadata_pointer::instruction::initialize(
+            &token_2022::ID,
+            &mint.key(),
+            Some(accounts.mint_authority.key()),
+            Some(mint.key()),
+        )?,
+        &[mint.to_account_info()],
+    )?;
+
+    token_2022::initialize_mint2(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            InitializeMint2 {
+                mint: mint.to_account_info(),
+            },
+        ),
+        accounts.collateral_mint.decimals,
+        &accounts.mint_authority.key(),
+        None,
+    )?;
+
+    invoke_signed(
+        &spl_token_metadata_interface::instruction::initialize(
+            &token_2022::ID,
+            &mint.key(),
+            &accounts.mint_authority.key(),
+            &mint.key(),
+            &accounts.mint_authority.key(),
+            metadata.name,
+            metadata.symbol,
+            metadata.uri,
+        ),
+        &[mint.to_account_info(), accounts.mint_authority.to_account_info()],
+        &[&authority_seeds[..]],
+    )?;
+
+    Ok(())
+}
+
+// Strategy adapter interface: strategy programs expose Anchor instructions
+// `strategy_deposit(amount)`, `strategy_withdraw(amount)` and `strategy_harvest()`
+// taking [strategy state (w), strategy vault (w), depositor token account (w),
+// depositor (signer), mint, token program]. The collateral vault is the
+// depositor token account and the vault authority PDA signs as depositor.
+fn invoke_strategy<'info>(
+    strategy_program: &AccountInfo<'info>,
+    accounts: [AccountInfo<'info>; 6],
+    ix_name: &str,
+    amount: Option<u64>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = hash(format!("global:{}", ix_name).as_bytes()).to_bytes()[..8].to_vec();
+    if let Some(amount) = amount {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+
+    let ix = Instruction {
+        program_id: strategy_program.key(),
+        accounts: vec![
+            AccountMeta::new(accounts[0].key(), false),
+            AccountMeta::new(accounts[1].key(), false),
+            AccountMeta::new(accounts[2].key(), false),
+            AccountMeta::new_readonly(accounts[3].key(), true),
+            AccountMeta::new_readonly(accounts[4].key(), false),
+            AccountMeta::new_readonly(accounts[5].key(), false),
+        ],
+        data,
+    };
+    let mut account_infos = accounts.to_vec();
+    account_infos.push(strategy_program.clone());
+    invoke_signed(&ix, &account_infos, signer_seeds)?;
+
+    Ok(())
+}
+
+// Scales an amount to the common 18-decimal unit used by reserve reports.
+fn to_reserve_units(amount: u64, decimals: u8) -> Result<u128> {
+    to_reserve_units_u128(amount as u128, decimals)
+}
+
+fn to_reserve_units_u128(amount: u128, decimals: u8) -> Result<u128> {
+    require!(decimals <= RESERVE_DECIMALS, ZiroDeltaError::Overflow);
+    10u128
+        .checked_pow((RESERVE_DECIMALS - decimals) as u32)
+        .and_then(|factor| amount.checked_mul(factor))
+        .ok_or_else(|| error!(ZiroDeltaError::Overflow))
+}
+
+// Resets the wallet's window once it has elapsed, then charges the mint to it.
+fn check_user_mint_limit(
+    user_mint_state: &mut UserMintState,
+    limit_config: &LimitConfig,
+    amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    if current_time >= user_mint_state.window_start + limit_config.user_mint_window {
+        user_mint_state.window_start = current_time;
+        user_mint_state.minted_in_window = 0;
+    }
+
+    let minted_in_window = user_mint_state
+        .minted_in_window
+        .checked_add(amount)
+        .ok_or(ZiroDeltaError::Overflow)?;
+    require!(
+        minted_in_window <= limit_config.user_mint_limit,
+        ZiroDeltaError::UserMintLimitExceeded
+    );
+    user_mint_state.minted_in_window = minted_in_window;
+
+    Ok(())
+}
+
+// A series can only take out collateral it deposited in that mint, so pairs
+// minted against one collateral can't drain another series' or mint's deposits.
+fn withdraw_series_collateral(
+    collateral_config: &mut CollateralConfig,
+    series_collateral: &mut SeriesCollateral,
+    amount: u64,
+) -> Result<()> {
+    series_collateral.deposited = series_collateral
+        .deposited
+        .checked_sub(amount)
+        .ok_or(ZiroDeltaError::InsufficientSeriesCollateral)?;
+    collateral_config.total_deposited = collateral_config
+        .total_deposited
+        .checked_sub(amount)
+        .ok_or(ZiroDeltaError::Overflow)?;
+    Ok(())
+}
+
+// Adds `amount` (in pair units) to the wallet's cumulative volume and returns
+// the volume before it, which is what fee tiers are keyed on. Wallets that
+// don't pass their mint state have no volume and pay the base fee.
+fn record_volume(user_mint_state: Option<&mut UserMintState>, amount: u64) -> Result<u64> {
+    match user_mint_state {
+        Some(user_mint_state) => {
+            let volume = user_mint_state.cumulative_volume;
+            user_mint_state.cumulative_volume = volume
+                .checked_add(amount)
+                .ok_or(ZiroDeltaError::Overflow)?;
+            Ok(volume)
+        }
+        None => Ok(0),
+    }
+}
+
+// The highest tier the wallet's cumulative volume qualifies for discounts the
+// base fee.
+fn calculate_fee(amount: u64, volume: u64, base_fee_bps: u16, tiers: &[FeeTier]) -> Result<u64> {
+    let discount_bps = tiers
+        .iter()
+        .rev()
+        .find(|tier| volume >= tier.min_volume)
+        .map_or(0, |tier| tier.discount_bps);
+    let fee_bps = base_fee_bps as u128 * (10000 - discount_bps as u128) / 10000;
+    let fee = (amount as u128)
+        .checked_mul(fee_bps)
+        .ok_or(ZiroDeltaError::Overflow)?
+        / 10000;
+    Ok(fee as u64)
+}
+
+fn validate_fee_tiers(tiers: &[FeeTier]) -> Result<()> {
+    require!(tiers.len() <= MAX_FEE_TIERS, ZiroDeltaError::InvalidFeeTiers);
+    for (i, tier) in tiers.iter().enumerate() {
+        require!(tier.discount_bps <= 10000, ZiroDeltaError::InvalidFeeTiers);
+        if i > 0 {
+            require!(
+                tier.min_volume > tiers[i - 1].min_volume,
+                ZiroDeltaError::InvalidFeeTiers
+            );
+        }
+    }
+    Ok(())
+}
+
+// Converts a collateral amount into PFRT/NFRT units, rounding down.
+fn normalize_amount(amount: u64, collateral_decimals: u8, pair_decimals: u8) -> Result<u64> {
+    rescale_amount(amount, collateral_decimals, pair_decimals)
+}
+
+// Converts a PFRT/NFRT amount into collateral units, rounding down.
+fn denormalize_amount(amount: u64, pair_decimals: u8, collateral_decimals: u8) -> Result<u64> {
+    rescale_amount(amount, pair_decimals, collateral_decimals)
+}
+
+fn rescale_amount(amount: u64, from_decimals: u8, to_decimals: u8) -> Result<u64> {
+    let scaled = if to_decimals >= from_decimals {
+        10u128
+            .checked_pow((to_decimals - from_decimals) as u32)
+            .and_then(|factor| (amount as u128).checked_mul(factor))
+    } else {
+        10u128
+            .checked_pow((from_decimals - to_decimals) as u32)
+            .map(|factor| amount as u128 / factor)
+    }
+    .ok_or(ZiroDeltaError::Overflow)?;
+    u64::try_from(scaled).map_err(|_| error!(ZiroDeltaError::Overflow))
+}
+
+// Rounds down so the vault never pays out more than the pairs it backs.
+fn calculate_settled_payout(
+    pfrt_amount: u64,
+    nfrt_amount: u64,
+    pfrt_payout_bps: u16,
+    nfrt_payout_bps: u16,
+) -> Result<u64> {
+    let weighted = (pfrt_amount as u128)
+        .checked_mul(pfrt_payout_bps as u128)
+        .and_then(|p| {
+            (nfrt_amount as u128)
+                .checked_mul(nfrt_payout_bps as u128)
+                .and_then(|n| p.checked_add(n))
+        })
+        .ok_or(ZiroDeltaError::Overflow)?;
+    u64::try_from(weighted / 10000).map_err(|_| error!(ZiroDeltaError::Overflow))
+}
+
 #[derive(Accounts)]
+#[instruction(epoch_id: u64)]
 pub struct Initialize<'info> {
-    #[account(init, payer = owner, space = 8 + 32 + 32 + 32 + 32 + 1)]
+    #[account(init, payer = owner, space = 8 + 32 + 32 + 1 + 32 + 1 + 1 + 1 + 1 + 2 + 2 + 8)]
     pub state: Account<'info, State>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<Series>(),
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub series: Account<'info, Series>,
+    #[account(
+        init,
+        payer = owner,
+        space = FeeConfig::LEN,
+        seeds = [b"fee_config", state.key().as_ref()],
+        bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<LimitConfig>(),
+        seeds = [b"limit_config", state.key().as_ref()],
+        bump
+    )]
+    pub limit_config: Account<'info, LimitConfig>,
+    #[account(
+        seeds = [b"governance_authority"],
+        bump,
+        seeds::program = ziro_delta_governance::ID
+    )]
+    /// CHECK: Governance PDA allowed to update the fee schedule and mint limits
+    pub governance: AccountInfo<'info>,
     #[account(mut)]
     pub owner: Signer<'info>,
-    pub pfrt_mint: Account<'info, Mint>,
-    pub nfrt_mint: Account<'info, Mint>,
-    pub collateral_vault: Account<'info, TokenAccount>,
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(token::mint = collateral_mint)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<CollateralConfig>(),
+        seeds = [b"collateral", state.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    pub epoch_manager: Account<'info, EpochManager>,
+    #[account(seeds = [b"mint_authority", state.key().as_ref()], bump)]
+    /// CHECK: This is a PDA used as mint authority
+    pub mint_authority: AccountInfo<'info>,
+    #[account(seeds = [b"vault_authority", state.key().as_ref()], bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -176,73 +1469,921 @@ pub struct SetPaused<'info> {
 }
 
 #[derive(Accounts)]
+pub struct VerifyReserves<'info> {
+    #[account(mut)]
+    pub state: Account<'info, State>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ReserveCheckpoint>(),
+        seeds = [b"reserve_checkpoint", state.key().as_ref()],
+        bump
+    )]
+    pub reserve_checkpoint: Account<'info, ReserveCheckpoint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddCollateral<'info> {
+    #[account(mut, has_one = owner)]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<CollateralConfig>(),
+        seeds = [b"collateral", state.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        init,
+        payer = owner,
+        token::mint = collateral_mint,
+        token::authority = vault_authority,
+        seeds = [b"collateral_vault", state.key().as_ref(), collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCollateral<'info> {
+    #[account(has_one = owner)]
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"collateral", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"fee_config", state.key().as_ref()],
+        bump = fee_config.bump,
+        has_one = governance
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMintLimits<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"limit_config", state.key().as_ref()],
+        bump = limit_config.bump,
+        has_one = governance
+    )]
+    pub limit_config: Account<'info, LimitConfig>,
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct UpdateSeriesSupplyCap<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        mut,
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = state
+    )]
+    pub series: Account<'info, Series>,
+    #[account(
+        seeds = [b"limit_config", state.key().as_ref()],
+        bump = limit_config.bump,
+        has_one = governance
+    )]
+    pub limit_config: Account<'info, LimitConfig>,
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureStrategy<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        seeds = [b"collateral", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        init_if_needed,
+        payer = governance,
+        space = 8 + std::mem::size_of::<StrategyConfig>(),
+        seeds = [b"strategy", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+    #[account(executable)]
+    /// CHECK: Strategy program implementing the adapter interface
+    pub strategy_program: AccountInfo<'info>,
+    /// CHECK: Strategy-owned state account, validated by the strategy program
+    pub strategy_state: AccountInfo<'info>,
+    /// CHECK: Strategy-owned token vault, validated by the strategy program
+    pub strategy_vault: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"governance_authority"],
+        bump,
+        seeds::program = ziro_delta_governance::ID
+    )]
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AllocateToStrategy<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        seeds = [b"collateral", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"strategy", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = strategy_config.bump,
+        has_one = strategy_program,
+        has_one = strategy_state,
+        has_one = strategy_vault
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_state: AccountInfo<'info>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_vault: AccountInfo<'info>,
+    /// CHECK: Checked against the strategy config
+    pub strategy_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    #[account(
+        seeds = [b"governance_authority"],
+        bump,
+        seeds::program = ziro_delta_governance::ID
+    )]
+    pub governance: Signer<'info>,
+}
+
+impl<'info> AllocateToStrategy<'info> {
+    fn strategy_accounts(&self) -> [AccountInfo<'info>; 6] {
+        [
+            self.strategy_state.to_account_info(),
+            self.strategy_vault.to_account_info(),
+            self.collateral_vault.to_account_info(),
+            self.vault_authority.to_account_info(),
+            self.collateral_mint.to_account_info(),
+            self.collateral_token_program.to_account_info(),
+        ]
+    }
+}
+
+#[derive(Accounts)]
+pub struct StrategyOperation<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        seeds = [b"collateral", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"strategy", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = strategy_config.bump,
+        has_one = strategy_program,
+        has_one = strategy_state,
+        has_one = strategy_vault
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_state: AccountInfo<'info>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_vault: AccountInfo<'info>,
+    /// CHECK: Checked against the strategy config
+    pub strategy_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    #[account(
+        seeds = [b"governance_authority"],
+        bump,
+        seeds::program = ziro_delta_governance::ID
+    )]
+    pub governance: Signer<'info>,
+}
+
+impl<'info> StrategyOperation<'info> {
+    fn strategy_accounts(&self) -> [AccountInfo<'info>; 6] {
+        [
+            self.strategy_state.to_account_info(),
+            self.strategy_vault.to_account_info(),
+            self.collateral_vault.to_account_info(),
+            self.vault_authority.to_account_info(),
+            self.collateral_mint.to_account_info(),
+            self.collateral_token_program.to_account_info(),
+        ]
+    }
+}
+
+#[derive(Accounts)]
+pub struct HarvestStrategy<'info> {
+    pub state: Account<'info, State>,
+    #[account(
+        seeds = [b"collateral", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(
+        mut,
+        seeds = [b"strategy", state.key().as_ref(), strategy_config.collateral_mint.as_ref()],
+        bump = strategy_config.bump,
+        has_one = strategy_program,
+        has_one = strategy_state,
+        has_one = strategy_vault
+    )]
+    pub strategy_config: Account<'info, StrategyConfig>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_state: AccountInfo<'info>,
+    #[account(mut)]
+    /// CHECK: Checked against the strategy config, validated by the strategy program
+    pub strategy_vault: AccountInfo<'info>,
+    /// CHECK: Checked against the strategy config
+    pub strategy_program: AccountInfo<'info>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    #[account(
+        mut,
+        seeds = [b"vault_info", collateral_config.mint.as_ref()],
+        bump = treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = treasury_vault_info.vault == treasury_vault.key()
+    )]
+    pub treasury_vault_info: Account<'info, VaultInfo>,
+    #[account(mut)]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    pub treasury_program: Program<'info, ZiroDeltaTreasury>,
+}
+
+impl<'info> HarvestStrategy<'info> {
+    fn strategy_accounts(&self) -> [AccountInfo<'info>; 6] {
+        [
+            self.strategy_state.to_account_info(),
+            self.strategy_vault.to_account_info(),
+            self.collateral_vault.to_account_info(),
+            self.vault_authority.to_account_info(),
+            self.collateral_mint.to_account_info(),
+            self.collateral_token_program.to_account_info(),
+        ]
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct OpenSeries<'info> {
+    #[account(mut, has_one = owner, has_one = epoch_manager)]
+    pub state: Account<'info, State>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + std::mem::size_of::<Series>(),
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub series: Account<'info, Series>,
+    #[account(
+        mut,
+        seeds = [b"pfrt_mint", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    /// CHECK: Created and initialised as a Token-2022 mint in `open_series`
+    pub pfrt_mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"nfrt_mint", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    /// CHECK: Created and initialised as a Token-2022 mint in `open_series`
+    pub nfrt_mint: UncheckedAccount<'info>,
+    #[account(address = state.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"limit_config", state.key().as_ref()],
+        bump = limit_config.bump
+    )]
+    pub limit_config: Account<'info, LimitConfig>,
+    pub epoch_manager: Account<'info, EpochManager>,
+    #[account(
+        seeds = [b"mint_authority", state.key().as_ref()],
+        bump = state.mint_authority_bump
+    )]
+    /// CHECK: This is a PDA used as mint authority
+    pub mint_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAuthorities<'info> {
+    #[account(mut, has_one = owner)]
+    pub state: Account<'info, State>,
+    #[account(has_one = state, has_one = pfrt_mint, has_one = nfrt_mint)]
+    pub series: Account<'info, Series>,
+    #[account(
+        seeds = [b"collateral", state.key().as_ref(), state.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    pub current_mint_authority: Signer<'info>,
+    pub current_vault_authority: Signer<'info>,
+    #[account(
+        seeds = [b"mint_authority", state.key().as_ref()],
+        bump = state.mint_authority_bump
+    )]
+    /// CHECK: This is a PDA used as mint authority
+    pub mint_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
 pub struct MintTokens<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
-    pub user_pfrt_account: Account<'info, TokenAccount>,
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_collateral_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = state,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint
+    )]
+    pub series: Account<'info, Series>,
+    #[account(mut)]
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
     #[account(mut)]
-    pub user_nfrt_account: Account<'info, TokenAccount>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
     #[account(mut)]
-    pub user_collateral_account: Account<'info, TokenAccount>,
-    #[account(mut, address = state.pfrt_mint)]
-    pub pfrt_mint: Account<'info, Mint>,
-    #[account(mut, address = state.nfrt_mint)]
-    pub nfrt_mint: Account<'info, Mint>,
-    #[account(mut, address = state.collateral_vault)]
-    pub collateral_vault: Account<'info, TokenAccount>,
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"fee_config", state.key().as_ref()],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        seeds = [b"limit_config", state.key().as_ref()],
+        bump = limit_config.bump
+    )]
+    pub limit_config: Account<'info, LimitConfig>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<UserMintState>(),
+        seeds = [b"user_mint", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_mint_state: Account<'info, UserMintState>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + std::mem::size_of::<SeriesCollateral>(),
+        seeds = [b"series_collateral", series.key().as_ref(), collateral_config.mint.as_ref()],
+        bump
+    )]
+    pub series_collateral: Account<'info, SeriesCollateral>,
+    #[account(
+        mut,
+        seeds = [b"vault_info", collateral_config.mint.as_ref()],
+        bump = treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = treasury_vault_info.vault == treasury_vault.key()
+    )]
+    pub treasury_vault_info: Account<'info, VaultInfo>,
+    #[account(mut)]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    pub treasury_program: Program<'info, ZiroDeltaTreasury>,
+    #[account(
+        seeds = [
+            b"settlement",
+            state.epoch_manager.as_ref(),
+            epoch_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        seeds::program = ziro_delta_epoch_manager::ID,
+        constraint = settlement.data_is_empty() @ ZiroDeltaError::EpochSettled
+    )]
+    /// CHECK: Settlement record for the epoch; minting stops once it exists
+    pub settlement: AccountInfo<'info>,
+    #[account(
+        seeds = [b"mint_authority", state.key().as_ref()],
+        bump = state.mint_authority_bump
+    )]
+    /// CHECK: This is a PDA used as mint authority
+    pub mint_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
     #[account(mut)]
-    pub owner_collateral_account: Account<'info, TokenAccount>,
-    pub pfrt_mint_authority: Signer<'info>,
-    pub nfrt_mint_authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
     pub state: Account<'info, State>,
 }
 
 #[derive(Accounts)]
+#[instruction(epoch_id: u64)]
 pub struct RedeemTokens<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
-    pub user_pfrt_account: Account<'info, TokenAccount>,
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_collateral_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = state,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint
+    )]
+    pub series: Account<'info, Series>,
+    #[account(mut)]
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"series_collateral", series.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = series_collateral.bump
+    )]
+    pub series_collateral: Account<'info, SeriesCollateral>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"fee_config", state.key().as_ref()],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        mut,
+        seeds = [b"vault_info", collateral_config.mint.as_ref()],
+        bump = treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = treasury_vault_info.vault == treasury_vault.key()
+    )]
+    pub treasury_vault_info: Account<'info, VaultInfo>,
+    #[account(mut)]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    pub treasury_program: Program<'info, ZiroDeltaTreasury>,
+    #[account(
+        seeds = [
+            b"settlement",
+            state.epoch_manager.as_ref(),
+            epoch_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        seeds::program = ziro_delta_epoch_manager::ID
+    )]
+    /// CHECK: Settlement record for the epoch, empty until it is settled
+    pub settlement: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"user_mint", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_mint_state: Option<Account<'info, UserMintState>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    #[account(mut)]
+    pub state: Account<'info, State>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct RedeemSettled<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub user_pfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_nfrt_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub user_collateral_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"series", state.key().as_ref(), epoch_id.to_le_bytes().as_ref()],
+        bump = series.bump,
+        has_one = state,
+        has_one = pfrt_mint,
+        has_one = nfrt_mint
+    )]
+    pub series: Account<'info, Series>,
+    #[account(mut)]
+    pub pfrt_mint: InterfaceAccount<'info, Mint>,
     #[account(mut)]
-    pub user_nfrt_account: Account<'info, TokenAccount>,
+    pub nfrt_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"collateral", state.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = collateral_config.bump,
+        has_one = collateral_vault
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+    #[account(mut)]
+    pub collateral_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = collateral_config.mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"series_collateral", series.key().as_ref(), collateral_config.mint.as_ref()],
+        bump = series_collateral.bump
+    )]
+    pub series_collateral: Account<'info, SeriesCollateral>,
+    #[account(
+        seeds = [b"vault_authority", state.key().as_ref()],
+        bump = state.vault_authority_bump
+    )]
+    /// CHECK: This is a PDA used as vault authority
+    pub vault_authority: AccountInfo<'info>,
+    #[account(
+        seeds = [b"fee_config", state.key().as_ref()],
+        bump = fee_config.bump
+    )]
+    pub fee_config: Account<'info, FeeConfig>,
+    #[account(
+        mut,
+        seeds = [b"vault_info", collateral_config.mint.as_ref()],
+        bump = treasury_vault_info.bump,
+        seeds::program = ziro_delta_treasury::ID,
+        constraint = treasury_vault_info.vault == treasury_vault.key()
+    )]
+    pub treasury_vault_info: Account<'info, VaultInfo>,
+    #[account(mut)]
+    pub treasury_vault: InterfaceAccount<'info, TokenAccount>,
+    pub treasury_program: Program<'info, ZiroDeltaTreasury>,
+    #[account(
+        seeds = [
+            b"settlement",
+            state.epoch_manager.as_ref(),
+            epoch_id.to_le_bytes().as_ref()
+        ],
+        bump,
+        seeds::program = ziro_delta_epoch_manager::ID
+    )]
+    pub settlement: Account<'info, EpochSettlement>,
+    #[account(
+        mut,
+        seeds = [b"user_mint", state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_mint_state: Option<Account<'info, UserMintState>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     #[account(mut)]
-    pub user_collateral_account: Account<'info, TokenAccount>,
-    #[account(mut, address = state.pfrt_mint)]
-    pub pfrt_mint: Account<'info, Mint>,
-    #[account(mut, address = state.nfrt_mint)]
-    pub nfrt_mint: Account<'info, Mint>,
-    #[account(mut, address = state.collateral_vault)]
-    pub collateral_vault: Account<'info, TokenAccount>,
-    pub collateral_vault_authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
     pub state: Account<'info, State>,
 }
 
 #[account]
 pub struct State {
     pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub paused: bool,
+    pub epoch_manager: Pubkey,
+    pub mint_authority_bump: u8,
+    pub vault_authority_bump: u8,
+    pub authorities_migrated: bool,
+    pub auto_pause_on_insolvency: bool,
+    // Registered series and collaterals, all of which verify_reserves requires
+    pub series_count: u16,
+    pub collateral_count: u16,
+    // Bumped whenever PFRT/NFRT supply changes, invalidating any
+    // reserve check paged across the change
+    pub supply_nonce: u64,
+}
+
+/// Mint and redemption fee schedule, owned by governance
+#[account]
+pub struct FeeConfig {
+    pub governance: Pubkey,
+    pub mint_fee_bps: u16,
+    pub redeem_fee_bps: u16,
+    pub early_redemption_fee_bps: u16,
+    pub tiers: Vec<FeeTier>,
+    pub bump: u8,
+}
+
+impl FeeConfig {
+    pub const LEN: usize = 8 + 32 + 2 + 2 + 2 + 4 + MAX_FEE_TIERS * (8 + 2) + 1;
+}
+
+/// Discount on the base fee for wallets whose cumulative mint and redeem
+/// volume, in pair units, is at least `min_volume`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: u64,
+    pub discount_bps: u16,
+}
+
+/// PFRT/NFRT pair for a single epoch
+#[account]
+pub struct Series {
+    pub state: Pubkey,
+    pub epoch_id: u64,
     pub pfrt_mint: Pubkey,
     pub nfrt_mint: Pubkey,
+    // Maximum outstanding PFRT/NFRT pairs
+    pub supply_cap: u64,
+    // Position in opening order, used to page through series in verify_reserves
+    pub index: u16,
+    pub bump: u8,
+}
+
+/// Launch limits on minting, owned by governance
+#[account]
+pub struct LimitConfig {
+    pub governance: Pubkey,
+    pub default_series_supply_cap: u64,
+    pub user_mint_limit: u64,
+    pub user_mint_window: i64,
+    pub bump: u8,
+}
+
+/// Per-wallet mint tracking for the rolling mint limit and fee tiers
+#[account]
+pub struct UserMintState {
+    pub window_start: i64,
+    pub minted_in_window: u64,
+    // Lifetime mint and redeem volume in pair units
+    pub cumulative_volume: u64,
+}
+
+/// Liabilities summed so far by a reserve check paged over series
+#[account]
+pub struct ReserveCheckpoint {
+    pub state: Pubkey,
+    // Index of the first series the next page must start from
+    pub next_series: u16,
+    // State supply nonce when the check started
+    pub supply_nonce: u64,
+    pub liabilities: u128,
+    pub bump: u8,
+}
+
+/// Yield strategy a collateral vault may deploy funds into
+#[account]
+pub struct StrategyConfig {
+    pub state: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub strategy_state: Pubkey,
+    pub strategy_vault: Pubkey,
+    pub max_allocation_bps: u16,
+    // Principal currently deployed in the strategy
+    pub allocated: u64,
+    pub total_harvested: u64,
+    pub bump: u8,
+}
+
+/// Collateral a series has deposited into one collateral mint's vault.
+/// Redemptions of the series draw only on these deposits.
+#[account]
+pub struct SeriesCollateral {
+    pub series: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub deposited: u64,
+    pub bump: u8,
+}
+
+/// Registry entry for an accepted collateral mint and its vault
+#[account]
+pub struct CollateralConfig {
+    pub state: Pubkey,
+    pub mint: Pubkey,
     pub collateral_vault: Pubkey,
-    pub paused: bool,
+    pub decimals: u8,
+    pub supply_cap: u64,
+    pub total_deposited: u64,
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+#[event]
+pub struct SeriesOpened {
+    pub epoch_id: u64,
+    pub pfrt_mint: Pubkey,
+    pub nfrt_mint: Pubkey,
 }
 
 #[event]
 pub struct MintEvent {
     pub user: Pubkey,
+    pub epoch_id: u64,
+    pub collateral_mint: Pubkey,
     pub collateral_amount: u64,
     pub pfrt_amount: u64,
     pub nfrt_amount: u64,
+    pub fee: u64,
 }
 
 #[event]
 pub struct RedeemEvent {
     pub user: Pubkey,
+    pub epoch_id: u64,
+    pub collateral_mint: Pubkey,
+    pub pfrt_amount: u64,
+    pub nfrt_amount: u64,
+    pub collateral_amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct SettledRedeemEvent {
+    pub user: Pubkey,
+    pub epoch_id: u64,
+    pub collateral_mint: Pubkey,
     pub pfrt_amount: u64,
     pub nfrt_amount: u64,
     pub collateral_amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct CollateralUpdated {
+    pub mint: Pubkey,
+    pub supply_cap: u64,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct FeeConfigUpdated {
+    pub governance: Pubkey,
+    pub mint_fee_bps: u16,
+    pub redeem_fee_bps: u16,
+    pub early_redemption_fee_bps: u16,
+    pub tiers: Vec<FeeTier>,
+}
+
+#[event]
+pub struct MintLimitsUpdated {
+    pub governance: Pubkey,
+    pub user_mint_limit: u64,
+    pub user_mint_window: i64,
+    pub default_series_supply_cap: u64,
+}
+
+#[event]
+pub struct SeriesSupplyCapUpdated {
+    pub epoch_id: u64,
+    pub supply_cap: u64,
+}
+
+#[event]
+pub struct StrategyConfigured {
+    pub collateral_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub max_allocation_bps: u16,
+}
+
+#[event]
+pub struct StrategyAllocated {
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub allocated: u64,
+}
+
+#[event]
+pub struct StrategyWithdrawn {
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub allocated: u64,
+}
+
+#[event]
+pub struct StrategyHarvested {
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    pub total_harvested: u64,
+}
+
+/// Amounts are in 18-decimal reserve units so mixed collateral adds up
+#[event]
+pub struct ReservesReport {
+    pub state: Pubkey,
+    pub liabilities: u128,
+    pub reserves: u128,
+    pub solvent: bool,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthoritiesMigrated {
+    pub state: Pubkey,
+    pub mint_authority: Pubkey,
+    pub vault_authority: Pubkey,
 }
 
 #[error_code]
@@ -255,6 +2396,45 @@ pub enum ZiroDeltaError {
     UnequalAmounts,
     #[msg("Calculation overflow.")]
     Overflow,
+    #[msg("Authorities have already been migrated.")]
+    AuthoritiesAlreadyMigrated,
+    #[msg("Series cannot be opened for a past epoch.")]
+    InvalidEpoch,
+    #[msg("Fee too high.")]
+    FeeTooHigh,
+    #[msg("Invalid fee tiers.")]
+    InvalidFeeTiers,
+    #[msg("Collateral is disabled.")]
+    CollateralDisabled,
+    #[msg("Collateral supply cap exceeded.")]
+    CollateralCapExceeded,
+    #[msg("Series supply cap exceeded.")]
+    SeriesSupplyCapExceeded,
+    #[msg("Wallet mint limit exceeded for the current window.")]
+    UserMintLimitExceeded,
+    #[msg("Mint window must be positive.")]
+    InvalidMintWindow,
+    #[msg("Strategy allocation exceeds the allowed share of the vault.")]
+    AllocationTooHigh,
+    #[msg("Strategy still holds allocated collateral.")]
+    StrategyInUse,
+    #[msg("Invalid reserve accounts.")]
+    InvalidReserveAccounts,
+    #[msg("PFRT/NFRT supply changed since the reserve check started.")]
+    StaleReserveCheckpoint,
+    #[msg("Collateral does not cover outstanding PFRT/NFRT.")]
+    Insolvent,
+    #[msg("Series has not deposited enough of this collateral.")]
+    InsufficientSeriesCollateral,
+    #[msg("The epoch has already been settled.")]
+    EpochSettled,
 }
 
-const MINT_FEE_BPS: u64 = 10; // 0.1%
+const DEFAULT_MINT_FEE_BPS: u16 = 10; // 0.1%
+const MAX_MINT_FEE_BPS: u16 = 100; // 1%
+const MAX_REDEEM_FEE_BPS: u16 = 100; // 1%
+const MAX_EARLY_REDEMPTION_FEE_BPS: u16 = 500; // 5%
+const MAX_FEE_TIERS: usize = 4;
+const DEFAULT_USER_MINT_WINDOW: i64 = 24 * 60 * 60; // 1 day
+const MAX_STRATEGY_ALLOCATION_BPS: u16 = 5000; // 50%
+const RESERVE_DECIMALS: u8 = 18;
@@ -1,7 +1,12 @@
 import * as anchor from "@project-serum/anchor";
 import { Program } from "@project-serum/anchor";
 import { ZiroDeltaMinting } from "../target/types/ziro_delta_minting";
-import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { ZiroDeltaEpochManager } from "../target/types/ziro_delta_epoch_manager";
+import { ZiroDeltaTreasury } from "../target/types/ziro_delta_treasury";
+import { ZiroDeltaOracle } from "../target/types/ziro_delta_oracle";
+import { ZiroDeltaGovernance } from "../target/types/ziro_delta_governance";
+import { MockStrategy } from "../target/types/mock_strategy";
+import { Token, TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
 import { assert } from "chai";
 
 describe("ziro-delta-minting", () => {
@@ -9,28 +14,158 @@ describe("ziro-delta-minting", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.ZiroDeltaMinting as Program<ZiroDeltaMinting>;
+  const epochManagerProgram = anchor.workspace.ZiroDeltaEpochManager as Program<ZiroDeltaEpochManager>;
+  const treasuryProgram = anchor.workspace.ZiroDeltaTreasury as Program<ZiroDeltaTreasury>;
+  const oracleProgram = anchor.workspace.ZiroDeltaOracle as Program<ZiroDeltaOracle>;
+  const strategyProgram = anchor.workspace.MockStrategy as Program<MockStrategy>;
+  const governanceProgram = anchor.workspace.ZiroDeltaGovernance as Program<ZiroDeltaGovernance>;
 
   let pfrtMint: Token;
   let nfrtMint: Token;
   let collateralMint: Token;
+  let usdcMint: Token;
 
   let userPfrtAccount: anchor.web3.PublicKey;
   let userNfrtAccount: anchor.web3.PublicKey;
   let userCollateralAccount: anchor.web3.PublicKey;
 
-  let ownerCollateralAccount: anchor.web3.PublicKey;
+  let treasuryVaultInfo: anchor.web3.PublicKey;
+  let treasuryVault: anchor.web3.PublicKey;
 
   let collateralVault: anchor.web3.PublicKey;
 
+  let mintAuthority: anchor.web3.PublicKey;
+  let vaultAuthority: anchor.web3.PublicKey;
+
+  let series: anchor.web3.PublicKey;
+  let feeConfig: anchor.web3.PublicKey;
+  let collateralConfig: anchor.web3.PublicKey;
+  let limitConfig: anchor.web3.PublicKey;
+  let userMintState: anchor.web3.PublicKey;
+  let settlement: anchor.web3.PublicKey;
+  let seriesCollateral: anchor.web3.PublicKey;
+
+  let governanceState: anchor.web3.PublicKey;
+  let governance: anchor.web3.PublicKey;
+  let governanceMint: Token;
+  let voteEscrow: anchor.web3.PublicKey;
+  let votingAccount: anchor.web3.PublicKey;
+
+  const epochId = new anchor.BN(0);
+
+  const findSeriesAddress = async (prefix: string, epoch: anchor.BN) =>
+    (
+      await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from(prefix), state.publicKey.toBuffer(), epoch.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )
+    )[0];
+
   const state = anchor.web3.Keypair.generate();
+  const epochManager = anchor.web3.Keypair.generate();
 
   const owner = anchor.web3.Keypair.generate();
   const user = anchor.web3.Keypair.generate();
 
+  // Governance-gated instructions only accept the governance PDA, so they
+  // are proposed, voted through and executed by the governance program
+  const executeAsGovernance = async (ix: anchor.web3.TransactionInstruction) => {
+    const proposal = anchor.web3.Keypair.generate();
+    const [voter] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote"), proposal.publicKey.toBuffer(), provider.wallet.publicKey.toBuffer()],
+      governanceProgram.programId
+    );
+    const voteAccounts = {
+      proposal: proposal.publicKey,
+      governance: governanceState,
+      voter,
+      governanceMint: governanceMint.publicKey,
+      voterTokenAccount: votingAccount,
+      voteEscrow,
+      user: provider.wallet.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const createProposal = await governanceProgram.methods
+      .createProposal("Minting update", "", {
+        programId: ix.programId,
+        accounts: ix.keys,
+        data: ix.data,
+      })
+      .accounts({
+        proposal: proposal.publicKey,
+        proposer: provider.wallet.publicKey,
+        governance: governanceState,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .instruction();
+    // The wallet holds the whole governance supply
+    await governanceProgram.methods
+      .vote({ for: {} }, new anchor.BN(1000))
+      .accounts({ ...voteAccounts, systemProgram: anchor.web3.SystemProgram.programId })
+      .preInstructions([createProposal])
+      .signers([proposal])
+      .rpc();
+
+    // Wait for the voting period to end
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+
+    await governanceProgram.methods
+      .executeProposal()
+      .accounts({
+        proposal: proposal.publicKey,
+        governance: governanceState,
+        governanceMint: governanceMint.publicKey,
+        governanceAuthority: governance,
+      })
+      .remainingAccounts([
+        ...ix.keys.map((key) => ({ ...key, isSigner: false })),
+        { pubkey: ix.programId, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+
+    await governanceProgram.methods.withdrawVotes().accounts(voteAccounts).rpc();
+  };
+
   before(async () => {
     await provider.connection.requestAirdrop(owner.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL);
     await provider.connection.requestAirdrop(user.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL);
 
+    [governanceState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance")],
+      governanceProgram.programId
+    );
+    [governance] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance_authority")],
+      governanceProgram.programId
+    );
+    [voteEscrow] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote_escrow")],
+      governanceProgram.programId
+    );
+    governanceMint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      9,
+      TOKEN_PROGRAM_ID
+    );
+    votingAccount = await governanceMint.createAccount(provider.wallet.publicKey);
+    await governanceMint.mintTo(votingAccount, owner, [], 1000);
+    await governanceProgram.methods
+      .initialize(new anchor.BN(0), new anchor.BN(2), new anchor.BN(50))
+      .accounts({
+        governance: governanceState,
+        governanceMint: governanceMint.publicKey,
+        voteEscrow,
+        authority: owner.publicKey,
+        user: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
     pfrtMint = await Token.createMint(
       provider.connection,
       owner,
@@ -62,20 +197,126 @@ describe("ziro-delta-minting", () => {
     userNfrtAccount = await nfrtMint.createAccount(user.publicKey);
     userCollateralAccount = await collateralMint.createAccount(user.publicKey);
 
-    ownerCollateralAccount = await collateralMint.createAccount(owner.publicKey);
+    const zdltMint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      9,
+      TOKEN_PROGRAM_ID
+    );
+
+    const [treasuryState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_state")],
+      treasuryProgram.programId
+    );
+    const [treasuryAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_authority")],
+      treasuryProgram.programId
+    );
+    [treasuryVaultInfo] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vault_info"), collateralMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+    [treasuryVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_vault"), collateralMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+
+    await treasuryProgram.methods
+      .initialize(owner.publicKey)
+      .accounts({
+        state: treasuryState,
+        treasuryAuthority,
+        zdltMint: zdltMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await treasuryProgram.methods
+      .createVault()
+      .accounts({
+        state: treasuryState,
+        vaultInfo: treasuryVaultInfo,
+        vault: treasuryVault,
+        treasuryAuthority,
+        mint: collateralMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner])
+      .rpc();
 
     collateralVault = await collateralMint.createAccount(owner.publicKey);
+
+    [mintAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("mint_authority"), state.publicKey.toBuffer()],
+      program.programId
+    );
+    [vaultAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vault_authority"), state.publicKey.toBuffer()],
+      program.programId
+    );
+    series = await findSeriesAddress("series", epochId);
+    [feeConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("fee_config"), state.publicKey.toBuffer()],
+      program.programId
+    );
+    [limitConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("limit_config"), state.publicKey.toBuffer()],
+      program.programId
+    );
+    [userMintState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("user_mint"), state.publicKey.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    [collateralConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("collateral"), state.publicKey.toBuffer(), collateralMint.publicKey.toBuffer()],
+      program.programId
+    );
+    [seriesCollateral] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("series_collateral"), series.toBuffer(), collateralMint.publicKey.toBuffer()],
+      program.programId
+    );
+    [settlement] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("settlement"), epochManager.publicKey.toBuffer(), epochId.toArrayLike(Buffer, "le", 8)],
+      epochManagerProgram.programId
+    );
+
+    await epochManagerProgram.methods
+      .initialize(new anchor.BN(0))
+      .accounts({
+        epochManager: epochManager.publicKey,
+        authority: owner.publicKey,
+        user: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner, epochManager])
+      .rpc();
   });
 
   it("Is initialized!", async () => {
     await program.methods
-      .initialize()
+      .initialize(epochId)
       .accounts({
         state: state.publicKey,
+        series,
+        feeConfig,
+        limitConfig,
+        governance,
         owner: owner.publicKey,
         pfrtMint: pfrtMint.publicKey,
         nfrtMint: nfrtMint.publicKey,
         collateralVault: collateralVault,
+        collateralMint: collateralMint.publicKey,
+        collateralConfig,
+        epochManager: epochManager.publicKey,
+        mintAuthority,
+        vaultAuthority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([owner, state])
@@ -83,6 +324,118 @@ describe("ziro-delta-minting", () => {
 
     const stateAccount = await program.account.state.fetch(state.publicKey);
     assert.ok(stateAccount.owner.equals(owner.publicKey));
+    assert.ok(!stateAccount.authoritiesMigrated);
+  });
+
+  it("Migrates authorities to PDAs", async () => {
+    await program.methods
+      .migrateAuthorities()
+      .accounts({
+        state: state.publicKey,
+        series,
+        owner: owner.publicKey,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
+        collateralVault,
+        currentMintAuthority: owner.publicKey,
+        currentVaultAuthority: owner.publicKey,
+        mintAuthority,
+        vaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    const pfrtMintInfo = await pfrtMint.getMintInfo();
+    const vaultInfo = await collateralMint.getAccountInfo(collateralVault);
+    assert.ok(pfrtMintInfo.mintAuthority.equals(mintAuthority));
+    assert.ok(vaultInfo.owner.equals(vaultAuthority));
+  });
+
+  it("Opens a series for the next epoch", async () => {
+    const nextEpoch = new anchor.BN(1);
+    const nextSeries = await findSeriesAddress("series", nextEpoch);
+    const nextPfrtMint = await findSeriesAddress("pfrt_mint", nextEpoch);
+    const nextNfrtMint = await findSeriesAddress("nfrt_mint", nextEpoch);
+
+    await program.methods
+      .openSeries(nextEpoch)
+      .accounts({
+        state: state.publicKey,
+        series: nextSeries,
+        pfrtMint: nextPfrtMint,
+        nfrtMint: nextNfrtMint,
+        collateralMint: collateralMint.publicKey,
+        limitConfig,
+        epochManager: epochManager.publicKey,
+        mintAuthority,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const seriesAccount = await program.account.series.fetch(nextSeries);
+    assert.ok(seriesAccount.epochId.eq(nextEpoch));
+    assert.ok(seriesAccount.pfrtMint.equals(nextPfrtMint));
+    assert.ok(seriesAccount.nfrtMint.equals(nextNfrtMint));
+
+    // New series are Token-2022 mints carrying their own metadata
+    const mintAccount = await provider.connection.getAccountInfo(nextPfrtMint);
+    assert.ok(mintAccount.owner.equals(TOKEN_2022_PROGRAM_ID));
+    assert.ok(mintAccount.data.includes(Buffer.from("ZiroDelta PFRT Epoch 1")));
+  });
+
+  it("Updates the fee schedule through governance", async () => {
+    const tiers = [{ minVolume: new anchor.BN(1_000_000), discountBps: 5000 }];
+
+    await executeAsGovernance(
+      await program.methods
+        .updateFeeConfig(null, 20, 50, tiers)
+        .accounts({
+          state: state.publicKey,
+          feeConfig,
+          governance,
+        })
+        .instruction()
+    );
+
+    const config = await program.account.feeConfig.fetch(feeConfig);
+    assert.equal(config.mintFeeBps, 10);
+    assert.equal(config.redeemFeeBps, 20);
+    assert.equal(config.earlyRedemptionFeeBps, 50);
+    assert.equal(config.tiers.length, 1);
+
+    try {
+      await executeAsGovernance(
+        await program.methods
+          .updateFeeConfig(1000, null, null, null)
+          .accounts({
+            state: state.publicKey,
+            feeConfig,
+            governance,
+          })
+          .instruction()
+      );
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "FeeTooHigh");
+    }
+
+    // Reset redemption fees so the balances below stay round
+    await executeAsGovernance(
+      await program.methods
+        .updateFeeConfig(null, 0, 0, [])
+        .accounts({
+          state: state.publicKey,
+          feeConfig,
+          governance,
+        })
+        .instruction()
+    );
   });
 
   it("Mints tokens", async () => {
@@ -91,22 +444,34 @@ describe("ziro-delta-minting", () => {
     await collateralMint.mintTo(userCollateralAccount, owner, [], amount.toNumber());
 
     await program.methods
-      .mint(amount)
+      .mint(epochId, amount)
       .accounts({
         state: state.publicKey,
+        series,
         user: user.publicKey,
         userPfrtAccount,
         userNfrtAccount,
         userCollateralAccount,
         pfrtMint: pfrtMint.publicKey,
         nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
         collateralVault,
-        ownerCollateralAccount,
-        pfrtMintAuthority: owner.publicKey,
-        nfrtMintAuthority: owner.publicKey,
+        seriesCollateral,
+        collateralMint: collateralMint.publicKey,
+        feeConfig,
+        limitConfig,
+        userMintState,
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+        settlement,
+        mintAuthority,
+        vaultAuthority,
         tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .signers([user, owner])
+      .signers([user])
       .rpc();
 
     const userPfrtBalance = await pfrtMint.getAccountInfo(userPfrtAccount);
@@ -117,26 +482,44 @@ describe("ziro-delta-minting", () => {
 
     assert.ok(userPfrtBalance.amount.eq(netAmount));
     assert.ok(userNfrtBalance.amount.eq(netAmount));
+
+    const treasuryBalance = await collateralMint.getAccountInfo(treasuryVault);
+    assert.ok(treasuryBalance.amount.eq(fee));
+
+    // Fee tiers key on the wallet's cumulative volume
+    const mintState = await program.account.userMintState.fetch(userMintState);
+    assert.ok(mintState.cumulativeVolume.eq(amount));
   });
 
   it("Redeems tokens", async () => {
     const amount = new anchor.BN(100);
 
     await program.methods
-      .redeem(amount, amount)
+      .redeem(epochId, amount, amount)
       .accounts({
         state: state.publicKey,
+        series,
         user: user.publicKey,
         userPfrtAccount,
         userNfrtAccount,
         userCollateralAccount,
         pfrtMint: pfrtMint.publicKey,
         nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
         collateralVault,
-        collateralVaultAuthority: owner.publicKey,
+        seriesCollateral,
+        collateralMint: collateralMint.publicKey,
+        vaultAuthority,
+        feeConfig,
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+        settlement,
+        userMintState,
         tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
       })
-      .signers([user, owner])
+      .signers([user])
       .rpc();
 
     const userPfrtBalance = await pfrtMint.getAccountInfo(userPfrtAccount);
@@ -158,22 +541,34 @@ describe("ziro-delta-minting", () => {
 
     try {
       await program.methods
-        .mint(new anchor.BN(100))
+        .mint(epochId, new anchor.BN(100))
         .accounts({
           state: state.publicKey,
+          series,
           user: user.publicKey,
           userPfrtAccount,
           userNfrtAccount,
           userCollateralAccount,
           pfrtMint: pfrtMint.publicKey,
           nfrtMint: nfrtMint.publicKey,
+          collateralConfig,
           collateralVault,
-          ownerCollateralAccount,
-          pfrtMintAuthority: owner.publicKey,
-          nfrtMintAuthority: owner.publicKey,
+          seriesCollateral,
+          collateralMint: collateralMint.publicKey,
+          feeConfig,
+          limitConfig,
+          userMintState,
+          treasuryVaultInfo,
+          treasuryVault,
+          treasuryProgram: treasuryProgram.programId,
+          settlement,
+          mintAuthority,
+          vaultAuthority,
           tokenProgram: TOKEN_PROGRAM_ID,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([user, owner])
+        .signers([user])
         .rpc();
       assert.fail("Should have failed");
     } catch (err) {
@@ -189,4 +584,726 @@ describe("ziro-delta-minting", () => {
       .signers([owner])
       .rpc();
   });
+
+  it("Mints against a second collateral with different decimals", async () => {
+    usdcMint = await Token.createMint(
+      provider.connection,
+      owner,
+      owner.publicKey,
+      null,
+      6,
+      TOKEN_PROGRAM_ID
+    );
+    const userUsdcAccount = await usdcMint.createAccount(user.publicKey);
+
+    const [usdcConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("collateral"), state.publicKey.toBuffer(), usdcMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const [usdcVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("collateral_vault"), state.publicKey.toBuffer(), usdcMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const [usdcSeriesCollateral] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("series_collateral"), series.toBuffer(), usdcMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const [treasuryState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_state")],
+      treasuryProgram.programId
+    );
+    const [treasuryAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_authority")],
+      treasuryProgram.programId
+    );
+    const [usdcTreasuryVaultInfo] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vault_info"), usdcMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+    const [usdcTreasuryVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_vault"), usdcMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+
+    await treasuryProgram.methods
+      .createVault()
+      .accounts({
+        state: treasuryState,
+        vaultInfo: usdcTreasuryVaultInfo,
+        vault: usdcTreasuryVault,
+        treasuryAuthority,
+        mint: usdcMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner])
+      .rpc();
+
+    await program.methods
+      .addCollateral(new anchor.BN(1_000_000))
+      .accounts({
+        state: state.publicKey,
+        collateralConfig: usdcConfig,
+        collateralVault: usdcVault,
+        collateralMint: usdcMint.publicKey,
+        vaultAuthority,
+        owner: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner])
+      .rpc();
+
+    await usdcMint.mintTo(userUsdcAccount, owner, [], 2_000_000);
+
+    const mintWithUsdc = (amount: anchor.BN) =>
+      program.methods
+        .mint(epochId, amount)
+        .accounts({
+          state: state.publicKey,
+          series,
+          user: user.publicKey,
+          userPfrtAccount,
+          userNfrtAccount,
+          userCollateralAccount: userUsdcAccount,
+          pfrtMint: pfrtMint.publicKey,
+          nfrtMint: nfrtMint.publicKey,
+          collateralConfig: usdcConfig,
+          collateralVault: usdcVault,
+          seriesCollateral: usdcSeriesCollateral,
+          collateralMint: usdcMint.publicKey,
+          feeConfig,
+          limitConfig,
+          userMintState,
+          treasuryVaultInfo: usdcTreasuryVaultInfo,
+          treasuryVault: usdcTreasuryVault,
+          treasuryProgram: treasuryProgram.programId,
+          settlement,
+          mintAuthority,
+          vaultAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    const before = await pfrtMint.getAccountInfo(userPfrtAccount);
+    await mintWithUsdc(new anchor.BN(10_000));
+    const after = await pfrtMint.getAccountInfo(userPfrtAccount);
+
+    // 10_000 units at 6 decimals less the 10 bps fee, scaled to 9 decimals
+    assert.ok(after.amount.sub(before.amount).eq(new anchor.BN(9_990_000)));
+
+    const config = await program.account.collateralConfig.fetch(usdcConfig);
+    assert.ok(config.totalDeposited.eq(new anchor.BN(9_990)));
+    const seriesDeposit = await program.account.seriesCollateral.fetch(usdcSeriesCollateral);
+    assert.ok(seriesDeposit.deposited.eq(new anchor.BN(9_990)));
+
+    // Pairs backed by the base collateral can't be redeemed for more USDC
+    // than the series deposited
+    await collateralMint.mintTo(userCollateralAccount, owner, [], 10_000_000);
+    await program.methods
+      .mint(epochId, new anchor.BN(10_000_000))
+      .accounts({
+        state: state.publicKey,
+        series,
+        user: user.publicKey,
+        userPfrtAccount,
+        userNfrtAccount,
+        userCollateralAccount,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
+        collateralVault,
+        seriesCollateral,
+        collateralMint: collateralMint.publicKey,
+        feeConfig,
+        limitConfig,
+        userMintState,
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+        settlement,
+        mintAuthority,
+        vaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+    try {
+      await program.methods
+        .redeem(epochId, new anchor.BN(9_991_000), new anchor.BN(9_991_000))
+        .accounts({
+          state: state.publicKey,
+          series,
+          user: user.publicKey,
+          userPfrtAccount,
+          userNfrtAccount,
+          userCollateralAccount: userUsdcAccount,
+          pfrtMint: pfrtMint.publicKey,
+          nfrtMint: nfrtMint.publicKey,
+          collateralConfig: usdcConfig,
+          collateralVault: usdcVault,
+          seriesCollateral: usdcSeriesCollateral,
+          collateralMint: usdcMint.publicKey,
+          vaultAuthority,
+          feeConfig,
+          treasuryVaultInfo: usdcTreasuryVaultInfo,
+          treasuryVault: usdcTreasuryVault,
+          treasuryProgram: treasuryProgram.programId,
+          settlement,
+          userMintState,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InsufficientSeriesCollateral");
+    }
+
+    try {
+      await mintWithUsdc(new anchor.BN(1_000_000));
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "CollateralCapExceeded");
+    }
+
+    await program.methods
+      .updateCollateral(null, false)
+      .accounts({
+        state: state.publicKey,
+        collateralConfig: usdcConfig,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    try {
+      await mintWithUsdc(new anchor.BN(10_000));
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "CollateralDisabled");
+    }
+  });
+
+  it("Enforces series supply caps and wallet mint limits", async () => {
+    await collateralMint.mintTo(userCollateralAccount, owner, [], 1000);
+
+    const mintBase = (amount: anchor.BN) =>
+      program.methods
+        .mint(epochId, amount)
+        .accounts({
+          state: state.publicKey,
+          series,
+          user: user.publicKey,
+          userPfrtAccount,
+          userNfrtAccount,
+          userCollateralAccount,
+          pfrtMint: pfrtMint.publicKey,
+          nfrtMint: nfrtMint.publicKey,
+          collateralConfig,
+          collateralVault,
+          seriesCollateral,
+          collateralMint: collateralMint.publicKey,
+          feeConfig,
+          limitConfig,
+          userMintState,
+          treasuryVaultInfo,
+          treasuryVault,
+          treasuryProgram: treasuryProgram.programId,
+          settlement,
+          mintAuthority,
+          vaultAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+    const supply = (await nfrtMint.getMintInfo()).supply;
+    await executeAsGovernance(
+      await program.methods
+        .updateSeriesSupplyCap(epochId, supply.add(new anchor.BN(50)))
+        .accounts({
+          state: state.publicKey,
+          series,
+          limitConfig,
+          governance,
+        })
+        .instruction()
+    );
+
+    try {
+      await mintBase(new anchor.BN(100));
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SeriesSupplyCapExceeded");
+    }
+
+    await executeAsGovernance(
+      await program.methods
+        .updateSeriesSupplyCap(epochId, new anchor.BN("18446744073709551615"))
+        .accounts({
+          state: state.publicKey,
+          series,
+          limitConfig,
+          governance,
+        })
+        .instruction()
+    );
+
+    // The wallet has already minted more than this within the window
+    await executeAsGovernance(
+      await program.methods
+        .updateMintLimits(new anchor.BN(50), null, null)
+        .accounts({
+          state: state.publicKey,
+          limitConfig,
+          governance,
+        })
+        .instruction()
+    );
+
+    try {
+      await mintBase(new anchor.BN(100));
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UserMintLimitExceeded");
+    }
+  });
+
+  it("Redeems a single leg after settlement", async () => {
+    const [oracleState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("oracle_state")],
+      oracleProgram.programId
+    );
+    await oracleProgram.methods
+      .initialize(3, new anchor.BN(900), 500)
+      .accounts({
+        state: oracleState,
+        authority: owner.publicKey,
+        user: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    // Max positive funding pays the whole pair to PFRT
+    await oracleProgram.methods
+      .emergencyUpdateFundingRate(1000)
+      .accounts({ state: oracleState, authority: owner.publicKey })
+      .signers([owner])
+      .rpc();
+
+    await epochManagerProgram.methods
+      .settleEpoch()
+      .accounts({
+        epochManager: epochManager.publicKey,
+        settlement,
+        oracleState,
+        authority: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const before = await collateralMint.getAccountInfo(userCollateralAccount);
+    const pfrtBefore = await pfrtMint.getAccountInfo(userPfrtAccount);
+    const nfrtBefore = await nfrtMint.getAccountInfo(userNfrtAccount);
+
+    await program.methods
+      .redeemSettled(epochId, new anchor.BN(100), new anchor.BN(0))
+      .accounts({
+        state: state.publicKey,
+        series,
+        user: user.publicKey,
+        userPfrtAccount,
+        userNfrtAccount,
+        userCollateralAccount,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
+        collateralVault,
+        seriesCollateral,
+        collateralMint: collateralMint.publicKey,
+        vaultAuthority,
+        feeConfig,
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+        settlement,
+        userMintState,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const after = await collateralMint.getAccountInfo(userCollateralAccount);
+    const userPfrtBalance = await pfrtMint.getAccountInfo(userPfrtAccount);
+    const userNfrtBalance = await nfrtMint.getAccountInfo(userNfrtAccount);
+
+    assert.ok(after.amount.sub(before.amount).eq(new anchor.BN(100)));
+    assert.ok(pfrtBefore.amount.sub(userPfrtBalance.amount).eq(new anchor.BN(100)));
+    assert.ok(userNfrtBalance.amount.eq(nfrtBefore.amount));
+
+    // The payout is known, so the series no longer mints
+    try {
+      await program.methods
+        .mint(epochId, new anchor.BN(100))
+        .accounts({
+          state: state.publicKey,
+          series,
+          user: user.publicKey,
+          userPfrtAccount,
+          userNfrtAccount,
+          userCollateralAccount,
+          pfrtMint: pfrtMint.publicKey,
+          nfrtMint: nfrtMint.publicKey,
+          collateralConfig,
+          collateralVault,
+          seriesCollateral,
+          collateralMint: collateralMint.publicKey,
+          feeConfig,
+          limitConfig,
+          userMintState,
+          treasuryVaultInfo,
+          treasuryVault,
+          treasuryProgram: treasuryProgram.programId,
+          settlement,
+          mintAuthority,
+          vaultAuthority,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "EpochSettled");
+    }
+  });
+
+  it("Deploys idle collateral to a strategy and harvests yield", async () => {
+    const [strategyState] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("strategy_state"), collateralMint.publicKey.toBuffer()],
+      strategyProgram.programId
+    );
+    const [strategyVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("strategy_vault"), collateralMint.publicKey.toBuffer()],
+      strategyProgram.programId
+    );
+    const [strategyConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("strategy"), state.publicKey.toBuffer(), collateralMint.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await strategyProgram.methods
+      .initialize(vaultAuthority)
+      .accounts({
+        strategyState,
+        strategyVault,
+        mint: collateralMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    // The governance PDA pays for the strategy config
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(governance, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await executeAsGovernance(
+      await program.methods
+        .configureStrategy(5000)
+        .accounts({
+          state: state.publicKey,
+          collateralConfig,
+          strategyConfig,
+          strategyProgram: strategyProgram.programId,
+          strategyState,
+          strategyVault,
+          governance,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .instruction()
+    );
+
+    const strategyAccounts = {
+      state: state.publicKey,
+      collateralConfig,
+      strategyConfig,
+      strategyState,
+      strategyVault,
+      strategyProgram: strategyProgram.programId,
+      collateralVault,
+      collateralMint: collateralMint.publicKey,
+      vaultAuthority,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    try {
+      await executeAsGovernance(
+        await program.methods
+          .allocateToStrategy(new anchor.BN(1_000_000))
+          .accounts({ ...strategyAccounts, governance })
+          .instruction()
+      );
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "AllocationTooHigh");
+    }
+
+    await executeAsGovernance(
+      await program.methods
+        .allocateToStrategy(new anchor.BN(100))
+        .accounts({ ...strategyAccounts, governance })
+        .instruction()
+    );
+
+    // Simulate lending yield accruing in the strategy
+    await collateralMint.mintTo(strategyVault, owner, [], 7);
+
+    const treasuryBefore = await collateralMint.getAccountInfo(treasuryVault);
+    await program.methods
+      .harvestStrategy()
+      .accounts({
+        ...strategyAccounts,
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+      })
+      .rpc();
+    const treasuryAfter = await collateralMint.getAccountInfo(treasuryVault);
+    assert.ok(treasuryAfter.amount.sub(treasuryBefore.amount).eq(new anchor.BN(7)));
+
+    // Withdrawals are governance only and can't exceed the allocation
+    try {
+      await executeAsGovernance(
+        await program.methods
+          .withdrawFromStrategy(new anchor.BN(101))
+          .accounts({ ...strategyAccounts, governance })
+          .instruction()
+      );
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidAmount");
+    }
+
+    const vaultBefore = await collateralMint.getAccountInfo(collateralVault);
+    await executeAsGovernance(
+      await program.methods
+        .withdrawFromStrategy(new anchor.BN(100))
+        .accounts({ ...strategyAccounts, governance })
+        .instruction()
+    );
+    const vaultAfter = await collateralMint.getAccountInfo(collateralVault);
+    assert.ok(vaultAfter.amount.sub(vaultBefore.amount).eqn(100));
+
+    const config = await program.account.strategyConfig.fetch(strategyConfig);
+    assert.ok(config.allocated.eqn(0));
+    assert.ok(config.totalHarvested.eqn(7));
+  });
+
+  it("Verifies reserves and auto-pauses on insolvency", async () => {
+    const settlementFor = async (epoch: anchor.BN) =>
+      (
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from("settlement"), epochManager.publicKey.toBuffer(), epoch.toArrayLike(Buffer, "le", 8)],
+          epochManagerProgram.programId
+        )
+      )[0];
+    const strategyFor = async (mint: anchor.web3.PublicKey) =>
+      (
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from("strategy"), state.publicKey.toBuffer(), mint.toBuffer()],
+          program.programId
+        )
+      )[0];
+    const readonly = (pubkey: anchor.web3.PublicKey) => ({ pubkey, isWritable: false, isSigner: false });
+    const reserveAccounts = async (stateKey: anchor.web3.PublicKey) => ({
+      state: stateKey,
+      reserveCheckpoint: (
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from("reserve_checkpoint"), stateKey.toBuffer()],
+          program.programId
+        )
+      )[0],
+      payer: provider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    });
+
+    const nextEpoch = new anchor.BN(1);
+    const [usdcConfig] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("collateral"), state.publicKey.toBuffer(), usdcMint.publicKey.toBuffer()],
+      program.programId
+    );
+    const [usdcVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("collateral_vault"), state.publicKey.toBuffer(), usdcMint.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const seriesAccounts = [
+      readonly(series),
+      readonly(pfrtMint.publicKey),
+      readonly(nfrtMint.publicKey),
+      readonly(await settlementFor(epochId)),
+      readonly(await findSeriesAddress("series", nextEpoch)),
+      readonly(await findSeriesAddress("pfrt_mint", nextEpoch)),
+      readonly(await findSeriesAddress("nfrt_mint", nextEpoch)),
+      readonly(await settlementFor(nextEpoch)),
+    ];
+    const baseCollateral = [
+      readonly(collateralConfig),
+      readonly(collateralVault),
+      readonly(await strategyFor(collateralMint.publicKey)),
+    ];
+    const usdcCollateral = [
+      readonly(usdcConfig),
+      readonly(usdcVault),
+      readonly(await strategyFor(usdcMint.publicKey)),
+    ];
+
+    const accounts = await reserveAccounts(state.publicKey);
+    await program.methods
+      .verifyReserves(0)
+      .accounts(accounts)
+      .remainingAccounts([...seriesAccounts, ...baseCollateral, ...usdcCollateral])
+      .rpc();
+
+    // The same check paged one series at a time
+    await program.methods
+      .verifyReserves(0)
+      .accounts(accounts)
+      .remainingAccounts(seriesAccounts.slice(0, 4))
+      .rpc();
+    let checkpoint = await program.account.reserveCheckpoint.fetch(accounts.reserveCheckpoint);
+    assert.equal(checkpoint.nextSeries, 1);
+    await program.methods
+      .verifyReserves(1)
+      .accounts(accounts)
+      .remainingAccounts([...seriesAccounts.slice(4), ...baseCollateral, ...usdcCollateral])
+      .rpc();
+    checkpoint = await program.account.reserveCheckpoint.fetch(accounts.reserveCheckpoint);
+    assert.equal(checkpoint.nextSeries, 0);
+
+    // Every registered series and collateral has to be included exactly
+    // once, and pages have to follow on from the checkpoint
+    for (const [seriesStart, remainingAccounts] of [
+      [0, [...seriesAccounts, ...baseCollateral]],
+      [0, [...seriesAccounts.slice(0, 4), ...baseCollateral, ...usdcCollateral]],
+      [0, [...seriesAccounts, ...baseCollateral, ...baseCollateral]],
+      [0, [...seriesAccounts.slice(4), ...baseCollateral, ...usdcCollateral]],
+      [1, [...seriesAccounts.slice(4), ...baseCollateral, ...usdcCollateral]],
+    ] as [number, typeof seriesAccounts][]) {
+      try {
+        await program.methods
+          .verifyReserves(seriesStart)
+          .accounts(accounts)
+          .remainingAccounts(remainingAccounts)
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "InvalidReserveAccounts");
+      }
+    }
+
+    // A deployment whose pairs were minted before handing over the mints
+    // has outstanding PFRT/NFRT with no collateral behind it
+    const insolventState = anchor.web3.Keypair.generate();
+    const insolventSeed = insolventState.publicKey.toBuffer();
+    const findInsolventAddress = async (seeds: Buffer[]) =>
+      (await anchor.web3.PublicKey.findProgramAddress(seeds, program.programId))[0];
+    const unbackedPfrt = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    const unbackedNfrt = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    await unbackedPfrt.mintTo(await unbackedPfrt.createAccount(owner.publicKey), owner, [], 1000);
+    await unbackedNfrt.mintTo(await unbackedNfrt.createAccount(owner.publicKey), owner, [], 1000);
+    const unbackedVault = await collateralMint.createAccount(owner.publicKey);
+    const insolventSeries = await findInsolventAddress([
+      Buffer.from("series"),
+      insolventSeed,
+      epochId.toArrayLike(Buffer, "le", 8),
+    ]);
+    const insolventCollateralConfig = await findInsolventAddress([
+      Buffer.from("collateral"),
+      insolventSeed,
+      collateralMint.publicKey.toBuffer(),
+    ]);
+
+    await program.methods
+      .initialize(epochId)
+      .accounts({
+        state: insolventState.publicKey,
+        series: insolventSeries,
+        feeConfig: await findInsolventAddress([Buffer.from("fee_config"), insolventSeed]),
+        limitConfig: await findInsolventAddress([Buffer.from("limit_config"), insolventSeed]),
+        governance,
+        owner: owner.publicKey,
+        pfrtMint: unbackedPfrt.publicKey,
+        nfrtMint: unbackedNfrt.publicKey,
+        collateralVault: unbackedVault,
+        collateralMint: collateralMint.publicKey,
+        collateralConfig: insolventCollateralConfig,
+        epochManager: epochManager.publicKey,
+        mintAuthority: await findInsolventAddress([Buffer.from("mint_authority"), insolventSeed]),
+        vaultAuthority: await findInsolventAddress([Buffer.from("vault_authority"), insolventSeed]),
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner, insolventState])
+      .rpc();
+
+    const insolventAccounts = [
+      readonly(insolventSeries),
+      readonly(unbackedPfrt.publicKey),
+      readonly(unbackedNfrt.publicKey),
+      readonly(await settlementFor(epochId)),
+      readonly(insolventCollateralConfig),
+      readonly(unbackedVault),
+      readonly(
+        await findInsolventAddress([Buffer.from("strategy"), insolventSeed, collateralMint.publicKey.toBuffer()])
+      ),
+    ];
+
+    try {
+      await program.methods
+        .verifyReserves(0)
+        .accounts(await reserveAccounts(insolventState.publicKey))
+        .remainingAccounts(insolventAccounts)
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Insolvent");
+    }
+
+    await program.methods
+      .setAutoPause(true)
+      .accounts({
+        state: insolventState.publicKey,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    await program.methods
+      .verifyReserves(0)
+      .accounts(await reserveAccounts(insolventState.publicKey))
+      .remainingAccounts(insolventAccounts)
+      .rpc();
+
+    const stateAccount = await program.account.state.fetch(insolventState.publicKey);
+    assert.ok(stateAccount.paused);
+  });
 });
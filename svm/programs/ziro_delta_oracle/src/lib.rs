@@ -70,8 +70,8 @@ pub mod ziro_delta_oracle {
     }
 
     /// Update funding rate from oracle data
-    pub fn update_funding_rate(
-        ctx: Context<UpdateFundingRate>,
+    pub fn update_funding_rate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateFundingRate<'info>>,
         oracle_data: Vec<OracleDataInput>,
     ) -> Result<()> {
         let state = &mut ctx.accounts.state;
@@ -207,9 +207,9 @@ pub mod ziro_delta_oracle {
 }
 
 // Helper functions
-fn process_oracle_data(
+fn process_oracle_data<'info>(
     oracle_data: &Vec<OracleDataInput>,
-    remaining_accounts: &[AccountInfo],
+    remaining_accounts: &'info [AccountInfo<'info>],
     total_weight: u16,
 ) -> Result<(i32, u8)> {
     let mut weighted_sum: i64 = 0;
@@ -376,6 +376,18 @@ pub struct OracleState {
     pub twap_funding_rate: i32,
 }
 
+impl OracleState {
+    /// Rate consumers act on: the manual override while in emergency mode,
+    /// the aggregated oracle rate otherwise
+    pub fn effective_funding_rate(&self) -> i32 {
+        if self.emergency_mode {
+            self.emergency_funding_rate
+        } else {
+            self.current_funding_rate
+        }
+    }
+}
+
 #[account]
 pub struct OracleData {
     pub pubkey: Pubkey,
@@ -0,0 +1,524 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use ziro_delta_amm::program::ZiroDeltaAmm;
+use ziro_delta_amm::{AmmState, TwammState};
+use ziro_delta_minting::program::ZiroDeltaMinting;
+use ziro_delta_oracle::OracleState;
+
+declare_id!("EHSPbyb3ChzqCAp4SMfsZfVBycMrSR4oeSCGwTJpTrnV");
+
+/// Single-transaction entry and exit for one side of a series. Composes
+/// minting/redemption of pairs with an AMM swap of the unwanted leg, bounded
+/// by one slippage limit in collateral terms.
+#[program]
+pub mod ziro_delta_router {
+    use super::*;
+
+    /// Mint pairs with `collateral_amount` and sell the NFRT leg for PFRT.
+    /// `max_collateral_per_pfrt` is the worst acceptable price, in collateral
+    /// base units per whole PFRT.
+    pub fn buy_with_collateral(
+        ctx: Context<BuyWithCollateral>,
+        epoch_id: u64,
+        collateral_amount: u64,
+        max_collateral_per_pfrt: u64,
+    ) -> Result<()> {
+        require!(collateral_amount > 0, RouterError::InvalidAmount);
+
+        let collateral_before = ctx.accounts.user_collateral_account.amount;
+        let pfrt_before = ctx.accounts.user_pfrt_account.amount;
+        let nfrt_before = ctx.accounts.user_nfrt_account.amount;
+
+        ziro_delta_minting::cpi::mint(
+            CpiContext::new(
+                ctx.accounts.minting_program.to_account_info(),
+                ziro_delta_minting::cpi::accounts::MintTokens {
+                    user: ctx.accounts.user.to_account_info(),
+                    user_pfrt_account: ctx.accounts.user_pfrt_account.to_account_info(),
+                    user_nfrt_account: ctx.accounts.user_nfrt_account.to_account_info(),
+                    user_collateral_account: ctx.accounts.user_collateral_account.to_account_info(),
+                    series: ctx.accounts.series.to_account_info(),
+                    pfrt_mint: ctx.accounts.pfrt_mint.to_account_info(),
+                    nfrt_mint: ctx.accounts.nfrt_mint.to_account_info(),
+                    collateral_config: ctx.accounts.collateral_config.to_account_info(),
+                    collateral_vault: ctx.accounts.collateral_vault.to_account_info(),
+                    series_collateral: ctx.accounts.series_collateral.to_account_info(),
+                    collateral_mint: ctx.accounts.collateral_mint.to_account_info(),
+                    fee_config: ctx.accounts.fee_config.to_account_info(),
+                    limit_config: ctx.accounts.limit_config.to_account_info(),
+                    user_mint_state: ctx.accounts.user_mint_state.to_account_info(),
+                    treasury_vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                    treasury_vault: ctx.accounts.treasury_vault.to_account_info(),
+                    treasury_program: ctx.accounts.treasury_program.to_account_info(),
+                    settlement: ctx.accounts.settlement.to_account_info(),
+                    mint_authority: ctx.accounts.mint_authority.to_account_info(),
+                    vault_authority: ctx.accounts.vault_authority.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    collateral_token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    state: ctx.accounts.minting_state.to_account_info(),
+                },
+            ),
+            epoch_id,
+            collateral_amount,
+        )?;
+
+        ctx.accounts.user_nfrt_account.reload()?;
+        let nfrt_minted = ctx
+            .accounts
+            .user_nfrt_account
+            .amount
+            .checked_sub(nfrt_before)
+            .ok_or(RouterError::Overflow)?;
+
+        // The pool's own min_amount_out is left open; the collateral price
+        // bound below covers both legs at once
+        ziro_delta_amm::cpi::swap(
+            CpiContext::new(
+                ctx.accounts.amm_program.to_account_info(),
+                ziro_delta_amm::cpi::accounts::Swap {
+                    state: ctx.accounts.pool.to_account_info(),
+                    pfrt_mint: ctx.accounts.pfrt_mint.to_account_info(),
+                    nfrt_mint: ctx.accounts.nfrt_mint.to_account_info(),
+                    user_trade_state: ctx.accounts.user_trade_state.as_ref().map(|a| a.to_account_info()),
+                    observations: ctx.accounts.observations.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                    pfrt_vault: ctx.accounts.pfrt_vault.to_account_info(),
+                    nfrt_vault: ctx.accounts.nfrt_vault.to_account_info(),
+                    user_pfrt_account: ctx.accounts.user_pfrt_account.to_account_info(),
+                    user_nfrt_account: ctx.accounts.user_nfrt_account.to_account_info(),
+                    user: ctx.accounts.user.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    fee_oracle: ctx.accounts.fee_oracle.as_ref().map(|o| o.to_account_info()),
+                    market_maker: ctx.accounts.market_maker.as_ref().map(|m| m.to_account_info()),
+                    twamm: ctx.accounts.twamm.as_ref().map(|t| t.to_account_info()),
+                },
+            ),
+            nfrt_minted,
+            0,
+            false,
+        )?;
+
+        ctx.accounts.user_collateral_account.reload()?;
+        ctx.accounts.user_pfrt_account.reload()?;
+        let collateral_spent = collateral_before
+            .checked_sub(ctx.accounts.user_collateral_account.amount)
+            .ok_or(RouterError::Overflow)?;
+        let pfrt_received = ctx
+            .accounts
+            .user_pfrt_account
+            .amount
+            .checked_sub(pfrt_before)
+            .ok_or(RouterError::Overflow)?;
+
+        // collateral_spent / pfrt_received <= max_collateral_per_pfrt
+        let one_pfrt = 10u128
+            .checked_pow(ctx.accounts.pfrt_mint.decimals as u32)
+            .ok_or(RouterError::Overflow)?;
+        let cost = (collateral_spent as u128)
+            .checked_mul(one_pfrt)
+            .ok_or(RouterError::Overflow)?;
+        let limit = (max_collateral_per_pfrt as u128)
+            .checked_mul(pfrt_received as u128)
+            .ok_or(RouterError::Overflow)?;
+        require!(cost <= limit, RouterError::SlippageExceeded);
+
+        emit!(CollateralBuyEvent {
+            user: ctx.accounts.user.key(),
+            epoch_id,
+            pool: ctx.accounts.pool.key(),
+            collateral_spent,
+            nfrt_sold: nfrt_minted,
+            pfrt_received,
+        });
+
+        Ok(())
+    }
+
+    /// Sell part of `pfrt_amount` for NFRT and redeem the resulting pairs.
+    /// Any unpaired PFRT left by rounding stays with the user.
+    pub fn sell_for_collateral(
+        ctx: Context<SellForCollateral>,
+        epoch_id: u64,
+        pfrt_amount: u64,
+        min_collateral_out: u64,
+    ) -> Result<()> {
+        require!(pfrt_amount > 0, RouterError::InvalidAmount);
+        require!(
+            pfrt_amount <= ctx.accounts.user_pfrt_account.amount,
+            RouterError::InvalidAmount
+        );
+
+        // Split against the pool as the swap will see it, long-term orders settled
+        let pool = &settled_pool(&ctx.accounts.pool, ctx.accounts.twamm.as_ref())?;
+        require!(
+            pool.pfrt_balance > 0 && pool.nfrt_balance > 0,
+            RouterError::InsufficientLiquidity
+        );
+        let fee_bps = ziro_delta_amm::effective_fee_bps(
+            pool,
+            ctx.accounts.fee_oracle.as_deref().map(|o| &**o),
+        )?;
+        let swap_amount = split_for_pairs(pool, fee_bps, pfrt_amount)?;
+        require!(swap_amount > 0, RouterError::InsufficientLiquidity);
+
+        let collateral_before = ctx.accounts.user_collateral_account.amount;
+        let nfrt_before = ctx.accounts.user_nfrt_account.amount;
+
+        ziro_delta_amm::cpi::swap(
+            CpiContext::new(
+                ctx.accounts.amm_program.to_account_info(),
+                ziro_delta_amm::cpi::accounts::Swap {
+                    state: ctx.accounts.pool.to_account_info(),
+                    pfrt_mint: ctx.accounts.pfrt_mint.to_account_info(),
+                    nfrt_mint: ctx.accounts.nfrt_mint.to_account_info(),
+                    user_trade_state: ctx.accounts.user_trade_state.as_ref().map(|a| a.to_account_info()),
+                    observations: ctx.accounts.observations.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                    pfrt_vault: ctx.accounts.pfrt_vault.to_account_info(),
+                    nfrt_vault: ctx.accounts.nfrt_vault.to_account_info(),
+                    user_pfrt_account: ctx.accounts.user_pfrt_account.to_account_info(),
+                    user_nfrt_account: ctx.accounts.user_nfrt_account.to_account_info(),
+                    user: ctx.accounts.user.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    fee_oracle: ctx.accounts.fee_oracle.as_ref().map(|o| o.to_account_info()),
+                    market_maker: ctx.accounts.market_maker.as_ref().map(|m| m.to_account_info()),
+                    twamm: ctx.accounts.twamm.as_ref().map(|t| t.to_account_info()),
+                },
+            ),
+            swap_amount,
+            0,
+            true,
+        )?;
+
+        ctx.accounts.user_nfrt_account.reload()?;
+        let nfrt_received = ctx
+            .accounts
+            .user_nfrt_account
+            .amount
+            .checked_sub(nfrt_before)
+            .ok_or(RouterError::Overflow)?;
+        let pairs = nfrt_received.min(pfrt_amount - swap_amount);
+        require!(pairs > 0, RouterError::InsufficientLiquidity);
+
+        ziro_delta_minting::cpi::redeem(
+            CpiContext::new(
+                ctx.accounts.minting_program.to_account_info(),
+                ziro_delta_minting::cpi::accounts::RedeemTokens {
+                    user: ctx.accounts.user.to_account_info(),
+                    user_pfrt_account: ctx.accounts.user_pfrt_account.to_account_info(),
+                    user_nfrt_account: ctx.accounts.user_nfrt_account.to_account_info(),
+                    user_collateral_account: ctx.accounts.user_collateral_account.to_account_info(),
+                    series: ctx.accounts.series.to_account_info(),
+                    pfrt_mint: ctx.accounts.pfrt_mint.to_account_info(),
+                    nfrt_mint: ctx.accounts.nfrt_mint.to_account_info(),
+                    collateral_config: ctx.accounts.collateral_config.to_account_info(),
+                    collateral_vault: ctx.accounts.collateral_vault.to_account_info(),
+                    series_collateral: ctx.accounts.series_collateral.to_account_info(),
+                    collateral_mint: ctx.accounts.collateral_mint.to_account_info(),
+                    vault_authority: ctx.accounts.vault_authority.to_account_info(),
+                    fee_config: ctx.accounts.fee_config.to_account_info(),
+                    treasury_vault_info: ctx.accounts.treasury_vault_info.to_account_info(),
+                    treasury_vault: ctx.accounts.treasury_vault.to_account_info(),
+                    treasury_program: ctx.accounts.treasury_program.to_account_info(),
+                    settlement: ctx.accounts.settlement.to_account_info(),
+                    user_mint_state: ctx
+                        .accounts
+                        .user_mint_state
+                        .as_ref()
+                        .map(|s| s.to_account_info()),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    collateral_token_program: ctx.accounts.collateral_token_program.to_account_info(),
+                    state: ctx.accounts.minting_state.to_account_info(),
+                },
+            ),
+            epoch_id,
+            pairs,
+            pairs,
+        )?;
+
+        ctx.accounts.user_collateral_account.reload()?;
+        let collateral_received = ctx
+            .accounts
+            .user_collateral_account
+            .amount
+            .checked_sub(collateral_before)
+            .ok_or(RouterError::Overflow)?;
+        require!(
+            collateral_received >= min_collateral_out,
+            RouterError::SlippageExceeded
+        );
+
+        emit!(CollateralSellEvent {
+            user: ctx.accounts.user.key(),
+            epoch_id,
+            pool: ctx.accounts.pool.key(),
+            pfrt_sold: swap_amount,
+            pfrt_redeemed: pairs,
+            collateral_received,
+        });
+
+        Ok(())
+    }
+}
+
+fn settled_pool(pool: &Account<AmmState>, twamm: Option<&AccountLoader<TwammState>>) -> Result<AmmState> {
+    let twamm = twamm.map(|t| t.load()).transpose()?;
+    if let Some(twamm) = &twamm {
+        require_keys_eq!(twamm.pool, pool.key(), RouterError::PoolMismatch);
+    }
+    ziro_delta_amm::settled_pool_state(pool, twamm.as_deref(), Clock::get()?.unix_timestamp)
+}
+
+// Largest PFRT amount to swap so its NFRT proceeds still pair with the PFRT
+// kept back, i.e. the smallest x with out(x) >= pfrt_amount - x
+fn split_for_pairs(pool: &AmmState, fee_bps: u16, pfrt_amount: u64) -> Result<u64> {
+    let mut low = 0u64;
+    let mut high = pfrt_amount;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (nfrt_out, _) = ziro_delta_amm::calculate_swap_amount(pool, mid, fee_bps, true)?;
+        if nfrt_out >= pfrt_amount - mid {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(low)
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct BuyWithCollateral<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = pfrt_mint,
+        token::authority = user
+    )]
+    pub user_pfrt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = nfrt_mint,
+        token::authority = user
+    )]
+    pub user_nfrt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pfrt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub nfrt_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Minting accounts, validated by the minting program
+    /// CHECK: Series PDA checked by ziro_delta_minting
+    pub series: UncheckedAccount<'info>,
+    /// CHECK: Collateral config checked by ziro_delta_minting
+    #[account(mut)]
+    pub collateral_config: UncheckedAccount<'info>,
+    /// CHECK: Collateral vault checked by ziro_delta_minting
+    #[account(mut)]
+    pub collateral_vault: UncheckedAccount<'info>,
+    /// CHECK: Series collateral record checked by ziro_delta_minting
+    #[account(mut)]
+    pub series_collateral: UncheckedAccount<'info>,
+    /// CHECK: Fee config checked by ziro_delta_minting
+    pub fee_config: UncheckedAccount<'info>,
+    /// CHECK: Limit config checked by ziro_delta_minting
+    pub limit_config: UncheckedAccount<'info>,
+    /// CHECK: User mint state initialised and checked by ziro_delta_minting
+    #[account(mut)]
+    pub user_mint_state: UncheckedAccount<'info>,
+    /// CHECK: Treasury vault info checked by ziro_delta_minting
+    #[account(mut)]
+    pub treasury_vault_info: UncheckedAccount<'info>,
+    /// CHECK: Treasury vault checked by ziro_delta_minting
+    #[account(mut)]
+    pub treasury_vault: UncheckedAccount<'info>,
+    /// CHECK: Treasury program checked by ziro_delta_minting
+    pub treasury_program: UncheckedAccount<'info>,
+    /// CHECK: Epoch settlement PDA checked by ziro_delta_minting
+    pub settlement: UncheckedAccount<'info>,
+    /// CHECK: Mint authority PDA checked by ziro_delta_minting
+    pub mint_authority: UncheckedAccount<'info>,
+    /// CHECK: Vault authority PDA checked by ziro_delta_minting
+    pub vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Minting state checked by ziro_delta_minting
+    #[account(mut)]
+    pub minting_state: UncheckedAccount<'info>,
+
+    // Pool accounts, validated by the AMM
+    #[account(
+        mut,
+        constraint = pool.pfrt_mint == pfrt_mint.key() @ RouterError::PoolMismatch,
+        constraint = pool.nfrt_mint == nfrt_mint.key() @ RouterError::PoolMismatch
+    )]
+    pub pool: Box<Account<'info, AmmState>>,
+    /// CHECK: Optional user trade state, initialised and checked by ziro_delta_amm
+    #[account(mut)]
+    pub user_trade_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Observation buffer checked by ziro_delta_amm
+    #[account(mut)]
+    pub observations: UncheckedAccount<'info>,
+    /// CHECK: Pool authority PDA checked by ziro_delta_amm
+    pub pool_authority: UncheckedAccount<'info>,
+    /// CHECK: Pool vault checked by ziro_delta_amm
+    #[account(mut)]
+    pub pfrt_vault: UncheckedAccount<'info>,
+    /// CHECK: Pool vault checked by ziro_delta_amm
+    #[account(mut)]
+    pub nfrt_vault: UncheckedAccount<'info>,
+    #[account(address = pool.fee_oracle @ RouterError::PoolMismatch)]
+    pub fee_oracle: Option<Box<Account<'info, OracleState>>>,
+    /// CHECK: Optional market-maker allowlist entry, checked by ziro_delta_amm
+    pub market_maker: Option<UncheckedAccount<'info>>,
+    /// CHECK: Pool's TWAMM account, checked by ziro_delta_amm
+    #[account(mut)]
+    pub twamm: Option<UncheckedAccount<'info>>,
+
+    pub minting_program: Program<'info, ZiroDeltaMinting>,
+    pub amm_program: Program<'info, ZiroDeltaAmm>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_id: u64)]
+pub struct SellForCollateral<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = pfrt_mint,
+        token::authority = user
+    )]
+    pub user_pfrt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = nfrt_mint,
+        token::authority = user
+    )]
+    pub user_nfrt_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = user
+    )]
+    pub user_collateral_account: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pfrt_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub nfrt_mint: Box<InterfaceAccount<'info, Mint>>,
+    pub collateral_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    // Minting accounts, validated by the minting program
+    /// CHECK: Series PDA checked by ziro_delta_minting
+    pub series: UncheckedAccount<'info>,
+    /// CHECK: Collateral config checked by ziro_delta_minting
+    #[account(mut)]
+    pub collateral_config: UncheckedAccount<'info>,
+    /// CHECK: Collateral vault checked by ziro_delta_minting
+    #[account(mut)]
+    pub collateral_vault: UncheckedAccount<'info>,
+    /// CHECK: Series collateral record checked by ziro_delta_minting
+    #[account(mut)]
+    pub series_collateral: UncheckedAccount<'info>,
+    /// CHECK: Fee config checked by ziro_delta_minting
+    pub fee_config: UncheckedAccount<'info>,
+    /// CHECK: Epoch settlement PDA checked by ziro_delta_minting
+    pub settlement: UncheckedAccount<'info>,
+    /// CHECK: Optional user mint state checked by ziro_delta_minting
+    #[account(mut)]
+    pub user_mint_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Treasury vault info checked by ziro_delta_minting
+    #[account(mut)]
+    pub treasury_vault_info: UncheckedAccount<'info>,
+    /// CHECK: Treasury vault checked by ziro_delta_minting
+    #[account(mut)]
+    pub treasury_vault: UncheckedAccount<'info>,
+    /// CHECK: Treasury program checked by ziro_delta_minting
+    pub treasury_program: UncheckedAccount<'info>,
+    /// CHECK: Vault authority PDA checked by ziro_delta_minting
+    pub vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Minting state checked by ziro_delta_minting
+    #[account(mut)]
+    pub minting_state: UncheckedAccount<'info>,
+
+    // Pool accounts, validated by the AMM
+    #[account(
+        mut,
+        constraint = pool.pfrt_mint == pfrt_mint.key() @ RouterError::PoolMismatch,
+        constraint = pool.nfrt_mint == nfrt_mint.key() @ RouterError::PoolMismatch
+    )]
+    pub pool: Box<Account<'info, AmmState>>,
+    /// CHECK: Optional user trade state, initialised and checked by ziro_delta_amm
+    #[account(mut)]
+    pub user_trade_state: Option<UncheckedAccount<'info>>,
+    /// CHECK: Observation buffer checked by ziro_delta_amm
+    #[account(mut)]
+    pub observations: UncheckedAccount<'info>,
+    /// CHECK: Pool authority PDA checked by ziro_delta_amm
+    pub pool_authority: UncheckedAccount<'info>,
+    /// CHECK: Pool vault checked by ziro_delta_amm
+    #[account(mut)]
+    pub pfrt_vault: UncheckedAccount<'info>,
+    /// CHECK: Pool vault checked by ziro_delta_amm
+    #[account(mut)]
+    pub nfrt_vault: UncheckedAccount<'info>,
+    #[account(address = pool.fee_oracle @ RouterError::PoolMismatch)]
+    pub fee_oracle: Option<Box<Account<'info, OracleState>>>,
+    /// CHECK: Optional market-maker allowlist entry, checked by ziro_delta_amm
+    pub market_maker: Option<UncheckedAccount<'info>>,
+    /// Pool's TWAMM account; its seeds are checked by ziro_delta_amm
+    #[account(mut)]
+    pub twamm: Option<AccountLoader<'info, TwammState>>,
+
+    pub minting_program: Program<'info, ZiroDeltaMinting>,
+    pub amm_program: Program<'info, ZiroDeltaAmm>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct CollateralBuyEvent {
+    pub user: Pubkey,
+    pub epoch_id: u64,
+    pub pool: Pubkey,
+    pub collateral_spent: u64,
+    pub nfrt_sold: u64,
+    pub pfrt_received: u64,
+}
+
+#[event]
+pub struct CollateralSellEvent {
+    pub user: Pubkey,
+    pub epoch_id: u64,
+    pub pool: Pubkey,
+    pub pfrt_sold: u64,
+    pub pfrt_redeemed: u64,
+    pub collateral_received: u64,
+}
+
+#[error_code]
+pub enum RouterError {
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Pool does not trade this series")]
+    PoolMismatch,
+    #[msg("Insufficient liquidity")]
+    InsufficientLiquidity,
+    #[msg("Collateral slippage exceeded")]
+    SlippageExceeded,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+}
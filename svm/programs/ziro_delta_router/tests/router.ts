@@ -0,0 +1,423 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import { ZiroDeltaRouter } from "../target/types/ziro_delta_router";
+import { ZiroDeltaMinting } from "../target/types/ziro_delta_minting";
+import { ZiroDeltaAmm } from "../target/types/ziro_delta_amm";
+import { ZiroDeltaEpochManager } from "../target/types/ziro_delta_epoch_manager";
+import { ZiroDeltaTreasury } from "../target/types/ziro_delta_treasury";
+import { ZiroDeltaGovernance } from "../target/types/ziro_delta_governance";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { assert } from "chai";
+
+describe("ziro-delta-router", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.ZiroDeltaRouter as Program<ZiroDeltaRouter>;
+  const mintingProgram = anchor.workspace.ZiroDeltaMinting as Program<ZiroDeltaMinting>;
+  const ammProgram = anchor.workspace.ZiroDeltaAmm as Program<ZiroDeltaAmm>;
+  const epochManagerProgram = anchor.workspace.ZiroDeltaEpochManager as Program<ZiroDeltaEpochManager>;
+  const treasuryProgram = anchor.workspace.ZiroDeltaTreasury as Program<ZiroDeltaTreasury>;
+  const governanceProgram = anchor.workspace.ZiroDeltaGovernance as Program<ZiroDeltaGovernance>;
+
+  const findAddress = async (seeds: Buffer[], programId: anchor.web3.PublicKey) =>
+    (await anchor.web3.PublicKey.findProgramAddress(seeds, programId))[0];
+
+  const state = anchor.web3.Keypair.generate();
+  const epochManager = anchor.web3.Keypair.generate();
+  const owner = anchor.web3.Keypair.generate();
+  const governance = anchor.web3.Keypair.generate();
+  const user = anchor.web3.Keypair.generate();
+
+  const epochId = new anchor.BN(0);
+  const ONE = 1_000_000_000;
+
+  let pfrtMint: Token;
+  let nfrtMint: Token;
+  let collateralMint: Token;
+
+  let userPfrtAccount: anchor.web3.PublicKey;
+  let userNfrtAccount: anchor.web3.PublicKey;
+  let userCollateralAccount: anchor.web3.PublicKey;
+
+  let mintingAccounts: any;
+  let mintOnlyAccounts: any;
+  let poolAccounts: any;
+
+  before(async () => {
+    await provider.connection.requestAirdrop(owner.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL);
+    await provider.connection.requestAirdrop(user.publicKey, 100 * anchor.web3.LAMPORTS_PER_SOL);
+
+    pfrtMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    nfrtMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    collateralMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+    const zdltMint = await Token.createMint(provider.connection, owner, owner.publicKey, null, 9, TOKEN_PROGRAM_ID);
+
+    userPfrtAccount = await pfrtMint.createAccount(user.publicKey);
+    userNfrtAccount = await nfrtMint.createAccount(user.publicKey);
+    userCollateralAccount = await collateralMint.createAccount(user.publicKey);
+    const collateralVault = await collateralMint.createAccount(owner.publicKey);
+
+    // Treasury vault that receives minting fees
+    const treasuryState = await findAddress([Buffer.from("treasury_state")], treasuryProgram.programId);
+    const treasuryAuthority = await findAddress([Buffer.from("treasury_authority")], treasuryProgram.programId);
+    const treasuryVaultInfo = await findAddress(
+      [Buffer.from("vault_info"), collateralMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+    const treasuryVault = await findAddress(
+      [Buffer.from("treasury_vault"), collateralMint.publicKey.toBuffer()],
+      treasuryProgram.programId
+    );
+
+    await treasuryProgram.methods
+      .initialize(governance.publicKey)
+      .accounts({
+        state: treasuryState,
+        treasuryAuthority,
+        zdltMint: zdltMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await treasuryProgram.methods
+      .createVault()
+      .accounts({
+        state: treasuryState,
+        vaultInfo: treasuryVaultInfo,
+        vault: treasuryVault,
+        treasuryAuthority,
+        mint: collateralMint.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner])
+      .rpc();
+
+    // Minting program with series 0 over the PFRT/NFRT mints above
+    const stateSeed = state.publicKey.toBuffer();
+    const mintAuthority = await findAddress([Buffer.from("mint_authority"), stateSeed], mintingProgram.programId);
+    const vaultAuthority = await findAddress([Buffer.from("vault_authority"), stateSeed], mintingProgram.programId);
+    const series = await findAddress(
+      [Buffer.from("series"), stateSeed, epochId.toArrayLike(Buffer, "le", 8)],
+      mintingProgram.programId
+    );
+    const feeConfig = await findAddress([Buffer.from("fee_config"), stateSeed], mintingProgram.programId);
+    const limitConfig = await findAddress([Buffer.from("limit_config"), stateSeed], mintingProgram.programId);
+    const collateralConfig = await findAddress(
+      [Buffer.from("collateral"), stateSeed, collateralMint.publicKey.toBuffer()],
+      mintingProgram.programId
+    );
+    const seriesCollateral = await findAddress(
+      [Buffer.from("series_collateral"), series.toBuffer(), collateralMint.publicKey.toBuffer()],
+      mintingProgram.programId
+    );
+
+    await epochManagerProgram.methods
+      .initialize(epochId)
+      .accounts({
+        epochManager: epochManager.publicKey,
+        authority: owner.publicKey,
+        user: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner, epochManager])
+      .rpc();
+
+    // Minting fees and limits are owned by the governance program's PDA
+    const governanceAuthority = await findAddress(
+      [Buffer.from("governance_authority")],
+      governanceProgram.programId
+    );
+
+    await mintingProgram.methods
+      .initialize(epochId)
+      .accounts({
+        state: state.publicKey,
+        series,
+        feeConfig,
+        limitConfig,
+        governance: governanceAuthority,
+        owner: owner.publicKey,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralVault,
+        collateralMint: collateralMint.publicKey,
+        collateralConfig,
+        epochManager: epochManager.publicKey,
+        mintAuthority,
+        vaultAuthority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner, state])
+      .rpc();
+
+    await mintingProgram.methods
+      .migrateAuthorities()
+      .accounts({
+        state: state.publicKey,
+        series,
+        owner: owner.publicKey,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
+        collateralVault,
+        currentMintAuthority: owner.publicKey,
+        currentVaultAuthority: owner.publicKey,
+        mintAuthority,
+        vaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    mintingAccounts = {
+      mintingState: state.publicKey,
+      series,
+      collateralConfig,
+      collateralVault,
+      seriesCollateral,
+      collateralMint: collateralMint.publicKey,
+      feeConfig,
+      settlement: await findAddress(
+        [Buffer.from("settlement"), epochManager.publicKey.toBuffer(), epochId.toArrayLike(Buffer, "le", 8)],
+        epochManagerProgram.programId
+      ),
+      treasuryVaultInfo,
+      treasuryVault,
+      treasuryProgram: treasuryProgram.programId,
+      vaultAuthority,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+      mintingProgram: mintingProgram.programId,
+    };
+
+    // Seed a pool for the series with pairs minted by the owner
+    const ownerPfrtAccount = await pfrtMint.createAccount(owner.publicKey);
+    const ownerNfrtAccount = await nfrtMint.createAccount(owner.publicKey);
+    const ownerCollateralAccount = await collateralMint.createAccount(owner.publicKey);
+    await collateralMint.mintTo(ownerCollateralAccount, owner, [], 100_000 * ONE);
+
+    await mintingProgram.methods
+      .mint(epochId, new anchor.BN(100_000 * ONE))
+      .accounts({
+        state: state.publicKey,
+        series,
+        user: owner.publicKey,
+        userPfrtAccount: ownerPfrtAccount,
+        userNfrtAccount: ownerNfrtAccount,
+        userCollateralAccount: ownerCollateralAccount,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        collateralConfig,
+        collateralVault,
+        seriesCollateral,
+        collateralMint: collateralMint.publicKey,
+        feeConfig,
+        limitConfig,
+        userMintState: await findAddress(
+          [Buffer.from("user_mint"), stateSeed, owner.publicKey.toBuffer()],
+          mintingProgram.programId
+        ),
+        treasuryVaultInfo,
+        treasuryVault,
+        treasuryProgram: treasuryProgram.programId,
+        settlement: mintingAccounts.settlement,
+        mintAuthority,
+        vaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const factory = await findAddress([Buffer.from("factory")], ammProgram.programId);
+    const pool = await findAddress(
+      [Buffer.from("pool"), pfrtMint.publicKey.toBuffer(), nfrtMint.publicKey.toBuffer()],
+      ammProgram.programId
+    );
+    const poolAuthority = await findAddress([Buffer.from("authority"), pool.toBuffer()], ammProgram.programId);
+    const pfrtVault = await findAddress([Buffer.from("pfrt_vault"), pool.toBuffer()], ammProgram.programId);
+    const nfrtVault = await findAddress([Buffer.from("nfrt_vault"), pool.toBuffer()], ammProgram.programId);
+    const lpMint = await findAddress([Buffer.from("lp_mint"), pool.toBuffer()], ammProgram.programId);
+    const observations = await findAddress([Buffer.from("observations"), pool.toBuffer()], ammProgram.programId);
+
+    await ammProgram.methods
+      .initializeFactory(governance.publicKey)
+      .accounts({
+        factory,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    await ammProgram.methods
+      .createPool(
+        epochId,
+        30,
+        new anchor.BN(10_000 * ONE),
+        new anchor.BN(1_000_000 * ONE),
+        500,
+        { constantProduct: {} }
+      )
+      .accounts({
+        factory,
+        poolListing: await findAddress(
+          [Buffer.from("pool_listing"), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+          ammProgram.programId
+        ),
+        state: pool,
+        authority: poolAuthority,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        pfrtVault,
+        nfrtVault,
+        lpMint,
+        observations,
+        governance: governance.publicKey,
+        user: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([owner, governance])
+      .rpc();
+
+    const lpToken = new Token(provider.connection, lpMint, TOKEN_PROGRAM_ID, owner);
+    const ownerLpAccount = await lpToken.createAccount(owner.publicKey);
+    const depth = new anchor.BN(99_900 * ONE);
+
+    await ammProgram.methods
+      .addLiquidity(depth, depth, new anchor.BN(0))
+      .accounts({
+        state: pool,
+        authority: poolAuthority,
+        pfrtMint: pfrtMint.publicKey,
+        nfrtMint: nfrtMint.publicKey,
+        lpMint,
+        observations,
+        pfrtVault,
+        nfrtVault,
+        userPfrtAccount: ownerPfrtAccount,
+        userNfrtAccount: ownerNfrtAccount,
+        userLpAccount: ownerLpAccount,
+        user: owner.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    poolAccounts = {
+      pool,
+      userTradeState: await findAddress(
+        [Buffer.from("user_trade"), pool.toBuffer(), user.publicKey.toBuffer()],
+        ammProgram.programId
+      ),
+      observations,
+      poolAuthority,
+      pfrtVault,
+      nfrtVault,
+      ammProgram: ammProgram.programId,
+    };
+
+    mintOnlyAccounts = {
+      limitConfig,
+      mintAuthority,
+      userMintState: await findAddress(
+        [Buffer.from("user_mint"), stateSeed, user.publicKey.toBuffer()],
+        mintingProgram.programId
+      ),
+    };
+  });
+
+  const userAccounts = () => ({
+    user: user.publicKey,
+    userPfrtAccount,
+    userNfrtAccount,
+    userCollateralAccount,
+    pfrtMint: pfrtMint.publicKey,
+    nfrtMint: nfrtMint.publicKey,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  it("Buys PFRT with collateral in one transaction", async () => {
+    const amount = new anchor.BN(1000 * ONE);
+    await collateralMint.mintTo(userCollateralAccount, owner, [], amount.toNumber());
+
+    // Minting 999 pairs and selling the NFRT leg costs about 0.504 collateral per PFRT
+    try {
+      await program.methods
+        .buyWithCollateral(epochId, amount, new anchor.BN(ONE / 2))
+        .accounts({ ...userAccounts(), ...mintingAccounts, ...mintOnlyAccounts, ...poolAccounts })
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SlippageExceeded");
+    }
+
+    await program.methods
+      .buyWithCollateral(epochId, amount, new anchor.BN(0.51 * ONE))
+      .accounts({ ...userAccounts(), ...mintingAccounts, ...mintOnlyAccounts, ...poolAccounts })
+      .signers([user])
+      .rpc();
+
+    const collateral = await collateralMint.getAccountInfo(userCollateralAccount);
+    const pfrt = await pfrtMint.getAccountInfo(userPfrtAccount);
+    const nfrt = await nfrtMint.getAccountInfo(userNfrtAccount);
+    assert.equal(collateral.amount.toNumber(), 0);
+    assert.equal(nfrt.amount.toNumber(), 0);
+    assert.ok(pfrt.amount.gt(new anchor.BN(1980 * ONE)));
+    assert.ok(pfrt.amount.lt(new anchor.BN(1990 * ONE)));
+  });
+
+  it("Sells PFRT for collateral in one transaction", async () => {
+    // Let the slot advance past the buy's swap
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+
+    const pfrtBefore = (await pfrtMint.getAccountInfo(userPfrtAccount)).amount;
+    const amount = new anchor.BN(1000 * ONE);
+
+    try {
+      await program.methods
+        .sellForCollateral(epochId, amount, new anchor.BN(600 * ONE))
+        .accounts({
+          ...userAccounts(),
+          ...mintingAccounts,
+          ...poolAccounts,
+          userMintState: mintOnlyAccounts.userMintState,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SlippageExceeded");
+    }
+
+    await program.methods
+      .sellForCollateral(epochId, amount, new anchor.BN(490 * ONE))
+      .accounts({
+        ...userAccounts(),
+        ...mintingAccounts,
+        ...poolAccounts,
+        userMintState: mintOnlyAccounts.userMintState,
+      })
+      .signers([user])
+      .rpc();
+
+    const collateral = await collateralMint.getAccountInfo(userCollateralAccount);
+    const pfrt = await pfrtMint.getAccountInfo(userPfrtAccount);
+    const nfrt = await nfrtMint.getAccountInfo(userNfrtAccount);
+    assert.ok(collateral.amount.gte(new anchor.BN(490 * ONE)));
+    // Only rounding dust of either leg is left unpaired
+    assert.ok(nfrt.amount.lt(new anchor.BN(ONE / 1000)));
+    assert.ok(pfrtBefore.sub(pfrt.amount).eq(amount));
+  });
+});
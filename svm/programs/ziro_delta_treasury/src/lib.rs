@@ -0,0 +1,674 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+declare_id!("A1z9DP4oruBut9UJL4n28JTukeqGXNDZWuSJ9fj2M2EJ");
+
+#[program]
+pub mod ziro_delta_treasury {
+    use super::*;
+
+    /// Initialize the protocol treasury, controlled by an admin key or multisig
+    pub fn initialize(ctx: Context<Initialize>, governance: Pubkey) -> Result<()> {
+        let state = &mut ctx.accounts.state;
+        state.governance = governance;
+        state.zdlt_mint = ctx.accounts.zdlt_mint.key();
+        state.authority_bump = ctx.bumps.treasury_authority;
+        state.stream_count = 0;
+        state.total_zdlt_burned = 0;
+
+        msg!("ZiroDelta Treasury initialized");
+        Ok(())
+    }
+
+    /// Create the PDA-owned vault for a revenue mint (permissionless)
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.mint = ctx.accounts.mint.key();
+        vault_info.vault = ctx.accounts.vault.key();
+        vault_info.total_deposited = 0;
+        vault_info.total_withdrawn = 0;
+        vault_info.total_streamed = 0;
+        vault_info.reserved = 0;
+        vault_info.bump = ctx.bumps.vault_info;
+
+        emit!(VaultCreated {
+            mint: vault_info.mint,
+            vault: vault_info.vault,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit protocol revenue into a treasury vault. Called by other
+    /// programs via CPI with their own PDA as `authority`. Vaults may hold
+    /// Token-2022 mints, so the recorded amount is net of any transfer fee.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, TreasuryError::InvalidAmount);
+
+        let balance_before = ctx.accounts.vault.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.from.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        ctx.accounts.vault.reload()?;
+        let received = ctx
+            .accounts
+            .vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(TreasuryError::Overflow)?;
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.total_deposited = vault_info
+            .total_deposited
+            .checked_add(received)
+            .ok_or(TreasuryError::Overflow)?;
+
+        emit!(RevenueDeposited {
+            mint: vault_info.mint,
+            depositor: ctx.accounts.authority.key(),
+            amount: received,
+            total_deposited: vault_info.total_deposited,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw unreserved funds from a vault (governance only)
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(amount > 0, TreasuryError::InvalidAmount);
+
+        let available = ctx
+            .accounts
+            .vault
+            .amount
+            .saturating_sub(ctx.accounts.vault_info.reserved);
+        require!(amount <= available, TreasuryError::InsufficientFunds);
+
+        let seeds = &[b"treasury_authority".as_ref(), &[ctx.accounts.state.authority_bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.total_withdrawn = vault_info
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(TreasuryError::Overflow)?;
+
+        emit!(TreasuryWithdrawal {
+            mint: vault_info.mint,
+            destination: ctx.accounts.destination.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Create a linear payment stream out of a vault (governance only)
+    pub fn create_stream(
+        ctx: Context<CreateStream>,
+        recipient: Pubkey,
+        total_amount: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        require!(total_amount > 0, TreasuryError::InvalidAmount);
+        require!(end_time > start_time, TreasuryError::InvalidStreamSchedule);
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        let available = ctx.accounts.vault.amount.saturating_sub(vault_info.reserved);
+        require!(total_amount <= available, TreasuryError::InsufficientFunds);
+        vault_info.reserved = vault_info
+            .reserved
+            .checked_add(total_amount)
+            .ok_or(TreasuryError::Overflow)?;
+
+        let state = &mut ctx.accounts.state;
+        let stream = &mut ctx.accounts.stream;
+        stream.id = state.stream_count;
+        stream.mint = vault_info.mint;
+        stream.recipient = recipient;
+        stream.total_amount = total_amount;
+        stream.claimed_amount = 0;
+        stream.start_time = start_time;
+        stream.end_time = end_time;
+        stream.cancelled = false;
+
+        state.stream_count += 1;
+
+        emit!(StreamCreated {
+            stream_id: stream.id,
+            mint: stream.mint,
+            recipient,
+            total_amount,
+            start_time,
+            end_time,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the vested portion of a stream (recipient only)
+    pub fn claim_stream(ctx: Context<ClaimStream>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let now = Clock::get()?.unix_timestamp;
+
+        let claimable = vested_amount(stream, now)?
+            .checked_sub(stream.claimed_amount)
+            .ok_or(TreasuryError::Overflow)?;
+        require!(claimable > 0, TreasuryError::NothingToClaim);
+
+        let seeds = &[b"treasury_authority".as_ref(), &[ctx.accounts.state.authority_bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.recipient_account.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            claimable,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        stream.claimed_amount += claimable;
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.reserved -= claimable;
+        vault_info.total_streamed = vault_info
+            .total_streamed
+            .checked_add(claimable)
+            .ok_or(TreasuryError::Overflow)?;
+
+        emit!(StreamClaimed {
+            stream_id: stream.id,
+            recipient: stream.recipient,
+            amount: claimable,
+            total_claimed: stream.claimed_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a stream and release its unvested reserve (governance only).
+    /// Amounts already vested stay claimable by the recipient.
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        require!(!stream.cancelled, TreasuryError::StreamAlreadyCancelled);
+
+        let now = Clock::get()?.unix_timestamp;
+        let vested = vested_amount(stream, now)?;
+        let released = stream.total_amount - vested;
+
+        stream.total_amount = vested;
+        stream.end_time = stream.end_time.min(now);
+        stream.cancelled = true;
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.reserved -= released;
+
+        emit!(StreamCancelled {
+            stream_id: stream.id,
+            released,
+        });
+
+        Ok(())
+    }
+
+    /// Buy ZDLT from a counterparty at a governance-approved price and burn it.
+    /// Both the governance signer and the seller must sign.
+    pub fn buyback_and_burn(
+        ctx: Context<BuybackAndBurn>,
+        amount_in: u64,
+        zdlt_amount: u64,
+    ) -> Result<()> {
+        require!(amount_in > 0 && zdlt_amount > 0, TreasuryError::InvalidAmount);
+
+        let available = ctx
+            .accounts
+            .vault
+            .amount
+            .saturating_sub(ctx.accounts.vault_info.reserved);
+        require!(amount_in <= available, TreasuryError::InsufficientFunds);
+
+        // Pay the seller out of treasury revenue
+        let seeds = &[b"treasury_authority".as_ref(), &[ctx.accounts.state.authority_bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.seller_payment_account.to_account_info(),
+                    authority: ctx.accounts.treasury_authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount_in,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // Burn the purchased ZDLT straight from the seller
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.zdlt_mint.to_account_info(),
+                    from: ctx.accounts.seller_zdlt_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            zdlt_amount,
+        )?;
+
+        let vault_info = &mut ctx.accounts.vault_info;
+        vault_info.total_withdrawn = vault_info
+            .total_withdrawn
+            .checked_add(amount_in)
+            .ok_or(TreasuryError::Overflow)?;
+
+        let state = &mut ctx.accounts.state;
+        state.total_zdlt_burned = state
+            .total_zdlt_burned
+            .checked_add(zdlt_amount)
+            .ok_or(TreasuryError::Overflow)?;
+
+        emit!(BuybackBurned {
+            mint: vault_info.mint,
+            amount_in,
+            zdlt_burned: zdlt_amount,
+            total_zdlt_burned: state.total_zdlt_burned,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper functions
+fn vested_amount(stream: &Stream, now: i64) -> Result<u64> {
+    if now <= stream.start_time {
+        return Ok(0);
+    }
+    if now >= stream.end_time {
+        return Ok(stream.total_amount);
+    }
+
+    let elapsed = (now - stream.start_time) as u128;
+    let duration = (stream.end_time - stream.start_time) as u128;
+    let vested = (stream.total_amount as u128)
+        .checked_mul(elapsed)
+        .ok_or(TreasuryError::Overflow)?
+        / duration;
+    Ok(vested as u64)
+}
+
+// Account structs
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<TreasuryState>(),
+        seeds = [b"treasury_state"],
+        bump
+    )]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(seeds = [b"treasury_authority"], bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub zdlt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(seeds = [b"treasury_state"], bump)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<VaultInfo>(),
+        seeds = [b"vault_info", mint.key().as_ref()],
+        bump
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = treasury_authority,
+        seeds = [b"treasury_vault", mint.key().as_ref()],
+        bump
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"treasury_authority"], bump = state.authority_bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault_info", vault_info.mint.as_ref()],
+        bump = vault_info.bump,
+        has_one = vault
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault_info.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(seeds = [b"treasury_state"], bump, has_one = governance)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_info", vault_info.mint.as_ref()],
+        bump = vault_info.bump,
+        has_one = vault
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault_info.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [b"treasury_authority"], bump = state.authority_bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub governance: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStream<'info> {
+    #[account(mut, seeds = [b"treasury_state"], bump, has_one = governance)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_info", vault_info.mint.as_ref()],
+        bump = vault_info.bump,
+        has_one = vault
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = governance,
+        space = 8 + std::mem::size_of::<Stream>(),
+        seeds = [b"stream", state.stream_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(mut)]
+    pub governance: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStream<'info> {
+    #[account(seeds = [b"treasury_state"], bump)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.id.to_le_bytes().as_ref()],
+        bump,
+        has_one = recipient
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_info", stream.mint.as_ref()],
+        bump = vault_info.bump,
+        has_one = vault
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, token::mint = stream.mint)]
+    pub recipient_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = stream.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(seeds = [b"treasury_authority"], bump = state.authority_bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub recipient: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    #[account(seeds = [b"treasury_state"], bump, has_one = governance)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_info", stream.mint.as_ref()],
+        bump = vault_info.bump
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    #[account(mut, seeds = [b"treasury_state"], bump, has_one = governance, has_one = zdlt_mint)]
+    pub state: Account<'info, TreasuryState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_info", vault_info.mint.as_ref()],
+        bump = vault_info.bump,
+        has_one = vault
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub zdlt_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub seller_payment_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault_info.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub seller_zdlt_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(seeds = [b"treasury_authority"], bump = state.authority_bump)]
+    /// CHECK: This is a PDA used as vault authority
+    pub treasury_authority: AccountInfo<'info>,
+
+    pub seller: Signer<'info>,
+    pub governance: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// Data structures
+#[account]
+pub struct TreasuryState {
+    pub governance: Pubkey,
+    pub zdlt_mint: Pubkey,
+    pub authority_bump: u8,
+    pub stream_count: u64,
+    pub total_zdlt_burned: u64,
+}
+
+#[account]
+pub struct VaultInfo {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub total_streamed: u64,
+    // Committed to open streams and unavailable for withdrawal
+    pub reserved: u64,
+    pub bump: u8,
+}
+
+#[account]
+pub struct Stream {
+    pub id: u64,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub cancelled: bool,
+}
+
+// Events
+#[event]
+pub struct VaultCreated {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RevenueDeposited {
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub total_deposited: u64,
+}
+
+#[event]
+pub struct TreasuryWithdrawal {
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StreamCreated {
+    pub stream_id: u64,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct StreamClaimed {
+    pub stream_id: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct StreamCancelled {
+    pub stream_id: u64,
+    pub released: u64,
+}
+
+#[event]
+pub struct BuybackBurned {
+    pub mint: Pubkey,
+    pub amount_in: u64,
+    pub zdlt_burned: u64,
+    pub total_zdlt_burned: u64,
+}
+
+// Errors
+#[error_code]
+pub enum TreasuryError {
+    #[msg("Invalid amount provided")]
+    InvalidAmount,
+    #[msg("Insufficient unreserved funds in vault")]
+    InsufficientFunds,
+    #[msg("Stream end time must be after start time")]
+    InvalidStreamSchedule,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Stream already cancelled")]
+    StreamAlreadyCancelled,
+    #[msg("Calculation overflow")]
+    Overflow,
+}
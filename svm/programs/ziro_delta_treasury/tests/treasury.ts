@@ -0,0 +1,184 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import { ZiroDeltaTreasury } from "../target/types/ziro_delta_treasury";
+import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { assert } from "chai";
+
+describe("ziro-delta-treasury", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.ZiroDeltaTreasury as Program<ZiroDeltaTreasury>;
+
+  const governance = anchor.web3.Keypair.generate();
+  const depositor = anchor.web3.Keypair.generate();
+  const recipient = anchor.web3.Keypair.generate();
+
+  let revenueMint: Token;
+  let zdltMint: Token;
+
+  let depositorAccount: anchor.web3.PublicKey;
+  let recipientAccount: anchor.web3.PublicKey;
+
+  let state: anchor.web3.PublicKey;
+  let treasuryAuthority: anchor.web3.PublicKey;
+  let vaultInfo: anchor.web3.PublicKey;
+  let vault: anchor.web3.PublicKey;
+
+  before(async () => {
+    for (const kp of [governance, depositor, recipient]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(kp.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL)
+      );
+    }
+
+    revenueMint = await Token.createMint(
+      provider.connection,
+      governance,
+      governance.publicKey,
+      null,
+      6,
+      TOKEN_PROGRAM_ID
+    );
+    zdltMint = await Token.createMint(
+      provider.connection,
+      governance,
+      governance.publicKey,
+      null,
+      9,
+      TOKEN_PROGRAM_ID
+    );
+
+    depositorAccount = await revenueMint.createAccount(depositor.publicKey);
+    recipientAccount = await revenueMint.createAccount(recipient.publicKey);
+    await revenueMint.mintTo(depositorAccount, governance, [], 1_000_000);
+
+    [state] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_state")],
+      program.programId
+    );
+    [treasuryAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+    [vaultInfo] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("vault_info"), revenueMint.publicKey.toBuffer()],
+      program.programId
+    );
+    [vault] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("treasury_vault"), revenueMint.publicKey.toBuffer()],
+      program.programId
+    );
+  });
+
+  it("Initializes and creates a vault", async () => {
+    await program.methods
+      .initialize(governance.publicKey)
+      .accounts({
+        state,
+        treasuryAuthority,
+        zdltMint: zdltMint.publicKey,
+        payer: governance.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([governance])
+      .rpc();
+
+    await program.methods
+      .createVault()
+      .accounts({
+        state,
+        vaultInfo,
+        vault,
+        treasuryAuthority,
+        mint: revenueMint.publicKey,
+        payer: depositor.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([depositor])
+      .rpc();
+
+    const info = await program.account.vaultInfo.fetch(vaultInfo);
+    assert.ok(info.vault.equals(vault));
+  });
+
+  it("Accepts deposits", async () => {
+    await program.methods
+      .deposit(new anchor.BN(600_000))
+      .accounts({
+        vaultInfo,
+        vault,
+        from: depositorAccount,
+        mint: revenueMint.publicKey,
+        authority: depositor.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([depositor])
+      .rpc();
+
+    const info = await program.account.vaultInfo.fetch(vaultInfo);
+    assert.ok(info.totalDeposited.eq(new anchor.BN(600_000)));
+  });
+
+  it("Reserves streamed funds against withdrawals", async () => {
+    const [stream] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stream"), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const now = Math.floor(Date.now() / 1000);
+
+    await program.methods
+      .createStream(recipient.publicKey, new anchor.BN(500_000), new anchor.BN(now - 10), new anchor.BN(now + 3600))
+      .accounts({
+        state,
+        vaultInfo,
+        vault,
+        stream,
+        governance: governance.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([governance])
+      .rpc();
+
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(200_000))
+        .accounts({
+          state,
+          vaultInfo,
+          vault,
+          destination: depositorAccount,
+          mint: revenueMint.publicKey,
+          treasuryAuthority,
+          governance: governance.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([governance])
+        .rpc();
+      assert.fail("Should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InsufficientFunds");
+    }
+
+    await program.methods
+      .claimStream()
+      .accounts({
+        state,
+        stream,
+        vaultInfo,
+        vault,
+        recipientAccount,
+        mint: revenueMint.publicKey,
+        treasuryAuthority,
+        recipient: recipient.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([recipient])
+      .rpc();
+
+    const claimed = await revenueMint.getAccountInfo(recipientAccount);
+    assert.ok(claimed.amount.gtn(0));
+  });
+});
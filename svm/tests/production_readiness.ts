@@ -15,6 +15,7 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
   let ammProgram: Program;
   let oracleProgram: Program;
   let emergencyProgram: Program;
+  let treasuryProgram: Program;
 
   // Test accounts
   let authority: Keypair;
@@ -31,7 +32,14 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
   let nfrtToken: Token;
 
   // Program states
+  let ammFactory: PublicKey;
   let ammState: PublicKey;
+  let ammAuthority: PublicKey;
+  let pfrtVault: PublicKey;
+  let nfrtVault: PublicKey;
+  let lpMint: PublicKey;
+  let ammObservations: PublicKey;
+  let authorityLpAccount: PublicKey;
   let oracleState: PublicKey;
   let emergencyState: PublicKey;
 
@@ -227,41 +235,77 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
     it("🏗️ Should initialize AMM with production security parameters", async () => {
       console.log("🏗️ Initializing production-ready AMM...");
 
-      const [ammPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("amm_state")],
+      [ammFactory] = await PublicKey.findProgramAddress(
+        [Buffer.from("factory")],
         ammProgram.programId
       );
-      ammState = ammPDA;
 
-      const [authorityPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("authority"), pfrtMint.toBuffer()],
+      [ammState] = await PublicKey.findProgramAddress(
+        [Buffer.from("pool"), pfrtMint.toBuffer(), nfrtMint.toBuffer()],
         ammProgram.programId
       );
 
-      const [pfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("pfrt_vault")],
+      [ammAuthority] = await PublicKey.findProgramAddress(
+        [Buffer.from("authority"), ammState.toBuffer()],
         ammProgram.programId
       );
 
-      const [nfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("nfrt_vault")],
+      [pfrtVault] = await PublicKey.findProgramAddress(
+        [Buffer.from("pfrt_vault"), ammState.toBuffer()],
+        ammProgram.programId
+      );
+
+      [nfrtVault] = await PublicKey.findProgramAddress(
+        [Buffer.from("nfrt_vault"), ammState.toBuffer()],
+        ammProgram.programId
+      );
+
+      [lpMint] = await PublicKey.findProgramAddress(
+        [Buffer.from("lp_mint"), ammState.toBuffer()],
+        ammProgram.programId
+      );
+
+      [ammObservations] = await PublicKey.findProgramAddress(
+        [Buffer.from("observations"), ammState.toBuffer()],
+        ammProgram.programId
+      );
+
+      const [poolListing] = await PublicKey.findProgramAddress(
+        [Buffer.from("pool_listing"), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
         ammProgram.programId
       );
 
       await ammProgram.methods
-        .initialize(
+        .initializeFactory(authority.publicKey)
+        .accounts({
+          factory: ammFactory,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      await ammProgram.methods
+        .createPool(
+          new anchor.BN(1), // Epoch id
           30, // 0.3% trading fee
           new anchor.BN(10000 * 1e6), // Max 10k tokens per trade
           new anchor.BN(100000 * 1e6), // 100k daily volume limit
-          500 // 5% max slippage
+          500, // 5% max slippage
+          { constantProduct: {} }
         )
         .accounts({
+          factory: ammFactory,
+          poolListing,
           state: ammState,
-          authority: authorityPDA,
+          authority: ammAuthority,
           pfrtMint: pfrtMint,
           nfrtMint: nfrtMint,
-          pfrtVault: pfrtVaultPDA,
-          nfrtVault: nfrtVaultPDA,
+          pfrtVault,
+          nfrtVault,
+          lpMint,
+          observations: ammObservations,
+          governance: authority.publicKey,
           user: authority.publicKey,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
@@ -275,6 +319,10 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
       assert.equal(ammStateAccount.maxTradeSize.toNumber(), 10000 * 1e6);
       assert.equal(ammStateAccount.tradingPaused, false);
 
+      const listing = await ammProgram.account.poolListing.fetch(poolListing);
+      assert.ok(listing.pool.equals(ammState));
+      assert.equal(listing.epochId.toNumber(), 1);
+
       console.log("✅ Production AMM initialized with security features:");
       console.log(`   💰 Trading fee: ${ammStateAccount.tradingFeeBps/100}%`);
       console.log(`   📏 Max trade size: ${ammStateAccount.maxTradeSize.toNumber()/1e6} tokens`);
@@ -292,15 +340,8 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
       await pfrtToken.mintTo(authorityPfrtAccount, authority, [], 1000000 * 1e6);
       await nfrtToken.mintTo(authorityNfrtAccount, authority, [], 1000000 * 1e6);
 
-      const [pfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("pfrt_vault")],
-        ammProgram.programId
-      );
-
-      const [nfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("nfrt_vault")],
-        ammProgram.programId
-      );
+      const lpToken = new Token(connection, lpMint, TOKEN_PROGRAM_ID, authority);
+      authorityLpAccount = await lpToken.createAccount(authority.publicKey);
 
       const liquidityAmount = 50000 * 1e6; // 50k tokens each
 
@@ -312,10 +353,16 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
         )
         .accounts({
           state: ammState,
-          pfrtVault: pfrtVaultPDA,
-          nfrtVault: nfrtVaultPDA,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          lpMint,
+          observations: ammObservations,
+          pfrtVault,
+          nfrtVault,
           userPfrtAccount: authorityPfrtAccount,
           userNfrtAccount: authorityNfrtAccount,
+          userLpAccount: authorityLpAccount,
           user: authority.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
@@ -325,14 +372,101 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
       const ammStateAccount = await ammProgram.account.ammState.fetch(ammState);
       assert.equal(ammStateAccount.pfrtBalance.toNumber(), liquidityAmount);
       assert.equal(ammStateAccount.nfrtBalance.toNumber(), liquidityAmount);
+      assert.equal(ammStateAccount.totalLiquidity.toNumber(), liquidityAmount);
+
+      // First deposit locks MINIMUM_LIQUIDITY in the pool
+      const lpBalance = await lpToken.getAccountInfo(authorityLpAccount);
+      assert.equal(lpBalance.amount.toNumber(), liquidityAmount - 1000);
+
+      // Remove a tenth of the position and put it straight back
+      const removeAmount = liquidityAmount / 10;
+      try {
+        await ammProgram.methods
+          .removeLiquidity(
+            new anchor.BN(removeAmount),
+            new anchor.BN(removeAmount + 1),
+            new anchor.BN(0)
+          )
+          .accounts({
+            state: ammState,
+            authority: ammAuthority,
+            pfrtMint: pfrtMint,
+            nfrtMint: nfrtMint,
+            lpMint,
+            observations: ammObservations,
+            pfrtVault,
+            nfrtVault,
+            userPfrtAccount: authorityPfrtAccount,
+            userNfrtAccount: authorityNfrtAccount,
+            userLpAccount: authorityLpAccount,
+            user: authority.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+
+      await ammProgram.methods
+        .removeLiquidity(
+          new anchor.BN(removeAmount),
+          new anchor.BN(removeAmount),
+          new anchor.BN(removeAmount)
+        )
+        .accounts({
+          state: ammState,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          lpMint,
+          observations: ammObservations,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: authorityPfrtAccount,
+          userNfrtAccount: authorityNfrtAccount,
+          userLpAccount: authorityLpAccount,
+          user: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      await ammProgram.methods
+        .addLiquidity(
+          new anchor.BN(removeAmount),
+          new anchor.BN(removeAmount),
+          new anchor.BN(removeAmount)
+        )
+        .accounts({
+          state: ammState,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          lpMint,
+          observations: ammObservations,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: authorityPfrtAccount,
+          userNfrtAccount: authorityNfrtAccount,
+          userLpAccount: authorityLpAccount,
+          user: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      const restored = await lpToken.getAccountInfo(authorityLpAccount);
+      assert.equal(restored.amount.toNumber(), liquidityAmount - 1000);
 
       console.log("✅ Initial liquidity added successfully");
       console.log(`   🪙 PFRT: ${ammStateAccount.pfrtBalance.toNumber()/1e6} tokens`);
       console.log(`   🪙 NFRT: ${ammStateAccount.nfrtBalance.toNumber()/1e6} tokens`);
     });
 
-    it("🔒 Should prevent flash loan attacks", async () => {
-      console.log("🔒 Testing flash loan protection...");
+    it("🔒 Should cap pool-wide price impact within a slot window", async () => {
+      console.log("🔒 Testing slot-window price impact cap...");
 
       // Create user token accounts
       const user1PfrtAccount = await pfrtToken.createAccount(user1.publicKey);
@@ -341,77 +475,157 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
       // Mint tokens for user
       await pfrtToken.mintTo(user1PfrtAccount, authority, [], 10000 * 1e6);
 
-      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("user_trade"), user1.publicKey.toBuffer()],
-        ammProgram.programId
+      // A second wallet trading back against user1 in the same window
+      const sybil = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(sybil.publicKey, anchor.web3.LAMPORTS_PER_SOL)
       );
+      const sybilPfrtAccount = await pfrtToken.createAccount(sybil.publicKey);
+      const sybilNfrtAccount = await nfrtToken.createAccount(sybil.publicKey);
+      await nfrtToken.mintTo(sybilNfrtAccount, authority, [], 500 * 1e6);
 
-      const [authorityPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("authority"), pfrtMint.toBuffer()],
+      const [marketMakerPDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("market_maker"), ammState.toBuffer(), sybil.publicKey.toBuffer()],
         ammProgram.programId
       );
 
-      const [pfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("pfrt_vault")],
-        ammProgram.programId
-      );
+      // 5% of spot movement shared by every trade within 50 slots
+      await ammProgram.methods
+        .configureSlotLimits(new anchor.BN(50), 500, new anchor.BN(0))
+        .accounts({
+          factory: ammFactory,
+          state: ammState,
+          governance: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      const swapAccounts = (user: Keypair, pfrtAccount: PublicKey, nfrtAccount: PublicKey) => ({
+        state: ammState,
+        userTradeState: null,
+        observations: ammObservations,
+        authority: ammAuthority,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        pfrtVault,
+        nfrtVault,
+        userPfrtAccount: pfrtAccount,
+        userNfrtAccount: nfrtAccount,
+        user: user.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        feeOracle: null,
+        marketMaker: null,
+      });
+
+      // ~1k into a ~50k pool moves the spot price ~4%, without a trade PDA
+      await ammProgram.methods
+        .swap(new anchor.BN(1000 * 1e6), new anchor.BN(0), true)
+        .accounts(swapAccounts(user1, user1PfrtAccount, user1NfrtAccount))
+        .signers([user1])
+        .rpc();
 
-      const [nfrtVaultPDA] = await PublicKey.findProgramAddress(
-        [Buffer.from("nfrt_vault")],
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
         ammProgram.programId
       );
+      assert.isNull(await connection.getAccountInfo(userTradeStatePDA));
+
+      console.log("✅ First swap completed without a user trade account");
+
+      // The back-run from another wallet pushes the window past 5%
+      try {
+        await ammProgram.methods
+          .swap(new anchor.BN(500 * 1e6), new anchor.BN(0), false)
+          .accounts(swapAccounts(sybil, sybilPfrtAccount, sybilNfrtAccount))
+          .signers([sybil])
+          .rpc();
+
+        assert.fail("Second wallet's swap should have hit the slot-window cap");
+      } catch (error) {
+        assert.include(error.toString(), "SlotPriceImpactExceeded");
+        console.log("✅ Slot-window cap blocked the second wallet");
+      }
 
-      // First swap should work
+      // Allowlisted market makers trade outside the cap
       await ammProgram.methods
-        .swap(
-          new anchor.BN(1000 * 1e6), // 1k tokens
-          new anchor.BN(0), // Min amount out
-          true // PFRT to NFRT
-        )
+        .addMarketMaker(sybil.publicKey, new anchor.BN(0))
         .accounts({
+          factory: ammFactory,
           state: ammState,
-          userTradeState: userTradeStatePDA,
-          authority: authorityPDA,
-          pfrtVault: pfrtVaultPDA,
-          nfrtVault: nfrtVaultPDA,
-          userPfrtAccount: user1PfrtAccount,
-          userNfrtAccount: user1NfrtAccount,
-          user: user1.publicKey,
+          marketMakerEntry: marketMakerPDA,
+          governance: authority.publicKey,
           systemProgram: SystemProgram.programId,
-          tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([user1])
+        .signers([authority])
+        .rpc();
+
+      await ammProgram.methods
+        .swap(new anchor.BN(500 * 1e6), new anchor.BN(0), false)
+        .accounts({
+          ...swapAccounts(sybil, sybilPfrtAccount, sybilNfrtAccount),
+          marketMaker: marketMakerPDA,
+        })
+        .signers([sybil])
+        .rpc();
+
+      const sybilPfrt = await pfrtToken.getAccountInfo(sybilPfrtAccount);
+      assert.ok(sybilPfrt.amount.gtn(0));
+      console.log("✅ Market maker swap accepted inside the window");
+
+      await ammProgram.methods
+        .removeMarketMaker()
+        .accounts({
+          factory: ammFactory,
+          state: ammState,
+          marketMakerEntry: marketMakerPDA,
+          governance: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+      assert.isNull(await connection.getAccountInfo(marketMakerPDA));
+
+      // Restore the default one-slot window for the remaining tests
+      await ammProgram.methods
+        .configureSlotLimits(new anchor.BN(1), 2000, new anchor.BN(0))
+        .accounts({
+          factory: ammFactory,
+          state: ammState,
+          governance: authority.publicKey,
+        })
+        .signers([authority])
         .rpc();
+    });
+
+    it("⏱️ Should serve TWAPs from the observation ring buffer", async () => {
+      console.log("⏱️ Consulting price observations...");
 
-      console.log("✅ First swap completed successfully");
+      const quote = await ammProgram.methods
+        .consult(1)
+        .accounts({ state: ammState, observations: ammObservations })
+        .view();
+      assert.ok(quote.pfrtTwap.gtn(0));
+      assert.ok(quote.nfrtTwap.gtn(0));
 
-      // Second swap in same slot should fail
       try {
         await ammProgram.methods
-          .swap(
-            new anchor.BN(1000 * 1e6),
-            new anchor.BN(0),
-            true
-          )
-          .accounts({
-            state: ammState,
-            userTradeState: userTradeStatePDA,
-            authority: authorityPDA,
-            pfrtVault: pfrtVaultPDA,
-            nfrtVault: nfrtVaultPDA,
-            userPfrtAccount: user1PfrtAccount,
-            userNfrtAccount: user1NfrtAccount,
-            user: user1.publicKey,
-            systemProgram: SystemProgram.programId,
-            tokenProgram: TOKEN_PROGRAM_ID,
-          })
-          .signers([user1])
-          .rpc();
-
-        assert.fail("Second swap should have failed due to flash loan protection");
+          .consult(7 * 24 * 3600)
+          .accounts({ state: ammState, observations: ammObservations })
+          .view();
+        assert.fail("Lookback beyond history should have failed");
       } catch (error) {
-        console.log("✅ Flash loan protection activated - second swap blocked");
+        assert.include(error.toString(), "ObservationTooOld");
       }
+
+      await ammProgram.methods
+        .increaseObservationCardinality(64)
+        .accounts({ state: ammState, observations: ammObservations })
+        .rpc();
+
+      const buffer = await ammProgram.account.observationBuffer.fetch(ammObservations);
+      assert.equal(buffer.cardinalityNext, 64);
+
+      console.log(`✅ TWAP over 1s: ${quote.pfrtTwap.toNumber()/1e6} NFRT per PFRT`);
     });
 
     it("📊 Should enforce trading limits", async () => {
@@ -425,7 +639,7 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
 
       try {
         const [userTradeStatePDA] = await PublicKey.findProgramAddress(
-          [Buffer.from("user_trade"), user2.publicKey.toBuffer()],
+          [Buffer.from("user_trade"), ammState.toBuffer(), user2.publicKey.toBuffer()],
           ammProgram.programId
         );
 
@@ -448,6 +662,1103 @@ describe("🚀 ZiroDelta Protocol - Solana Production Readiness", () => {
         console.log("✅ Trading limits enforced - oversized trade blocked");
       }
     });
+
+    it("📉 Should reject swaps with excessive price impact", async () => {
+      console.log("📉 Testing price impact protection...");
+
+      const user2PfrtAccount = (
+        await connection.getTokenAccountsByOwner(user2.publicKey, { mint: pfrtMint })
+      ).value[0].pubkey;
+      const user2NfrtAccount = await nfrtToken.createAccount(user2.publicKey);
+
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user2.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+
+      // ~9k into a ~50k pool moves the price well beyond the 5% bound
+      try {
+        await ammProgram.methods
+          .swap(
+            new anchor.BN(9000 * 1e6),
+            new anchor.BN(0),
+            true
+          )
+          .accounts({
+            state: ammState,
+            userTradeState: userTradeStatePDA,
+            observations: ammObservations,
+            authority: ammAuthority,
+            pfrtMint: pfrtMint,
+            nfrtMint: nfrtMint,
+            pfrtVault,
+            nfrtVault,
+            userPfrtAccount: user2PfrtAccount,
+            userNfrtAccount: user2NfrtAccount,
+            user: user2.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user2])
+          .rpc();
+
+        assert.fail("High impact swap should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "PriceImpactTooHigh");
+        console.log("✅ Price impact protection enforced - swap rejected");
+      }
+    });
+
+    it("🎯 Should price complementary tokens on the logit curve", async () => {
+      console.log("🎯 Creating an epoch pool on the logit curve...");
+
+      const epochPfrt = await Token.createMint(connection, authority, authority.publicKey, null, 6, TOKEN_PROGRAM_ID);
+      const epochNfrt = await Token.createMint(connection, authority, authority.publicKey, null, 6, TOKEN_PROGRAM_ID);
+
+      const pda = async (seeds: Buffer[]) =>
+        (await PublicKey.findProgramAddress(seeds, ammProgram.programId))[0];
+      const pool = await pda([Buffer.from("pool"), epochPfrt.publicKey.toBuffer(), epochNfrt.publicKey.toBuffer()]);
+      const poolAccounts = {
+        state: pool,
+        authority: await pda([Buffer.from("authority"), pool.toBuffer()]),
+        pfrtMint: epochPfrt.publicKey,
+        nfrtMint: epochNfrt.publicKey,
+        pfrtVault: await pda([Buffer.from("pfrt_vault"), pool.toBuffer()]),
+        nfrtVault: await pda([Buffer.from("nfrt_vault"), pool.toBuffer()]),
+        lpMint: await pda([Buffer.from("lp_mint"), pool.toBuffer()]),
+        observations: await pda([Buffer.from("observations"), pool.toBuffer()]),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      await ammProgram.methods
+        .createPool(
+          new anchor.BN(2),
+          30,
+          new anchor.BN(10000 * 1e6),
+          new anchor.BN(100000 * 1e6),
+          500,
+          { logit: { depthBps: 20000 } } // b = 2x deposited reserves
+        )
+        .accounts({
+          ...poolAccounts,
+          factory: ammFactory,
+          poolListing: await pda([Buffer.from("pool_listing"), new anchor.BN(1).toArrayLike(Buffer, "le", 8)]),
+          governance: authority.publicKey,
+          user: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([authority])
+        .rpc();
+
+      // Nothing to price against until the pool is seeded
+      try {
+        await ammProgram.methods
+          .quoteSwap(new anchor.BN(1000 * 1e6), true)
+          .accounts({
+            state: pool,
+            pfrtMint: epochPfrt.publicKey,
+            nfrtMint: epochNfrt.publicKey,
+            feeOracle: null,
+            twamm: null,
+          })
+          .view();
+        assert.fail("Quote on an empty pool should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientLiquidity");
+      }
+
+      const lpPfrt = await epochPfrt.createAccount(authority.publicKey);
+      const lpNfrt = await epochNfrt.createAccount(authority.publicKey);
+      const lpShares = await new Token(connection, poolAccounts.lpMint, TOKEN_PROGRAM_ID, authority)
+        .createAccount(authority.publicKey);
+      await epochPfrt.mintTo(lpPfrt, authority, [], 50000 * 1e6);
+      await epochNfrt.mintTo(lpNfrt, authority, [], 50000 * 1e6);
+
+      await ammProgram.methods
+        .addLiquidity(new anchor.BN(50000 * 1e6), new anchor.BN(50000 * 1e6), new anchor.BN(0))
+        .accounts({
+          ...poolAccounts,
+          userPfrtAccount: lpPfrt,
+          userNfrtAccount: lpNfrt,
+          userLpAccount: lpShares,
+          user: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      const traderPfrt = await epochPfrt.createAccount(user1.publicKey);
+      const traderNfrt = await epochNfrt.createAccount(user1.publicKey);
+      await epochPfrt.mintTo(traderPfrt, authority, [], 1000 * 1e6);
+
+      await ammProgram.methods
+        .swap(new anchor.BN(1000 * 1e6), new anchor.BN(0), true)
+        .accounts({
+          ...poolAccounts,
+          userTradeState: await pda([Buffer.from("user_trade"), pool.toBuffer(), user1.publicKey.toBuffer()]),
+          userPfrtAccount: traderPfrt,
+          userNfrtAccount: traderNfrt,
+          user: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      // Constant product would pay ~978 NFRT for this trade; the logit curve is deeper at fair value
+      const received = await epochNfrt.getAccountInfo(traderNfrt);
+      assert.ok(received.amount.toNumber() > 990 * 1e6);
+
+      const poolState = await ammProgram.account.ammState.fetch(pool);
+      assert.ok(poolState.curveLiquidity.eq(new anchor.BN(200000 * 1e6)));
+
+      console.log(`✅ Logit curve swap: 1000 PFRT -> ${received.amount.toNumber()/1e6} NFRT`);
+    });
+
+    it("🎯 Should swap for an exact output amount", async () => {
+      console.log("🎯 Buying an exact amount of PFRT...");
+
+      const accountFor = async (mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(user1.publicKey, { mint })).value[0].pubkey;
+      const user1PfrtAccount = await accountFor(pfrtMint);
+      const user1NfrtAccount = await accountFor(nfrtMint);
+
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+      const swapAccounts = {
+        state: ammState,
+        userTradeState: userTradeStatePDA,
+        observations: ammObservations,
+        authority: ammAuthority,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        pfrtVault,
+        nfrtVault,
+        userPfrtAccount: user1PfrtAccount,
+        userNfrtAccount: user1NfrtAccount,
+        user: user1.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+      const amountOut = new anchor.BN(500 * 1e6);
+
+      // PFRT trades above 1 NFRT after the earlier swap, so 500 NFRT can't buy 500 PFRT
+      try {
+        await ammProgram.methods
+          .swapExactOut(amountOut, new anchor.BN(500 * 1e6), false)
+          .accounts(swapAccounts)
+          .signers([user1])
+          .rpc();
+        assert.fail("Swap above max_amount_in should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "SlippageExceeded");
+      }
+
+      const pfrtBefore = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      const nfrtBefore = await nfrtToken.getAccountInfo(user1NfrtAccount);
+
+      await ammProgram.methods
+        .swapExactOut(amountOut, new anchor.BN(600 * 1e6), false)
+        .accounts(swapAccounts)
+        .signers([user1])
+        .rpc();
+
+      const pfrtAfter = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      const nfrtAfter = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      assert.ok(pfrtAfter.amount.sub(pfrtBefore.amount).eq(amountOut));
+      const spent = nfrtBefore.amount.sub(nfrtAfter.amount);
+      assert.ok(spent.gt(amountOut) && spent.lte(new anchor.BN(600 * 1e6)));
+
+      console.log(`✅ Bought exactly 500 PFRT for ${spent.toNumber()/1e6} NFRT`);
+    });
+
+    it("💸 Should split trading fees with the protocol treasury", async () => {
+      console.log("💸 Turning on the protocol fee switch...");
+
+      const adminAccounts = {
+        factory: ammFactory,
+        state: ammState,
+        governance: authority.publicKey,
+      };
+
+      try {
+        await ammProgram.methods
+          .updateParameters(null, null, null, null, 6000, null)
+          .accounts(adminAccounts)
+          .signers([authority])
+          .rpc();
+        assert.fail("Protocol share above the cap should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "ProtocolFeeTooHigh");
+      }
+
+      // Half of the 0.3% trading fee goes to the protocol
+      await ammProgram.methods
+        .updateParameters(null, null, null, null, 5000, null)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      // Treasury vaults for both legs of the pool
+      const [treasuryState] = await PublicKey.findProgramAddress(
+        [Buffer.from("treasury_state")],
+        treasuryProgram.programId
+      );
+      const [treasuryAuthority] = await PublicKey.findProgramAddress(
+        [Buffer.from("treasury_authority")],
+        treasuryProgram.programId
+      );
+      const treasuryVaultFor = async (mint: PublicKey) => {
+        const [vaultInfo] = await PublicKey.findProgramAddress(
+          [Buffer.from("vault_info"), mint.toBuffer()],
+          treasuryProgram.programId
+        );
+        const [vault] = await PublicKey.findProgramAddress(
+          [Buffer.from("treasury_vault"), mint.toBuffer()],
+          treasuryProgram.programId
+        );
+        await treasuryProgram.methods
+          .createVault()
+          .accounts({
+            state: treasuryState,
+            vaultInfo,
+            vault,
+            treasuryAuthority,
+            mint,
+            payer: authority.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          })
+          .signers([authority])
+          .rpc();
+        return { vaultInfo, vault };
+      };
+
+      await treasuryProgram.methods
+        .initialize(authority.publicKey)
+        .accounts({
+          state: treasuryState,
+          treasuryAuthority,
+          zdltMint: pfrtMint,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+      const pfrtTreasury = await treasuryVaultFor(pfrtMint);
+      const nfrtTreasury = await treasuryVaultFor(nfrtMint);
+
+      const accountFor = async (mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(user1.publicKey, { mint })).value[0].pubkey;
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+      const before = await ammProgram.account.ammState.fetch(ammState);
+
+      await ammProgram.methods
+        .swap(new anchor.BN(100 * 1e6), new anchor.BN(1), true)
+        .accounts({
+          state: ammState,
+          userTradeState: userTradeStatePDA,
+          observations: ammObservations,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: await accountFor(pfrtMint),
+          userNfrtAccount: await accountFor(nfrtMint),
+          user: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      // 0.3 PFRT fee: 0.15 stays with LPs, 0.15 is held for the protocol
+      const after = await ammProgram.account.ammState.fetch(ammState);
+      assert.equal(after.protocolFeesPfrt.toNumber(), 150000);
+      assert.equal(
+        after.cumulativeProtocolFeesPfrt.sub(before.cumulativeProtocolFeesPfrt).toNumber(),
+        150000
+      );
+      assert.equal(after.cumulativeLpFeesPfrt.sub(before.cumulativeLpFeesPfrt).toNumber(), 150000);
+      assert.equal(after.pfrtBalance.sub(before.pfrtBalance).toNumber(), 100 * 1e6 - 150000);
+
+      await ammProgram.methods
+        .collectProtocolFees()
+        .accounts({
+          state: ammState,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          pfrtVault,
+          nfrtVault,
+          pfrtTreasuryVaultInfo: pfrtTreasury.vaultInfo,
+          pfrtTreasuryVault: pfrtTreasury.vault,
+          nfrtTreasuryVaultInfo: nfrtTreasury.vaultInfo,
+          nfrtTreasuryVault: nfrtTreasury.vault,
+          treasuryProgram: treasuryProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const collected = await ammProgram.account.ammState.fetch(ammState);
+      const treasuryBalance = await pfrtToken.getAccountInfo(pfrtTreasury.vault);
+      assert.equal(collected.protocolFeesPfrt.toNumber(), 0);
+      assert.equal(treasuryBalance.amount.toNumber(), 150000);
+
+      console.log("✅ Protocol fees collected to the treasury");
+    });
+
+    it("🌡️ Should price swaps with the dynamic fee bounds", async () => {
+      console.log("🌡️ Configuring dynamic fees...");
+
+      const adminAccounts = {
+        factory: ammFactory,
+        state: ammState,
+        governance: authority.publicKey,
+      };
+
+      try {
+        await ammProgram.methods
+          .configureDynamicFee(true, 200, 100, 0, 0, PublicKey.default)
+          .accounts(adminAccounts)
+          .signers([authority])
+          .rpc();
+        assert.fail("Min fee above max fee should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFeeBounds");
+      }
+
+      // A base fee is now adjustable too
+      await ammProgram.methods
+        .updateParameters(null, null, null, null, null, 25)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      // The floor lifts the 0.25% base fee to 0.8% whatever the volatility
+      await ammProgram.methods
+        .configureDynamicFee(true, 80, 300, 5000, 0, PublicKey.default)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      const accountFor = async (mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(user1.publicKey, { mint })).value[0].pubkey;
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+      const before = await ammProgram.account.ammState.fetch(ammState);
+      assert.equal(before.tradingFeeBps, 25);
+      assert.equal(before.dynamicFeeEnabled, true);
+
+      await ammProgram.methods
+        .swap(new anchor.BN(100 * 1e6), new anchor.BN(1), true)
+        .accounts({
+          state: ammState,
+          userTradeState: userTradeStatePDA,
+          observations: ammObservations,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: await accountFor(pfrtMint),
+          userNfrtAccount: await accountFor(nfrtMint),
+          user: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          feeOracle: null,
+        })
+        .signers([user1])
+        .rpc();
+
+      const after = await ammProgram.account.ammState.fetch(ammState);
+      const totalFee = after.cumulativeLpFeesPfrt
+        .sub(before.cumulativeLpFeesPfrt)
+        .add(after.cumulativeProtocolFeesPfrt.sub(before.cumulativeProtocolFeesPfrt));
+      assert.ok(totalFee.gte(new anchor.BN(800000)));
+      assert.ok(totalFee.lte(new anchor.BN(3000000)));
+
+      // Back to the static 0.3% fee for the remaining tests
+      await ammProgram.methods
+        .updateParameters(null, null, null, null, null, 30)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+      await ammProgram.methods
+        .configureDynamicFee(false, 30, 30, 0, 0, PublicKey.default)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      console.log(`✅ Dynamic fee charged ${totalFee.toNumber()/1e6} PFRT on a 100 PFRT swap`);
+    });
+
+    it("🧮 Should quote swaps and liquidity changes as return data", async () => {
+      console.log("🧮 Quoting without touching pool state...");
+
+      const quoteAccounts = {
+        state: ammState,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        feeOracle: null,
+        twamm: null,
+      };
+      const before = await ammProgram.account.ammState.fetch(ammState);
+
+      const addQuote = await ammProgram.methods
+        .quoteAddLiquidity(new anchor.BN(1000 * 1e6), new anchor.BN(1000 * 1e6))
+        .accounts(quoteAccounts)
+        .view();
+      assert.ok(addQuote.liquidity.gtn(0));
+      assert.ok(addQuote.pfrtBalanceAfter.eq(before.pfrtBalance.add(new anchor.BN(1000 * 1e6))));
+      assert.ok(addQuote.totalLiquidityAfter.eq(before.totalLiquidity.add(addQuote.liquidity)));
+
+      const removeQuote = await ammProgram.methods
+        .quoteRemoveLiquidity(new anchor.BN(1000 * 1e6))
+        .accounts(quoteAccounts)
+        .view();
+      assert.ok(
+        removeQuote.pfrtAmount.eq(
+          before.pfrtBalance.mul(new anchor.BN(1000 * 1e6)).div(before.totalLiquidity)
+        )
+      );
+
+      const swapQuote = await ammProgram.methods
+        .quoteSwap(new anchor.BN(100 * 1e6), true)
+        .accounts(quoteAccounts)
+        .view();
+      assert.equal(swapQuote.feeBps, 30);
+      assert.equal(swapQuote.fee.toNumber(), 300000);
+      assert.ok(swapQuote.priceImpactBps.ltn(500));
+
+      const unchanged = await ammProgram.account.ammState.fetch(ammState);
+      assert.ok(unchanged.pfrtBalance.eq(before.pfrtBalance));
+      assert.ok(unchanged.totalLiquidity.eq(before.totalLiquidity));
+
+      // The swap lands exactly on the quote
+      const accountFor = async (mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(user1.publicKey, { mint })).value[0].pubkey;
+      const user1NfrtAccount = await accountFor(nfrtMint);
+      const [userTradeStatePDA] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+      const nfrtBefore = await nfrtToken.getAccountInfo(user1NfrtAccount);
+
+      await ammProgram.methods
+        .swap(new anchor.BN(100 * 1e6), swapQuote.amountOut, true)
+        .accounts({
+          state: ammState,
+          userTradeState: userTradeStatePDA,
+          observations: ammObservations,
+          authority: ammAuthority,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: await accountFor(pfrtMint),
+          userNfrtAccount: user1NfrtAccount,
+          user: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          feeOracle: null,
+        })
+        .signers([user1])
+        .rpc();
+
+      const nfrtAfter = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      const after = await ammProgram.account.ammState.fetch(ammState);
+      assert.ok(nfrtAfter.amount.sub(nfrtBefore.amount).eq(swapQuote.amountOut));
+      assert.ok(after.pfrtBalance.eq(swapQuote.pfrtBalanceAfter));
+      assert.ok(after.nfrtBalance.eq(swapQuote.nfrtBalanceAfter));
+
+      console.log(`✅ Quoted ${swapQuote.amountOut.toNumber()/1e6} NFRT for 100 PFRT and matched it on-chain`);
+    });
+
+    it("🎟️ Should enforce per-wallet rolling volume quotas by tier", async () => {
+      console.log("🎟️ Testing per-wallet volume quotas...");
+
+      const trader = Keypair.generate();
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(trader.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+      );
+      const traderPfrtAccount = await pfrtToken.createAccount(trader.publicKey);
+      const traderNfrtAccount = await nfrtToken.createAccount(trader.publicKey);
+      await pfrtToken.mintTo(traderPfrtAccount, authority, [], 1000 * 1e6);
+
+      const [traderTradeState] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), trader.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+      const adminAccounts = {
+        factory: ammFactory,
+        state: ammState,
+        governance: authority.publicKey,
+      };
+      const swapAccounts = {
+        state: ammState,
+        userTradeState: traderTradeState,
+        observations: ammObservations,
+        authority: ammAuthority,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        pfrtVault,
+        nfrtVault,
+        userPfrtAccount: traderPfrtAccount,
+        userNfrtAccount: traderNfrtAccount,
+        user: trader.publicKey,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        feeOracle: null,
+        marketMaker: null,
+      };
+      const quota = (tokens: number) => new anchor.BN(tokens * 1e6);
+
+      // 300 tokens a day by default, 2k for tier 1
+      await ammProgram.methods
+        .configureVolumeQuotas([quota(300), quota(2000), quota(0), quota(0)])
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      // Quotas live in the user trade account, so it can't be left out
+      try {
+        await ammProgram.methods
+          .swap(quota(200), new anchor.BN(0), true)
+          .accounts({ ...swapAccounts, userTradeState: null })
+          .signers([trader])
+          .rpc();
+        assert.fail("Swap without a user trade account should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "UserTradeStateRequired");
+      }
+
+      await ammProgram.methods
+        .swap(quota(200), new anchor.BN(0), true)
+        .accounts(swapAccounts)
+        .signers([trader])
+        .rpc();
+
+      try {
+        await ammProgram.methods
+          .swap(quota(200), new anchor.BN(0), true)
+          .accounts(swapAccounts)
+          .signers([trader])
+          .rpc();
+        assert.fail("Swap beyond the wallet's quota should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "VolumeQuotaExceeded");
+        assert.ok(error.logs.some((log: string) => log.includes("Remaining daily volume quota: 100000000")));
+        console.log("✅ Quota enforced with 100 tokens of allowance remaining");
+      }
+
+      // Tier 1 raises the same wallet's limit
+      await ammProgram.methods
+        .setUserTier(1)
+        .accounts({
+          ...adminAccounts,
+          user: trader.publicKey,
+          userTradeState: traderTradeState,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      await ammProgram.methods
+        .swap(quota(200), new anchor.BN(0), true)
+        .accounts(swapAccounts)
+        .signers([trader])
+        .rpc();
+
+      const tradeState = await ammProgram.account.userTradeState.fetch(traderTradeState);
+      assert.equal(tradeState.tier, 1);
+      assert.ok(tradeState.quotaWindowVolume.eq(quota(400)));
+      console.log("✅ Tier 1 wallet traded past the default quota");
+
+      await ammProgram.methods
+        .configureVolumeQuotas([quota(0), quota(0), quota(0), quota(0)])
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+    });
+
+    it("⏳ Should stream long-term orders into the pool", async () => {
+      console.log("⏳ Testing TWAMM long-term orders...");
+
+      const accountFor = async (mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(user1.publicKey, { mint })).value[0].pubkey;
+      const user1PfrtAccount = await accountFor(pfrtMint);
+      const user1NfrtAccount = await accountFor(nfrtMint);
+
+      const [twamm] = await PublicKey.findProgramAddress(
+        [Buffer.from("twamm"), ammState.toBuffer()],
+        ammProgram.programId
+      );
+      const orderId = new anchor.BN(0);
+      const [order] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from("long_term_order"),
+          ammState.toBuffer(),
+          user1.publicKey.toBuffer(),
+          orderId.toArrayLike(Buffer, "le", 8),
+        ],
+        ammProgram.programId
+      );
+
+      await ammProgram.methods
+        .initializeTwamm()
+        .accounts({
+          state: ammState,
+          twamm,
+          payer: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+      // Sell 1k PFRT until the end of the second hourly interval
+      await ammProgram.methods
+        .placeLongTermOrder(orderId, new anchor.BN(1000 * 1e6), new anchor.BN(2), true)
+        .accounts({
+          state: ammState,
+          pfrtMint: pfrtMint,
+          nfrtMint: nfrtMint,
+          observations: ammObservations,
+          twamm,
+          order,
+          pfrtVault,
+          nfrtVault,
+          userPfrtAccount: user1PfrtAccount,
+          userNfrtAccount: user1NfrtAccount,
+          user: user1.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      const placed = await ammProgram.account.longTermOrder.fetch(order);
+      assert.ok(placed.sellRate.gtn(0));
+      assert.ok(placed.expiry.sub(placed.startTime).gten(3600));
+
+      // Swaps must settle the stream first
+      try {
+        await ammProgram.methods
+          .swap(new anchor.BN(10 * 1e6), new anchor.BN(0), true)
+          .accounts({
+            state: ammState,
+            userTradeState: null,
+            observations: ammObservations,
+            authority: ammAuthority,
+            pfrtMint: pfrtMint,
+            nfrtMint: nfrtMint,
+            pfrtVault,
+            nfrtVault,
+            userPfrtAccount: user1PfrtAccount,
+            userNfrtAccount: user1NfrtAccount,
+            user: user1.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            feeOracle: null,
+            marketMaker: null,
+            twamm: null,
+          })
+          .signers([user1])
+          .rpc();
+        assert.fail("Swap without the TWAMM account should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "TwammAccountRequired");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      // Quotes settle the stream on a copy, so they need the TWAMM account too
+      const quoteAccounts = {
+        state: ammState,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        feeOracle: null,
+        twamm: null,
+      };
+      try {
+        await ammProgram.methods
+          .quoteSwap(new anchor.BN(10 * 1e6), true)
+          .accounts(quoteAccounts)
+          .view();
+        assert.fail("Quote without the TWAMM account should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "TwammAccountRequired");
+      }
+      const unsettled = await ammProgram.account.ammState.fetch(ammState);
+      const settledQuote = await ammProgram.methods
+        .quoteSwap(new anchor.BN(10 * 1e6), true)
+        .accounts({ ...quoteAccounts, twamm })
+        .view();
+      assert.ok(
+        settledQuote.pfrtBalanceAfter.gt(unsettled.pfrtBalance.add(new anchor.BN(10 * 1e6)))
+      );
+
+      const orderAccounts = {
+        state: ammState,
+        authority: ammAuthority,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        observations: ammObservations,
+        twamm,
+        order,
+        pfrtVault,
+        nfrtVault,
+        ownerPfrtAccount: user1PfrtAccount,
+        ownerNfrtAccount: user1NfrtAccount,
+        user: user1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      const nfrtBefore = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      await ammProgram.methods
+        .withdrawLongTermOrder()
+        .accounts(orderAccounts)
+        .signers([user1])
+        .rpc();
+      const nfrtAfter = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      const proceeds = nfrtAfter.amount.sub(nfrtBefore.amount);
+      assert.ok(proceeds.gtn(0));
+      console.log(`✅ Withdrew ${proceeds.toNumber()/1e6} NFRT streamed so far`);
+
+      // Cancelling refunds the unsold PFRT and closes the order
+      const pfrtBefore = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      await ammProgram.methods
+        .cancelLongTermOrder()
+        .accounts({ ...orderAccounts, owner: user1.publicKey })
+        .signers([user1])
+        .rpc();
+      const pfrtAfter = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      const refund = pfrtAfter.amount.sub(pfrtBefore.amount);
+      assert.ok(refund.gt(new anchor.BN(900 * 1e6)) && refund.lt(new anchor.BN(1000 * 1e6)));
+      assert.isNull(await connection.getAccountInfo(order));
+
+      const pool = await ammProgram.account.ammState.fetch(ammState);
+      assert.equal(pool.openLongTermOrders.toNumber(), 0);
+      console.log(`✅ Cancelled with ${refund.toNumber()/1e6} PFRT refunded`);
+    });
+
+    it("⏳ Should refuse long-term orders a shallow logit curve can't absorb", async () => {
+      console.log("⏳ Placing an oversized long-term order on a logit pool...");
+
+      const epochPfrt = await Token.createMint(connection, authority, authority.publicKey, null, 6, TOKEN_PROGRAM_ID);
+      const epochNfrt = await Token.createMint(connection, authority, authority.publicKey, null, 6, TOKEN_PROGRAM_ID);
+
+      const pda = async (seeds: Buffer[]) =>
+        (await PublicKey.findProgramAddress(seeds, ammProgram.programId))[0];
+      const pool = await pda([Buffer.from("pool"), epochPfrt.publicKey.toBuffer(), epochNfrt.publicKey.toBuffer()]);
+      const poolAccounts = {
+        state: pool,
+        authority: await pda([Buffer.from("authority"), pool.toBuffer()]),
+        pfrtMint: epochPfrt.publicKey,
+        nfrtMint: epochNfrt.publicKey,
+        pfrtVault: await pda([Buffer.from("pfrt_vault"), pool.toBuffer()]),
+        nfrtVault: await pda([Buffer.from("nfrt_vault"), pool.toBuffer()]),
+        lpMint: await pda([Buffer.from("lp_mint"), pool.toBuffer()]),
+        observations: await pda([Buffer.from("observations"), pool.toBuffer()]),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+
+      await ammProgram.methods
+        .createPool(
+          new anchor.BN(2),
+          30,
+          new anchor.BN(10000 * 1e6),
+          new anchor.BN(100000 * 1e6),
+          500,
+          { logit: { depthBps: 1000 } } // b = 10% of deposited reserves
+        )
+        .accounts({
+          ...poolAccounts,
+          factory: ammFactory,
+          poolListing: await pda([Buffer.from("pool_listing"), new anchor.BN(2).toArrayLike(Buffer, "le", 8)]),
+          governance: authority.publicKey,
+          user: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([authority])
+        .rpc();
+
+      const ownerPfrt = await epochPfrt.createAccount(authority.publicKey);
+      const ownerNfrt = await epochNfrt.createAccount(authority.publicKey);
+      const lpShares = await new Token(connection, poolAccounts.lpMint, TOKEN_PROGRAM_ID, authority)
+        .createAccount(authority.publicKey);
+      await epochPfrt.mintTo(ownerPfrt, authority, [], 400000 * 1e6);
+      await epochNfrt.mintTo(ownerNfrt, authority, [], 50000 * 1e6);
+
+      await ammProgram.methods
+        .addLiquidity(new anchor.BN(50000 * 1e6), new anchor.BN(50000 * 1e6), new anchor.BN(0))
+        .accounts({
+          ...poolAccounts,
+          userPfrtAccount: ownerPfrt,
+          userNfrtAccount: ownerNfrt,
+          userLpAccount: lpShares,
+          user: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      const twamm = await pda([Buffer.from("twamm"), pool.toBuffer()]);
+      await ammProgram.methods
+        .initializeTwamm()
+        .accounts({
+          state: pool,
+          twamm,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc();
+
+      const placeOrder = async (orderId: anchor.BN, amount: anchor.BN) => {
+        const order = await pda([
+          Buffer.from("long_term_order"),
+          pool.toBuffer(),
+          authority.publicKey.toBuffer(),
+          orderId.toArrayLike(Buffer, "le", 8),
+        ]);
+        await ammProgram.methods
+          .placeLongTermOrder(orderId, amount, new anchor.BN(1), true)
+          .accounts({
+            state: pool,
+            pfrtMint: epochPfrt.publicKey,
+            nfrtMint: epochNfrt.publicKey,
+            observations: poolAccounts.observations,
+            twamm,
+            order,
+            pfrtVault: poolAccounts.pfrtVault,
+            nfrtVault: poolAccounts.nfrtVault,
+            userPfrtAccount: ownerPfrt,
+            userNfrtAccount: ownerNfrt,
+            user: authority.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([authority])
+          .rpc();
+        return order;
+      };
+
+      // Selling 300k PFRT within the hour would push (nfrt - pfrt) / b
+      // well past the curve's range and leave the pool unpriceable
+      try {
+        await placeOrder(new anchor.BN(0), new anchor.BN(300000 * 1e6));
+        assert.fail("Oversized long-term order should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "LongTermOrderTooLarge");
+      }
+
+      const pool0 = await ammProgram.account.ammState.fetch(pool);
+      assert.equal(pool0.openLongTermOrders.toNumber(), 0);
+
+      // An order the curve can absorb is still accepted
+      await placeOrder(new anchor.BN(1), new anchor.BN(1000 * 1e6));
+      const pool1 = await ammProgram.account.ammState.fetch(pool);
+      assert.equal(pool1.openLongTermOrders.toNumber(), 1);
+
+      console.log("✅ Oversized long-term order rejected, pool still trading");
+    });
+
+    it("🎯 Should escrow limit orders and let keepers fill them", async () => {
+      console.log("🎯 Testing limit orders...");
+
+      const accountFor = async (owner: PublicKey, mint: PublicKey) =>
+        (await connection.getTokenAccountsByOwner(owner, { mint })).value[0].pubkey;
+      const user1PfrtAccount = await accountFor(user1.publicKey, pfrtMint);
+      const user1NfrtAccount = await accountFor(user1.publicKey, nfrtMint);
+      const keeperPfrtAccount = await accountFor(user2.publicKey, pfrtMint);
+      const [ownerTradeState] = await PublicKey.findProgramAddress(
+        [Buffer.from("user_trade"), ammState.toBuffer(), user1.publicKey.toBuffer()],
+        ammProgram.programId
+      );
+
+      const orderAddresses = async (orderId: anchor.BN) => {
+        const [order] = await PublicKey.findProgramAddress(
+          [
+            Buffer.from("limit_order"),
+            ammState.toBuffer(),
+            user1.publicKey.toBuffer(),
+            orderId.toArrayLike(Buffer, "le", 8),
+          ],
+          ammProgram.programId
+        );
+        const [escrow] = await PublicKey.findProgramAddress(
+          [Buffer.from("limit_order_escrow"), order.toBuffer()],
+          ammProgram.programId
+        );
+        return { order, escrow };
+      };
+      const place = async (orderId: anchor.BN, limitPrice: number) => {
+        const { order, escrow } = await orderAddresses(orderId);
+        await ammProgram.methods
+          .placeLimitOrder(orderId, new anchor.BN(100 * 1e6), new anchor.BN(limitPrice), new anchor.BN(0))
+          .accounts({
+            state: ammState,
+            authority: ammAuthority,
+            inputMint: nfrtMint,
+            order,
+            escrow,
+            userInputAccount: user1NfrtAccount,
+            userTradeState: ownerTradeState,
+            user: user1.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        return { order, escrow };
+      };
+      const fillAccounts = (order: PublicKey, escrow: PublicKey) => ({
+        state: ammState,
+        authority: ammAuthority,
+        pfrtMint: pfrtMint,
+        nfrtMint: nfrtMint,
+        observations: ammObservations,
+        order,
+        owner: user1.publicKey,
+        escrow,
+        pfrtVault,
+        nfrtVault,
+        ownerOutputAccount: user1PfrtAccount,
+        ownerTradeState,
+        keeperOutputAccount: keeperPfrtAccount,
+        keeper: user2.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        feeOracle: null,
+        twamm: null,
+      });
+
+      // Asking 2 PFRT per NFRT is well above the pool price
+      const nfrtBefore = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      const high = await place(new anchor.BN(0), 2 * 1e6);
+      try {
+        await ammProgram.methods
+          .fillLimitOrder()
+          .accounts(fillAccounts(high.order, high.escrow))
+          .signers([user2])
+          .rpc();
+        assert.fail("Fill below the limit price should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "LimitPriceNotReached");
+      }
+
+      await ammProgram.methods
+        .cancelLimitOrder()
+        .accounts({
+          state: ammState,
+          authority: ammAuthority,
+          inputMint: nfrtMint,
+          order: high.order,
+          owner: user1.publicKey,
+          escrow: high.escrow,
+          ownerInputAccount: user1NfrtAccount,
+          user: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+      const nfrtRefunded = await nfrtToken.getAccountInfo(user1NfrtAccount);
+      assert.ok(nfrtRefunded.amount.eq(nfrtBefore.amount));
+      assert.isNull(await connection.getAccountInfo(high.escrow));
+      console.log("✅ Unreachable order refused and cancelled");
+
+      // 0.9 PFRT per NFRT is already crossed, so any keeper can fill
+      const low = await place(new anchor.BN(1), 0.9 * 1e6);
+
+      // Fills count against the owner's volume quota, not the keeper's
+      const quota = (tokens: number) => new anchor.BN(tokens * 1e6);
+      const adminAccounts = {
+        factory: ammFactory,
+        state: ammState,
+        governance: authority.publicKey,
+      };
+      await ammProgram.methods
+        .configureVolumeQuotas([quota(50), quota(50), quota(0), quota(0)])
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+      try {
+        await ammProgram.methods
+          .fillLimitOrder()
+          .accounts({ ...fillAccounts(low.order, low.escrow), ownerTradeState: null })
+          .signers([user2])
+          .rpc();
+        assert.fail("Fill without the owner's trade account should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "UserTradeStateRequired");
+      }
+      try {
+        await ammProgram.methods
+          .fillLimitOrder()
+          .accounts(fillAccounts(low.order, low.escrow))
+          .signers([user2])
+          .rpc();
+        assert.fail("Fill beyond the owner's quota should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "VolumeQuotaExceeded");
+      }
+      await ammProgram.methods
+        .configureVolumeQuotas([quota(0), quota(0), quota(0), quota(0)])
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      // Fills respect the pool's trade size cap like swaps do, even when it
+      // was lowered after the order was placed
+      const { maxTradeSize } = await ammProgram.account.ammState.fetch(ammState);
+      await ammProgram.methods
+        .updateParameters(quota(50), null, null, null, null, null)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+      try {
+        await ammProgram.methods
+          .fillLimitOrder()
+          .accounts(fillAccounts(low.order, low.escrow))
+          .signers([user2])
+          .rpc();
+        assert.fail("Fill above the trade size cap should have failed");
+      } catch (error) {
+        assert.include(error.toString(), "TradeSizeTooLarge");
+      }
+      await ammProgram.methods
+        .updateParameters(maxTradeSize, null, null, null, null, null)
+        .accounts(adminAccounts)
+        .signers([authority])
+        .rpc();
+
+      const pfrtBefore = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      const keeperBefore = await pfrtToken.getAccountInfo(keeperPfrtAccount);
+
+      await ammProgram.methods
+        .fillLimitOrder()
+        .accounts(fillAccounts(low.order, low.escrow))
+        .signers([user2])
+        .rpc();
+
+      const pfrtAfter = await pfrtToken.getAccountInfo(user1PfrtAccount);
+      const keeperAfter = await pfrtToken.getAccountInfo(keeperPfrtAccount);
+      const received = pfrtAfter.amount.sub(pfrtBefore.amount);
+      const keeperFee = keeperAfter.amount.sub(keeperBefore.amount);
+      assert.ok(received.gte(new anchor.BN(90 * 1e6)));
+      assert.ok(keeperFee.gtn(0) && keeperFee.lt(received.divn(100)));
+      assert.isNull(await connection.getAccountInfo(low.order));
+
+      console.log(`✅ Keeper filled for ${received.toNumber()/1e6} PFRT, earning ${keeperFee.toNumber()/1e6}`);
+    });
   });
 
   describe("🚨 Emergency Response System", () => {